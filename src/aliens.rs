@@ -9,26 +9,33 @@
  *
  */
 
-extern crate glfw;
-extern crate kiss3d;
-extern crate nalgebra as na;
 extern crate rand;
 
-use std::time::Instant;
 use rand::Rng;
-use na::{ Vector3, Translation3, UnitQuaternion };
-use kiss3d::window::Window;
-use kiss3d::scene::SceneNode;
 
 use super::bullet;
 use super::collision;
+use super::difficulty::{ Difficulty, March };
+use super::formation::Formation;
+use super::prng::Prng;
+use super::save;
+use super::game::TICKS_PER_SECOND;
 
 const ALIEN_HEIGHT: f32     = 10.0; /* in 3d units */
 const ALIEN_WIDTH: f32      = 13.0; /* in 3d units */
-const ALIENS_PER_ROW: i32   = 11;
-const ALIEN_ROWS: i32       = 5;
-const ALIEN_TOP_Y: i32      = 9;    /* in whole number of aliens from game world center */
+pub const ALIENS_PER_ROW: i32 = 11;
+pub const ALIEN_ROWS: i32     = 5;
+pub const ALIEN_TOP_Y: i32  = 9;    /* in whole number of aliens from game world center */
 const ALIEN_SIDE_SPACE: i32 = 3;    /* space either side (in nr of aliens) of alien pattern */
+const ALIEN_SPIN: f32       = 0.018; /* y-axis rotation per tick of an alive alien */
+
+const ALIEN_FRAME_TICKS: u32 = 2 * TICKS_PER_SECOND;  /* time between animation frames */
+pub const ALIEN_DEATH_TICKS: u32 = 11 * TICKS_PER_SECOND; /* time spent exploding before dead */
+
+/* side walls the squadron bounces between, which also hem in the heroes' ships. screen
+ * left is the positive x direction */
+pub const WALL_LEFT: f32 = ((ALIENS_PER_ROW / 2) + 1 + ALIEN_SIDE_SPACE) as f32 * ALIEN_WIDTH;
+pub const WALL_RIGHT: f32 = ((ALIENS_PER_ROW / 2) + ALIEN_SIDE_SPACE) as f32 * (0.0 - ALIEN_WIDTH);
 
 pub const ALIEN_POINTS: i32 = 100;  /* number of points per alien */
 pub const ALIEN_Y_CEILING: f32 = (ALIEN_TOP_Y as f32) * ALIEN_HEIGHT;
@@ -37,28 +44,217 @@ const BOMB_RADIUS:  f32 = 4.0;
 const BOMB_COLOR_R: f32 = 0.0;
 const BOMB_COLOR_G: f32 = 1.0;
 const BOMB_COLOR_B: f32 = 0.0;
-const BOMB_DESCENT: f32 = -1.0;
+
+const BURST_SPEED: f32 = 2.5;   /* squadron speed multiplier during a speed burst */
+const BURST_TICKS: u32 = 2 * TICKS_PER_SECOND;           /* how long a speed burst lasts */
+const BURST_RECHARGE_TICKS: u32 = 10 * TICKS_PER_SECOND; /* minimum time from the start of one burst to the next */
 
 /* aliens are made up of a number of pixels */
-struct Pixel
+#[derive(Clone, Copy)]
+pub struct Pixel
 {
   /* dimensions of this pixel */
-  width: f32, height: f32, depth: f32,
+  pub width: f32, pub height: f32, pub depth: f32,
 
   /* the pixel's x, y, z base coords and a translation to move the pixel into
      another position. this allows the pixel to be animated by switching between
      its base and translated positions. yes, this could be point structs... soon */
-  x:  f32, y:  f32, z:  f32,
-  tx: f32, ty: f32, tz: f32,
+  pub x:  f32, pub y:  f32, pub z:  f32,
+  pub tx: f32, pub ty: f32, pub tz: f32,
+
+  pub r:  f32, pub g:  f32, pub b:  f32 /* color of the pixel */
+}
+
+/* the kinds of alien a formation can be made up of */
+#[derive(Clone, Copy, PartialEq)]
+pub enum Species
+{
+  Squid,   /* small and narrow, usually on the top row */
+  Crab,    /* the middle rows, and every alien before there was a choice */
+  Octopus  /* big and wide, usually on the bottom rows */
+}
+
+impl Species
+{
+  /* returns the name of the species to show the player */
+  pub fn title(&self) -> &'static str
+  {
+    match self
+    {
+      Species::Squid => "Squid",
+      Species::Crab => "Crab",
+      Species::Octopus => "Octopus"
+    }
+  }
+
+  /* returns the letter standing for the species in formation files and saved games */
+  pub fn letter(&self) -> char
+  {
+    match self
+    {
+      Species::Squid => 'S',
+      Species::Crab => 'C',
+      Species::Octopus => 'O'
+    }
+  }
+
+  /* returns the species a letter stands for, or None if it doesn't stand for one */
+  pub fn from_letter(letter: char) -> Option<Species>
+  {
+    match letter
+    {
+      'S' => Some(Species::Squid),
+      'C' => Some(Species::Crab),
+      'O' => Some(Species::Octopus),
+      _ => None
+    }
+  }
+}
+
+/* the species in the order their designs are numbered, eg: for baking into meshes */
+pub const SPECIES: [Species; 3] = [ Species::Squid, Species::Crab, Species::Octopus ];
+
+/* return the pixels making up an alien of the given species, relative to the alien's center */
+pub fn design(species: Species) -> Vec<Pixel>
+{
+  match species
+  {
+    Species::Squid => squid_design(),
+    Species::Crab => crab_design(),
+    Species::Octopus => octopus_design()
+  }
+}
+
+/* the squid is 8 pixels wide. its tentacles wriggle in and out between frames:
+
+        **
+       ****
+      ******
+     ** ** **
+     ********
+       *  *
+      * ** *
+     * *  * *     */
+fn squid_design() -> Vec<Pixel>
+{
+  vec!
+  [
+    Pixel { width:   2.0, height: 1.0, depth: 1.0, x:   0.0, y:   4.0, z: 0.0, tx:   0.0, ty:   0.0, tz: 0.0, r: 1.0, g: 0.3, b: 1.0 },
+
+    Pixel { width:   4.0, height: 1.0, depth: 1.0, x:   0.0, y:   3.0, z: 0.0, tx:   0.0, ty:   0.0, tz: 0.0, r: 1.0, g: 0.3, b: 1.0 },
+
+    Pixel { width:   6.0, height: 1.0, depth: 1.0, x:   0.0, y:   2.0, z: 0.0, tx:   0.0, ty:   0.0, tz: 0.0, r: 1.0, g: 0.3, b: 1.0 },
+
+    Pixel { width:   2.0, height: 1.0, depth: 1.0, x:  -3.0, y:   1.0, z: 0.0, tx:   0.0, ty:   0.0, tz: 0.0, r: 1.0, g: 0.3, b: 1.0 },
+    Pixel { width:   2.0, height: 1.0, depth: 1.0, x:   0.0, y:   1.0, z: 0.0, tx:   0.0, ty:   0.0, tz: 0.0, r: 1.0, g: 0.3, b: 1.0 },
+    Pixel { width:   2.0, height: 1.0, depth: 1.0, x:   3.0, y:   1.0, z: 0.0, tx:   0.0, ty:   0.0, tz: 0.0, r: 1.0, g: 0.3, b: 1.0 },
+
+    Pixel { width:   8.0, height: 1.0, depth: 1.0, x:   0.0, y:   0.0, z: 0.0, tx:   0.0, ty:   0.0, tz: 0.0, r: 1.0, g: 0.3, b: 1.0 },
+
+    Pixel { width:   1.0, height: 1.0, depth: 1.0, x:  -1.5, y:  -1.0, z: 0.0, tx:  -1.0, ty:   0.0, tz: 0.0, r: 1.0, g: 0.3, b: 1.0 },
+    Pixel { width:   1.0, height: 1.0, depth: 1.0, x:   1.5, y:  -1.0, z: 0.0, tx:   1.0, ty:   0.0, tz: 0.0, r: 1.0, g: 0.3, b: 1.0 },
+
+    Pixel { width:   1.0, height: 1.0, depth: 1.0, x:  -2.5, y:  -2.0, z: 0.0, tx:  -1.0, ty:   0.0, tz: 0.0, r: 1.0, g: 0.3, b: 1.0 },
+    Pixel { width:   2.0, height: 1.0, depth: 1.0, x:   0.0, y:  -2.0, z: 0.0, tx:   0.0, ty:   0.0, tz: 0.0, r: 1.0, g: 0.3, b: 1.0 },
+    Pixel { width:   1.0, height: 1.0, depth: 1.0, x:   2.5, y:  -2.0, z: 0.0, tx:   1.0, ty:   0.0, tz: 0.0, r: 1.0, g: 0.3, b: 1.0 },
+
+    Pixel { width:   1.0, height: 1.0, depth: 1.0, x:  -3.5, y:  -3.0, z: 0.0, tx:   1.0, ty:   0.0, tz: 0.0, r: 1.0, g: 0.3, b: 1.0 },
+    Pixel { width:   1.0, height: 1.0, depth: 1.0, x:  -1.5, y:  -3.0, z: 0.0, tx:  -1.0, ty:   0.0, tz: 0.0, r: 1.0, g: 0.3, b: 1.0 },
+    Pixel { width:   1.0, height: 1.0, depth: 1.0, x:   1.5, y:  -3.0, z: 0.0, tx:   1.0, ty:   0.0, tz: 0.0, r: 1.0, g: 0.3, b: 1.0 },
+    Pixel { width:   1.0, height: 1.0, depth: 1.0, x:   3.5, y:  -3.0, z: 0.0, tx:  -1.0, ty:   0.0, tz: 0.0, r: 1.0, g: 0.3, b: 1.0 }
+  ]
+}
 
-  r:  f32, g:  f32, b:  f32, /* color of the pixel */
-  explode_x: f32, explode_y: f32, explode_z: f32, /* vector describing the pixel's explosion trajectory */
-  node: Option<SceneNode> /* this pixel's scene node */
+/* the octopus is 12 pixels wide. its feet tuck in between frames:
+
+         ****
+      **********
+     ************
+     ***  **  ***
+     ************
+        **  **
+       ** ** **
+     **        **     */
+fn octopus_design() -> Vec<Pixel>
+{
+  vec!
+  [
+    Pixel { width:   4.0, height: 1.0, depth: 1.0, x:   0.0, y:   4.0, z: 0.0, tx:   0.0, ty:   0.0, tz: 0.0, r: 0.3, g: 0.8, b: 1.0 },
+
+    Pixel { width:  10.0, height: 1.0, depth: 1.0, x:   0.0, y:   3.0, z: 0.0, tx:   0.0, ty:   0.0, tz: 0.0, r: 0.3, g: 0.8, b: 1.0 },
+
+    Pixel { width:  12.0, height: 1.0, depth: 1.0, x:   0.0, y:   2.0, z: 0.0, tx:   0.0, ty:   0.0, tz: 0.0, r: 0.3, g: 0.8, b: 1.0 },
+
+    Pixel { width:   3.0, height: 1.0, depth: 1.0, x:  -4.5, y:   1.0, z: 0.0, tx:   0.0, ty:   0.0, tz: 0.0, r: 0.3, g: 0.8, b: 1.0 },
+    Pixel { width:   2.0, height: 1.0, depth: 1.0, x:   0.0, y:   1.0, z: 0.0, tx:   0.0, ty:   0.0, tz: 0.0, r: 0.3, g: 0.8, b: 1.0 },
+    Pixel { width:   3.0, height: 1.0, depth: 1.0, x:   4.5, y:   1.0, z: 0.0, tx:   0.0, ty:   0.0, tz: 0.0, r: 0.3, g: 0.8, b: 1.0 },
+
+    Pixel { width:  12.0, height: 1.0, depth: 1.0, x:   0.0, y:   0.0, z: 0.0, tx:   0.0, ty:   0.0, tz: 0.0, r: 0.3, g: 0.8, b: 1.0 },
+
+    Pixel { width:   2.0, height: 1.0, depth: 1.0, x:  -2.0, y:  -1.0, z: 0.0, tx:   0.0, ty:   0.0, tz: 0.0, r: 0.3, g: 0.8, b: 1.0 },
+    Pixel { width:   2.0, height: 1.0, depth: 1.0, x:   2.0, y:  -1.0, z: 0.0, tx:   0.0, ty:   0.0, tz: 0.0, r: 0.3, g: 0.8, b: 1.0 },
+
+    Pixel { width:   2.0, height: 1.0, depth: 1.0, x:  -3.0, y:  -2.0, z: 0.0, tx:   0.0, ty:   0.0, tz: 0.0, r: 0.3, g: 0.8, b: 1.0 },
+    Pixel { width:   2.0, height: 1.0, depth: 1.0, x:   0.0, y:  -2.0, z: 0.0, tx:   0.0, ty:   0.0, tz: 0.0, r: 0.3, g: 0.8, b: 1.0 },
+    Pixel { width:   2.0, height: 1.0, depth: 1.0, x:   3.0, y:  -2.0, z: 0.0, tx:   0.0, ty:   0.0, tz: 0.0, r: 0.3, g: 0.8, b: 1.0 },
+
+    Pixel { width:   2.0, height: 1.0, depth: 1.0, x:  -5.0, y:  -3.0, z: 0.0, tx:   2.0, ty:   0.0, tz: 0.0, r: 0.3, g: 0.8, b: 1.0 },
+    Pixel { width:   2.0, height: 1.0, depth: 1.0, x:   5.0, y:  -3.0, z: 0.0, tx:  -2.0, ty:   0.0, tz: 0.0, r: 0.3, g: 0.8, b: 1.0 }
+  ]
+}
+
+/* the crab is 11 pixels wide */
+fn crab_design() -> Vec<Pixel>
+{
+  /* describe the alien in blocks of pixels. could load this in as a model but
+   * I want to animate this programmatically and I just want to draw something
+   * to the screen. i've included the pixels going from left to right, top to
+   * bottom, grouping horizontal lines into bars, and leaving individual pixels
+   * as is. the overall design is:
+
+       *     *
+        *   *
+       *******
+      ** *** **
+     ***********
+     * ******* *
+     * *     * *
+        ** **       */
+
+
+  vec!
+  [
+    Pixel { width:  1.0, height: 1.0, depth: 1.0, x: -3.0, y:  4.0, z: 0.0, tx:  1.0, ty: 0.0, tz: 0.0, r: 0.2, g: 1.0, b: 0.2 },
+    Pixel { width:  1.0, height: 1.0, depth: 1.0, x:  3.0, y:  4.0, z: 0.0, tx: -1.0, ty: 0.0, tz: 0.0, r: 0.2, g: 1.0, b: 0.2 },
+
+    Pixel { width:  1.0, height: 1.0, depth: 1.0, x: -2.0, y:  3.0, z: 0.0, tx:  0.0, ty: 0.0, tz: 0.0, r: 0.2, g: 1.0, b: 0.2 },
+    Pixel { width:  1.0, height: 1.0, depth: 1.0, x:  2.0, y:  3.0, z: 0.0, tx:  0.0, ty: 0.0, tz: 0.0, r: 0.2, g: 1.0, b: 0.2 },
+
+    Pixel { width:  7.0, height: 1.0, depth: 1.0, x:  0.0, y:  2.0, z: 0.0, tx:  0.0, ty: 0.0, tz: 0.0, r: 0.2, g: 1.0, b: 0.2 },
+
+    Pixel { width:  2.0, height: 1.0, depth: 1.0, x: -4.0, y:  1.0, z: 0.0, tx:  0.0, ty: 0.0, tz: 0.0, r: 0.2, g: 1.0, b: 0.2 },
+    Pixel { width:  3.0, height: 1.0, depth: 1.0, x:  0.0, y:  1.0, z: 0.0, tx:  0.0, ty: 0.0, tz: 0.0, r: 0.2, g: 1.0, b: 0.2 },
+    Pixel { width:  2.0, height: 1.0, depth: 1.0, x:  4.0, y:  1.0, z: 0.0, tx:  0.0, ty: 0.0, tz: 0.0, r: 0.2, g: 1.0, b: 0.2 },
+
+    Pixel { width: 11.0, height: 1.0, depth: 1.0, x:  0.0, y:  0.0, z: 0.0, tx:  0.0, ty: 0.0, tz: 0.0, r: 0.2, g: 1.0, b: 0.2 },
+
+    Pixel { width:  1.0, height: 1.0, depth: 1.0, x:  5.0, y: -1.0, z: 0.0, tx:  0.0, ty: 3.0, tz: 0.0, r: 0.2, g: 1.0, b: 0.2 },
+    Pixel { width:  7.0, height: 1.0, depth: 1.0, x:  0.0, y: -1.0, z: 0.0, tx:  0.0, ty: 0.0, tz: 0.0, r: 0.2, g: 1.0, b: 0.2 },
+    Pixel { width:  1.0, height: 1.0, depth: 1.0, x: -5.0, y: -1.0, z: 0.0, tx:  0.0, ty: 3.0, tz: 0.0, r: 0.2, g: 1.0, b: 0.2 },
+
+    Pixel { width:  1.0, height: 1.0, depth: 1.0, x: -5.0, y: -2.0, z: 0.0, tx:  0.0, ty: 3.0, tz: 0.0, r: 0.2, g: 1.0, b: 0.2 },
+    Pixel { width:  1.0, height: 1.0, depth: 1.0, x: -3.0, y: -2.0, z: 0.0, tx:  0.0, ty: 0.0, tz: 0.0, r: 0.2, g: 1.0, b: 0.2 },
+    Pixel { width:  1.0, height: 1.0, depth: 1.0, x:  3.0, y: -2.0, z: 0.0, tx:  0.0, ty: 0.0, tz: 0.0, r: 0.2, g: 1.0, b: 0.2 },
+    Pixel { width:  1.0, height: 1.0, depth: 1.0, x:  5.0, y: -2.0, z: 0.0, tx:  0.0, ty: 3.0, tz: 0.0, r: 0.2, g: 1.0, b: 0.2 },
+
+    Pixel { width:  2.0, height: 1.0, depth: 1.0, x: -1.5, y: -3.0, z: 0.0, tx: -2.0, ty: 0.0, tz: 0.0, r: 0.2, g: 1.0, b: 0.2 },
+    Pixel { width:  2.0, height: 1.0, depth: 1.0, x:  1.5, y: -3.0, z: 0.0, tx:  2.0, ty: 0.0, tz: 0.0, r: 0.2, g: 1.0, b: 0.2 }
+  ]
 }
 
 /* aliens have 3 game states: alive, exploding, or dead */
-#[derive(PartialEq)]
-enum State
+#[derive(Clone, Copy, PartialEq)]
+pub enum State
 {
   Alive,
   Dying,
@@ -66,6 +262,7 @@ enum State
 }
 
 /* aliens are either shuffling left, right, or down and then right, or down then left */
+#[derive(Clone, Copy, PartialEq)]
 pub enum Movement
 {
   Left,         /* moving left */
@@ -75,96 +272,66 @@ pub enum Movement
 }
 
 /* aliens have 2 animation states: the base design and a slightly modified one */
-enum Frame
+#[derive(Clone, Copy, PartialEq)]
+pub enum Frame
 {
   Base,
   Translated
 }
 
+/* an alien is pure game state: what it looks like on screen is up to the renderer */
 pub struct Alien
 {
   x: f32, y: f32, z: f32,         /* center of the model on the playfield */
-  pixels: Vec<Pixel>,             /* the pixels making up this alien */
-  model: SceneNode,               /* the scene node holding all the pixels */
+  angle: f32,                     /* y-axis rotation of the model */
   frame: Frame,                   /* the type of animation frame being displayed */
   state: State,                   /* whether the alien is alive, dead, etc */
-  last_time: Instant,             /* last time we animated this alien */
-  time_of_death: Option<Instant>, /* when the alien was declared dead */
-  rng: rand::rngs::ThreadRng,     /* access to the thread's RNG */
+  frame_ticks: u32,               /* ticks since we last switched animation frame */
+  death_ticks: Option<u32>,       /* ticks since the alien was declared dead */
   drop_steps: f32,                /* number of units we've moved alien down at end of row */
-  movement: Movement              /* the direction the alien is traveling */
+  movement: Movement,             /* the direction the alien is traveling */
+  column: i32,                    /* column of the formation this alien was spawned in */
+  row: i32,                       /* row of the formation this alien was spawned in, 0 = bottom */
+  species: Species                /* what the alien looks like */
 }
 
 impl Alien
 {
   /* allocate and initialize a new alien onject */
-  pub fn new(window: &mut Window) -> Alien
+  pub fn new() -> Alien
   {
     Alien
     {
-      /* describe the alien in blocks of pixels. could load this in as a model but
-       * I want to animate this programmatically and I just want to draw something
-       * to the screen. i've included the pixels going from left to right, top to
-       * bottom, grouping horizontal lines into bars, and leaving individual pixels
-       * as is. the overall design is:
-
-           *     *
-            *   *
-           *******
-          ** *** **
-         ***********
-         * ******* *
-         * *     * *
-            ** **       */
-
-
-      pixels: vec!
-      [
-        Pixel { width:  1.0, height: 1.0, depth: 1.0, x: -3.0, y:  4.0, z: 0.0, tx:  1.0, ty: 0.0, tz: 0.0, r: 0.2, g: 1.0, b: 0.2, node: None, explode_x: 0.0, explode_y: 0.0, explode_z: 0.0 },
-        Pixel { width:  1.0, height: 1.0, depth: 1.0, x:  3.0, y:  4.0, z: 0.0, tx: -1.0, ty: 0.0, tz: 0.0, r: 0.2, g: 1.0, b: 0.2, node: None, explode_x: 0.0, explode_y: 0.0, explode_z: 0.0 },
-
-        Pixel { width:  1.0, height: 1.0, depth: 1.0, x: -2.0, y:  3.0, z: 0.0, tx:  0.0, ty: 0.0, tz: 0.0, r: 0.2, g: 1.0, b: 0.2, node: None, explode_x: 0.0, explode_y: 0.0, explode_z: 0.0 },
-        Pixel { width:  1.0, height: 1.0, depth: 1.0, x:  2.0, y:  3.0, z: 0.0, tx:  0.0, ty: 0.0, tz: 0.0, r: 0.2, g: 1.0, b: 0.2, node: None, explode_x: 0.0, explode_y: 0.0, explode_z: 0.0 },
-
-        Pixel { width:  7.0, height: 1.0, depth: 1.0, x:  0.0, y:  2.0, z: 0.0, tx:  0.0, ty: 0.0, tz: 0.0, r: 0.2, g: 1.0, b: 0.2, node: None, explode_x: 0.0, explode_y: 0.0, explode_z: 0.0 },
-
-        Pixel { width:  2.0, height: 1.0, depth: 1.0, x: -4.0, y:  1.0, z: 0.0, tx:  0.0, ty: 0.0, tz: 0.0, r: 0.2, g: 1.0, b: 0.2, node: None, explode_x: 0.0, explode_y: 0.0, explode_z: 0.0 },
-        Pixel { width:  3.0, height: 1.0, depth: 1.0, x:  0.0, y:  1.0, z: 0.0, tx:  0.0, ty: 0.0, tz: 0.0, r: 0.2, g: 1.0, b: 0.2, node: None, explode_x: 0.0, explode_y: 0.0, explode_z: 0.0 },
-        Pixel { width:  2.0, height: 1.0, depth: 1.0, x:  4.0, y:  1.0, z: 0.0, tx:  0.0, ty: 0.0, tz: 0.0, r: 0.2, g: 1.0, b: 0.2, node: None, explode_x: 0.0, explode_y: 0.0, explode_z: 0.0 },
-
-        Pixel { width: 11.0, height: 1.0, depth: 1.0, x:  0.0, y:  0.0, z: 0.0, tx:  0.0, ty: 0.0, tz: 0.0, r: 0.2, g: 1.0, b: 0.2, node: None, explode_x: 0.0, explode_y: 0.0, explode_z: 0.0 },
-
-        Pixel { width:  1.0, height: 1.0, depth: 1.0, x:  5.0, y: -1.0, z: 0.0, tx:  0.0, ty: 3.0, tz: 0.0, r: 0.2, g: 1.0, b: 0.2, node: None, explode_x: 0.0, explode_y: 0.0, explode_z: 0.0 },
-        Pixel { width:  7.0, height: 1.0, depth: 1.0, x:  0.0, y: -1.0, z: 0.0, tx:  0.0, ty: 0.0, tz: 0.0, r: 0.2, g: 1.0, b: 0.2, node: None, explode_x: 0.0, explode_y: 0.0, explode_z: 0.0 },
-        Pixel { width:  1.0, height: 1.0, depth: 1.0, x: -5.0, y: -1.0, z: 0.0, tx:  0.0, ty: 3.0, tz: 0.0, r: 0.2, g: 1.0, b: 0.2, node: None, explode_x: 0.0, explode_y: 0.0, explode_z: 0.0 },
-
-        Pixel { width:  1.0, height: 1.0, depth: 1.0, x: -5.0, y: -2.0, z: 0.0, tx:  0.0, ty: 3.0, tz: 0.0, r: 0.2, g: 1.0, b: 0.2, node: None, explode_x: 0.0, explode_y: 0.0, explode_z: 0.0 },
-        Pixel { width:  1.0, height: 1.0, depth: 1.0, x: -3.0, y: -2.0, z: 0.0, tx:  0.0, ty: 0.0, tz: 0.0, r: 0.2, g: 1.0, b: 0.2, node: None, explode_x: 0.0, explode_y: 0.0, explode_z: 0.0 },
-        Pixel { width:  1.0, height: 1.0, depth: 1.0, x:  3.0, y: -2.0, z: 0.0, tx:  0.0, ty: 0.0, tz: 0.0, r: 0.2, g: 1.0, b: 0.2, node: None, explode_x: 0.0, explode_y: 0.0, explode_z: 0.0 },
-        Pixel { width:  1.0, height: 1.0, depth: 1.0, x:  5.0, y: -2.0, z: 0.0, tx:  0.0, ty: 3.0, tz: 0.0, r: 0.2, g: 1.0, b: 0.2, node: None, explode_x: 0.0, explode_y: 0.0, explode_z: 0.0 },
-
-        Pixel { width:  2.0, height: 1.0, depth: 1.0, x: -1.5, y: -3.0, z: 0.0, tx: -2.0, ty: 0.0, tz: 0.0, r: 0.2, g: 1.0, b: 0.2, node: None, explode_x: 0.0, explode_y: 0.0, explode_z: 0.0 },
-        Pixel { width:  2.0, height: 1.0, depth: 1.0, x:  1.5, y: -3.0, z: 0.0, tx:  2.0, ty: 0.0, tz: 0.0, r: 0.2, g: 1.0, b: 0.2, node: None, explode_x: 0.0, explode_y: 0.0, explode_z: 0.0 }
-      ],
-
       x: 0.0, y: 0.0, z: 0.0, /* default position of alien model's center */
-
-      /* attach all the pixels together as a group */
-      model: window.add_group(),
+      angle: 0.0,
 
       /* start off in normal animation frame */
       frame: Frame::Base,
 
       state: State::Alive,
-      last_time: Instant::now(),
-      time_of_death: None,
-      rng: rand::thread_rng(),
+      frame_ticks: 0,
+      death_ticks: None,
       drop_steps: 0.0,
-      movement: Movement::Right
+      movement: Movement::Right,
+      column: 0,
+      row: 0,
+      species: Species::Crab
     }
   }
 
-  /* calling new() just initializes the alien. call spawn() to actually create it on screen
+  /* create an alien that dives straight down the playfield, rather than marching with the squadron
+   * => x, y = coords to start diving from
+   *    species = what the alien looks like */
+  pub fn diver(x: f32, y: f32, species: Species) -> Alien
+  {
+    let mut alien = Alien::new();
+    alien.spawn(x, y, 0.0, 0.0);
+    alien.species = species;
+    alien.movement = Movement::DownLeft; /* nothing turns a diver, so it never stops going down */
+    return alien;
+  }
+
+  /* calling new() just initializes the alien. call spawn() to place it on the playfield
    * => center_x, center_y, center_z = coords for the center of the alien model
    *    angle = y-axis rotation angle to apply to the alien */
   pub fn spawn(&mut self, center_x: f32, center_y: f32, center_z: f32, angle: f32)
@@ -172,29 +339,11 @@ impl Alien
     self.x = center_x;
     self.y = center_y;
     self.z = center_z;
-
-    /* spin through the array of pixels to create this monster */
-    for pixel in self.pixels.iter_mut()
-    {
-      /* create a cube pixel aka a scene node */
-      let mut p = self.model.add_cube(pixel.width, pixel.height, pixel.depth);
-
-      /* move pixel into position within the alien */
-      p.append_translation(&Translation3::new(pixel.x, pixel.y, pixel.z));
-
-      /* color it */
-      p.set_color(pixel.r, pixel.g, pixel.b);
-
-      /* keep a record of the pixel's scene node */
-      (*pixel).node = Some(p);
-    }
-
-    /* move the whole model into position and rotate it as required */
-    self.model.append_translation(&Translation3::new(center_x, center_y, center_z));
-    self.rotate(angle);
+    self.angle = angle;
   }
 
-  /* kill off this alien by marking it as dying and calculate how it's going to explode into pieces */
+  /* kill off this alien by marking it as dying. the renderer works out how it's going to
+   * explode into pieces */
   pub fn die(&mut self)
   {
     /* only aliens still alive can die */
@@ -205,19 +354,10 @@ impl Alien
     };
 
     self.state = State::Dying;
-
-    /* generate random x,y,z vector for explosion trajectory for this pixel */
-    for pixel in self.pixels.iter_mut()
-    {
-      pixel.explode_x = random_explosion_vector(&mut self.rng);
-      pixel.explode_y = random_explosion_vector(&mut self.rng);
-      pixel.explode_z = random_explosion_vector(&mut self.rng);
-    }
-
-    self.time_of_death = Some(Instant::now());
+    self.death_ticks = Some(0);
   }
 
-  /* call for each video frame to animate the alien
+  /* call for each simulation tick to animate the alien
    * => step = number of coordinate points to move. 0.0 for no movement */
   pub fn animate(&mut self, step: f32)
   {
@@ -227,13 +367,14 @@ impl Alien
       State::Alive =>
       {
         /* rotate the alien slightly */
-        self.rotate(0.018);
+        self.angle = self.angle + ALIEN_SPIN;
 
-        /* switch between animation frames every second */
-        if self.last_time.elapsed().as_secs() > 1
+        /* switch between animation frames every couple of seconds */
+        self.frame_ticks = self.frame_ticks + 1;
+        if self.frame_ticks > ALIEN_FRAME_TICKS
         {
           self.switch();
-          self.last_time = Instant::now();
+          self.frame_ticks = 0;
         }
 
         let mut tx = 0.0;
@@ -251,179 +392,330 @@ impl Alien
         self.x = self.x + tx;
         self.y = self.y + ty;
         self.drop_steps = self.drop_steps + ty;
-        self.model.append_translation(&Translation3::new(tx, ty, 0.0));
       },
 
       State::Dying =>
       {
-        self.explode();
+        /* after a period of seconds, the remains are wiped away and the alien is dead */
+        let ticks = self.death_ticks.unwrap_or(0) + 1;
+        self.death_ticks = Some(ticks);
+        if ticks > ALIEN_DEATH_TICKS
+        {
+          self.state = State::Dead;
+        }
       },
 
       _ => {}
     }
   }
 
-  /* rotate the whole alien model by given angle along y-axis */
-  fn rotate(&mut self, angle: f32)
+  /* move the alien one step of a classic march, and flip its animation frame as it goes,
+   * like the original. when heading down, the alien drops a whole row in one go
+   * => step = number of coordinate points to move left or right */
+  fn march(&mut self, step: f32)
   {
-    let rotate = UnitQuaternion::from_axis_angle(&Vector3::y_axis(), angle);
-    self.model.prepend_to_local_rotation(&rotate);
+    match self.movement
+    {
+      Movement::Left => self.x = self.x + step,
+      Movement::Right => self.x = self.x - step,
+      Movement::DownRight | Movement::DownLeft =>
+      {
+        self.y = self.y - ALIEN_HEIGHT;
+        self.drop_steps = 0.0 - ALIEN_HEIGHT;
+        self.movement = match self.movement
+        {
+          Movement::DownRight => Movement::Right,
+          _ => Movement::Left
+        };
+      }
+    }
+
+    self.switch();
+    self.frame_ticks = 0;
   }
 
   /* call this to switch pixels between their base and translated positions.
    * this allows the alien to have two frames of animation */
   fn switch(&mut self)
   {
-    match self.frame
+    self.frame = match self.frame
     {
-      Frame::Base =>
-      {
-        /* move pixels into alternate positions, and update alien frame state */
-        for pixel in self.pixels.iter_mut()
-        {
-          pixel.node.as_mut().unwrap().append_translation(&Translation3::new(pixel.tx, pixel.ty, pixel.tz));
-        }
-        self.frame = Frame::Translated;
-      },
-
-      Frame::Translated =>
-      {
-        /* move pixels back to normal positions, and update alien frame state */
-        for pixel in self.pixels.iter_mut()
-        {
-          pixel.node.as_mut().unwrap().append_translation(&Translation3::new(pixel.tx * -1.0, pixel.ty * -1.0, pixel.tz * -1.0));
-        }
-        self.frame = Frame::Base;
-      }
+      Frame::Base => Frame::Translated,
+      Frame::Translated => Frame::Base
     };
   }
 
-  /* remove all objects (pixels) from the game world */
-  pub fn delete(&mut self)
+  /* returns (x, y, z) coords of the alien's center */
+  pub fn get_coords(&self) -> (f32, f32, f32)
   {
-    for pixel in self.pixels.iter_mut()
-    {
-      pixel.node.as_mut().unwrap().unlink();
-      pixel.node = None;
-    }
+    (self.x, self.y, self.z)
+  }
+
+  /* returns the alien's y-axis rotation */
+  pub fn get_angle(&self) -> f32
+  {
+    self.angle
+  }
+
+  /* returns the animation frame to draw the alien's pixels in */
+  pub fn get_frame(&self) -> Frame
+  {
+    self.frame
   }
 
-  /* override_color
-     Set all the pixels in an alien to a particular color
-     => r, g, b = new RGB color of all the alien's pixel blocks
-  */
-  pub fn override_color(&mut self, r: f32, g: f32, b: f32)
+  /* returns whether the alien is alive, exploding or dead */
+  pub fn get_state(&self) -> State
   {
-    for pixel in self.pixels.iter_mut()
+    self.state
+  }
+
+  /* returns the number of ticks the alien has been exploding for, or None if not dead */
+  pub fn ticks_since_death(&self) -> Option<u32>
+  {
+    self.death_ticks
+  }
+
+  /* returns what kind of alien this is */
+  pub fn get_species(&self) -> Species
+  {
+    self.species
+  }
+
+  /* change what kind of alien this is, eg: when restoring it from a saved game */
+  pub fn set_species(&mut self, species: Species)
+  {
+    self.species = species;
+  }
+
+  /* returns the (column, row) of the formation the alien was spawned in */
+  pub fn get_position(&self) -> (i32, i32)
+  {
+    (self.column, self.row)
+  }
+
+  /* write the alien to a saved game */
+  pub fn save(&self, writer: &mut save::Writer)
+  {
+    writer.label("alien");
+    for value in [ self.x, self.y, self.z, self.angle ].iter()
     {
-      pixel.node.as_mut().unwrap().set_color(r, g, b);
+      writer.value(value);
     }
+
+    writer.value(match self.frame
+    {
+      Frame::Base => "base",
+      Frame::Translated => "translated"
+    });
+    writer.value(match self.state
+    {
+      State::Alive => "alive",
+      State::Dying => "dying",
+      State::Dead => "dead"
+    });
+    writer.value(self.frame_ticks);
+    writer.option(self.death_ticks);
+    writer.value(self.drop_steps);
+    writer.value(movement_name(self.movement));
+    writer.value(self.column);
+    writer.value(self.row);
   }
 
-  /* animate blowing up the alien: scatter its compoents, spinning them, and then delete them */
-  fn explode(&mut self)
+  /* recreate an alien from a saved game */
+  pub fn restore(reader: &mut save::Reader) -> Result<Alien, save::SaveError>
   {
-    let rotate = UnitQuaternion::from_axis_angle(&Vector3::y_axis(), 0.05);
-    let secs_since_death = self.time_of_death.unwrap().elapsed().as_secs();
+    reader.label("alien")?;
+    let (x, y, z, angle) = (reader.value()?, reader.value()?, reader.value()?, reader.value()?);
 
-    for pixel in self.pixels.iter_mut()
+    let frame = match reader.value::<String>()?.as_str()
     {
-      pixel.node.as_mut().unwrap().append_translation(&Translation3::new(pixel.explode_x, pixel.explode_y, pixel.explode_z));
-      pixel.node.as_mut().unwrap().prepend_to_local_rotation(&rotate);
-
-      /* change color of the pixel based on seconds passed */
-      match secs_since_death
-      {
-        0 | 1 => pixel.node.as_mut().unwrap().set_color(1.0, 0.4, 0.0),
-            2 => pixel.node.as_mut().unwrap().set_color(1.0, 0.6, 0.0),
-            3 => pixel.node.as_mut().unwrap().set_color(1.0, 0.8, 0.0),
-            4 => pixel.node.as_mut().unwrap().set_color(1.0, 1.0, 0.0),
-            5 => pixel.node.as_mut().unwrap().set_color(0.8, 0.8, 0.0),
-            6 => pixel.node.as_mut().unwrap().set_color(0.6, 0.6, 0.0),
-            7 => pixel.node.as_mut().unwrap().set_color(0.4, 0.4, 0.0),
-            _ => pixel.node.as_mut().unwrap().set_color(0.2, 0.2, 0.0),
-      };
-    }
+      "base" => Frame::Base,
+      "translated" => Frame::Translated,
+      other => return Err(reader.unexpected("animation frame", other))
+    };
+    let state = match reader.value::<String>()?.as_str()
+    {
+      "alive" => State::Alive,
+      "dying" => State::Dying,
+      "dead" => State::Dead,
+      other => return Err(reader.unexpected("alien state", other))
+    };
+    let frame_ticks = reader.value()?;
+    let death_ticks = reader.option()?;
+    let drop_steps = reader.value()?;
+    let movement = reader.value::<String>()?;
+    let movement = movement_from_name(reader, movement.as_str())?;
 
-    /* after a period of seconds, wipe away the remains: mark all components of the alien invisible,
-     * unlink them from the scene, and mark the alien as dead. */
-    if secs_since_death > 10
+    Ok(Alien
     {
-      for pixel in self.pixels.iter_mut()
-      {
-        pixel.node.as_mut().unwrap().unlink();
-      }
-      self.state = State::Dead;
-    }
+      x: x, y: y, z: z,
+      angle: angle,
+      frame: frame,
+      state: state,
+      frame_ticks: frame_ticks,
+      death_ticks: death_ticks,
+      drop_steps: drop_steps,
+      movement: movement,
+      column: reader.value()?,
+      row: reader.value()?,
+      species: Species::Crab
+    })
   }
 }
 
-/* ------------------------------------------------------------------------------ */
-
-/* generate a random value suitable for exploding a pixel */
-pub fn random_explosion_vector(rng: &mut rand::rngs::ThreadRng) -> f32
+/* returns a movement's name, as written in saved games */
+fn movement_name(movement: Movement) -> &'static str
 {
-  if rng.gen()
+  match movement
   {
-    return rng.gen_range(-0.5f32, -0.1f32);
+    Movement::Left => "left",
+    Movement::Right => "right",
+    Movement::DownRight => "downright",
+    Movement::DownLeft => "downleft"
   }
+}
 
-  return rng.gen_range(0.1f32, 0.5f32);
+/* returns the movement with the given name, read from a saved game */
+fn movement_from_name(reader: &save::Reader, name: &str) -> Result<Movement, save::SaveError>
+{
+  match name
+  {
+    "left" => Ok(Movement::Left),
+    "right" => Ok(Movement::Right),
+    "downright" => Ok(Movement::DownRight),
+    "downleft" => Ok(Movement::DownLeft),
+    other => Err(reader.unexpected("alien movement", other))
+  }
 }
 
 /* ------------------------------------------------------------------------------ */
 
-/* collect up all aliens and the bomb they drop in a playfield together */
+/* collect up all aliens and the bomb they drop in a playfield together. the squadron is a
+ * grid of ALIENS_PER_ROW columns by ALIEN_ROWS rows, stored a row at a time from the bottom
+ * row up, and from column 0 in each row. column 0 is on the right of the screen, as screen
+ * left is the positive x direction, and row 0 is at the bottom */
 pub struct Aliens
 {
-  squadron: Vec<Alien>,
-  pub bomb: Option<bullet::Bullet>
+  squadron: Vec<Alien>,     /* every alien in the formation, dead or alive, in grid order */
+  column_alive: Vec<i32>,   /* number of aliens still alive in each column */
+  pub bomb: Option<bullet::Bullet>,
+  burst_ticks: Option<u32>, /* ticks since the last speed burst was triggered, if any */
+  rng: Prng,                /* decides which aliens drop bombs */
+
+  /* in a classic march, how far through the squadron the current ripple has got, and the
+   * way to turn when it's done if an alien reached a side wall on the way */
+  ripple_next: usize,
+  ripple_turn: Option<Movement>
 }
 
 /* control the whole squad at once */
 impl Aliens
 {
-  /* construct a playfield of aliens, pass it back as a vector of aliens */
-  pub fn new(mut window: &mut Window) -> Aliens
+  /* construct a playfield of aliens, pass it back as a vector of aliens
+   * => seed = starting point for the squadron's random decisions
+   *    formation = which aliens to line up where */
+  pub fn new(seed: u64, formation: &Formation) -> Aliens
   {
     let mut baddies = Aliens
     {
-      squadron: Vec::<Alien>::with_capacity(55),
-      bomb: None
+      squadron: Vec::<Alien>::with_capacity((ALIENS_PER_ROW * ALIEN_ROWS) as usize),
+      column_alive: vec![ 0; ALIENS_PER_ROW as usize ],
+      bomb: None,
+      burst_ticks: None,
+      rng: Prng::new(seed),
+      ripple_next: 0,
+      ripple_turn: None
     };
 
-    /* generate a formation ALIENS_PER_ROW number of aliens wide, centered
-    * on the x-axis, and ALIEN_ROWS number of aliens tall, from ALIEN_TOP_Y downwards.
-    * ALIEN_TOP_Y is in whole number of aliens from the center of the playfield */
-    for y in (ALIEN_TOP_Y - ALIEN_ROWS)..ALIEN_TOP_Y
+    /* fill the whole grid, ALIENS_PER_ROW number of aliens wide, centered on the x-axis, in
+     * grid order. the formation sets the height of each row, in whole number of aliens from
+     * the center of the playfield. gaps in the formation are filled with aliens that are
+     * already dead, so the grid is always complete */
+    for row in 0..ALIEN_ROWS
     {
-      for x in 0 - (ALIENS_PER_ROW / 2)..(ALIENS_PER_ROW / 2) + 1
+      for column in 0..ALIENS_PER_ROW
       {
-        let mut baddie = Alien::new(&mut window);
-        let rotation = 0.4 * ((x + y) as f32);
-        baddie.spawn(x as f32 * ALIEN_WIDTH, y as f32 * ALIEN_HEIGHT, 0.0, rotation);
+        let mut baddie = Alien::new();
+        let x = column - (ALIENS_PER_ROW / 2);
+        baddie.column = column;
+        baddie.row = row;
+        baddie.spawn(x as f32 * ALIEN_WIDTH, formation.height(row) as f32 * ALIEN_HEIGHT, 0.0,
+                     formation.rotation(column, row));
+
+        match formation.species(column, row)
+        {
+          Some(species) =>
+          {
+            baddie.species = species;
+            baddies.column_alive[column as usize] = baddies.column_alive[column as usize] + 1;
+          },
+          None => baddie.state = State::Dead
+        }
+
         baddies.squadron.push(baddie);
       }
     }
 
-    /* sort baddies from high y to low y, for bomb dropping code */
-    baddies.squadron.sort_by(|a, b| b.y.partial_cmp(&a.y).unwrap());
-
     return baddies;
   }
 
-  /* ensure all objects are removed from the game world */
-  pub fn delete(&mut self)
+  /* returns where the alien at the given column and row is kept in the squadron */
+  fn index(column: i32, row: i32) -> usize
+  {
+    (row * ALIENS_PER_ROW + column) as usize
+  }
+
+  /* returns the aliens in the given formation column, from the bottom row up */
+  fn column(&self, column: i32) -> impl Iterator<Item = &Alien>
+  {
+    (0..ALIEN_ROWS).map(move |row| &self.squadron[Aliens::index(column, row)])
+  }
+
+  /* returns the lowest alive alien in the given column, or None if the column has been
+   * wiped out or doesn't exist */
+  pub fn lowest_in_column(&self, column: i32) -> Option<&Alien>
   {
-    for baddie in self.squadron.iter_mut()
+    match (0..ALIENS_PER_ROW).contains(&column)
     {
-      baddie.delete();
+      true => self.column(column).find(|a| a.state == State::Alive),
+      false => None
     }
-    self.destroy_bomb();
   }
 
-  /* drop a bomb if one isn't already in play */
-  pub fn fire(&mut self, mut window: &mut Window)
+  /* returns the living column furthest to the left of the screen, or None if all are dead */
+  pub fn leftmost_column(&self) -> Option<i32>
+  {
+    (0..ALIENS_PER_ROW).rev().find(|c| self.column_alive[*c as usize] > 0)
+  }
+
+  /* returns the living column furthest to the right of the screen, or None if all are dead */
+  pub fn rightmost_column(&self) -> Option<i32>
+  {
+    (0..ALIENS_PER_ROW).find(|c| self.column_alive[*c as usize] > 0)
+  }
+
+  /* returns the lowest row with an alien still alive in it, or None if all are dead */
+  pub fn lowest_row(&self) -> Option<i32>
+  {
+    (0..ALIEN_ROWS).find(|row| (0..ALIENS_PER_ROW).any(|c| self.squadron[Aliens::index(c, *row)].state == State::Alive))
+  }
+
+  /* returns the number of aliens the level started with. gaps in the formation are dead
+   * from the start and never die, so are left out */
+  fn enlisted(&self) -> usize
+  {
+    self.squadron.iter().filter(|a| a.state != State::Dead || a.death_ticks.is_some()).count()
+  }
+
+  /* return the aliens in the squadron, for drawing */
+  pub fn get_squadron(&self) -> &Vec<Alien>
+  {
+    &self.squadron
+  }
+
+  /* drop a bomb if one isn't already in play
+   * => speed = distance the bomb falls each tick */
+  pub fn fire(&mut self, speed: f32)
   {
     if self.bomb.is_none() == true
     {
@@ -435,73 +727,128 @@ impl Aliens
         return; /* no alive aliens means no bombs dropped */
       }
 
-      /* work out which alien should drop a bomb next. the lowest alien in each column can
-       * drop a bomb. first pick a random alive alien so we get its x, y position */
-      let index = rand::thread_rng().gen::<usize>() % aliens;
-      let baddie = self.squadron.iter().filter(|f| f.state == State::Alive).nth(index).unwrap();
+      /* work out which alien should drop a bomb next. the lowest alive alien in each column
+       * can drop a bomb. pick a random alive alien to choose the column, so columns with
+       * more aliens left in them bomb more often */
+      let index = self.rng.gen::<usize>() % aliens;
+      let column = self.squadron.iter().filter(|f| f.state == State::Alive).nth(index).unwrap().column;
+      self.fire_column(column, speed);
+    }
+  }
 
-      /* now find the alien in the same x column with the lowest y. this assumes
-       * the vector remains sorted from top left to bottom right... */
-      let lowest = self.squadron.iter().filter(|f| f.x == baddie.x && f.y <= baddie.y).last().unwrap();
+  /* drop a bomb from the lowest alive alien in the given column, if a bomb isn't already in
+   * play. this is used when a second player commands the squadron in versus mode
+   * => column = formation column to drop the bomb from
+   *    speed = distance the bomb falls each tick
+   * <= true if a bomb was dropped, false if not */
+  pub fn fire_column(&mut self, column: i32, speed: f32) -> bool
+  {
+    if self.bomb.is_some() == true
+    {
+      return false; /* one bomb at a time, same as when the squadron fires by itself */
+    }
+
+    let (x, y) = match self.lowest_in_column(column)
+    {
+      Some(lowest) => (lowest.x, lowest.y - (ALIEN_HEIGHT / 2.0)), /* start bomb just below alien */
+      None => return false
+    };
+
+    self.bomb = Some(bullet::Bullet::new(x, y, BOMB_RADIUS,
+                                         BOMB_COLOR_R, BOMB_COLOR_G, BOMB_COLOR_B,
+                                         0.0 - speed));
+    return true;
+  }
 
-      let x = lowest.x;
-      let y = lowest.y - (ALIEN_HEIGHT / 2.0); /* start bomb just below alien */
-      self.bomb = Some(bullet::Bullet::new(&mut window, x, y, BOMB_RADIUS,
-                                           BOMB_COLOR_R, BOMB_COLOR_G, BOMB_COLOR_B,
-                                           BOMB_DESCENT));
+  /* return the formation columns that still have at least one alive alien, lowest first */
+  pub fn alive_columns(&self) -> Vec<i32>
+  {
+    (0..ALIENS_PER_ROW).filter(|c| self.column_alive[*c as usize] > 0).collect()
+  }
+
+  /* return Some(x, y) coords of the lowest alive alien in the given column, or None if
+   * the column has been wiped out */
+  pub fn column_coords(&self, column: i32) -> Option<(f32, f32)>
+  {
+    self.lowest_in_column(column).map(|lowest| (lowest.x, lowest.y))
+  }
+
+  /* temporarily speed up the whole squadron, if the previous burst has recharged
+   * <= true if the burst started, false if still recharging */
+  pub fn burst(&mut self) -> bool
+  {
+    if let Some(ticks) = self.burst_ticks
+    {
+      if ticks < BURST_RECHARGE_TICKS
+      {
+        return false;
+      }
     }
+
+    self.burst_ticks = Some(0);
+    return true;
   }
 
-  /* remove bomb from game */
-  pub fn destroy_bomb(&mut self)
+  /* return true if a speed burst is currently under way */
+  fn bursting(&self) -> bool
   {
-    if self.bomb.as_mut().is_some() == true
+    match self.burst_ticks
     {
-      self.bomb.as_mut().unwrap().destroy();
-      self.bomb = None;
+      Some(ticks) => ticks < BURST_TICKS,
+      None => false
     }
   }
 
-  /* update the positions of the aliens and check to see if any collided with the invisible walls
-   * which causes them to move down a row and reverse movement. also animate the aliens' bomb */
-  pub fn animate(&mut self)
+  /* remove bomb from game */
+  pub fn destroy_bomb(&mut self)
   {
-    let mut hit_wall_right = false;
-    let mut hit_wall_left = false;
+    self.bomb = None;
+  }
 
+  /* update the positions of the aliens and check to see if any collided with the invisible walls
+   * which causes them to move down a row and reverse movement. also animate the aliens' bomb
+   * => difficulty = sets how fast and in what way the squadron moves */
+  pub fn animate(&mut self, difficulty: &Difficulty)
+  {
     /* animate the aliens' bomb */
     if self.bomb.is_some() == true
     {
       self.bomb.as_mut().unwrap().animate();
     }
 
+    /* keep track of how long ago the last speed burst started */
+    if let Some(ticks) = self.burst_ticks
+    {
+      self.burst_ticks = Some(ticks.saturating_add(1));
+    }
+
+    match difficulty.march
+    {
+      March::Smooth => self.march_smooth(difficulty),
+      March::Classic => self.march_classic(difficulty)
+    }
+  }
+
+  /* move every alien a little, all together */
+  fn march_smooth(&mut self, difficulty: &Difficulty)
+  {
     /* scale the speed depending on how many aliens are alive - fewer means faster */
-    let aliens = (ALIENS_PER_ROW * ALIEN_ROWS) as usize - self.squadron.iter().filter(|f| f.state == State::Alive).count();
-    let step = 0.1 + (aliens as f32 * 0.008);
+    let aliens = self.enlisted() - self.squadron.iter().filter(|f| f.state == State::Alive).count();
+    let mut step = difficulty.march_speed(aliens);
+    if self.bursting() == true
+    {
+      step = step * BURST_SPEED;
+    }
 
-    /* move the aliens one by one, and check for collision with side walls */
+    /* move the aliens one by one */
     for baddie in self.squadron.iter_mut().filter(|f| f.state != State::Dead)
     {
       /* animate and move this particular alien */
       baddie.animate(step);
 
-      /* if we're moving left or right, check to see if we hit a wall */
       match baddie.movement
       {
-        Movement::Left | Movement::Right =>
-        {
-          /* did the baddie just collide with a wall om the left? */
-          if baddie.x > ((ALIENS_PER_ROW / 2) + 1 + ALIEN_SIDE_SPACE) as f32 * ALIEN_WIDTH
-          {
-            hit_wall_left = true;
-          }
-
-          /* did the baddie just collide with a wall om the right? */
-          if baddie.x < ((ALIENS_PER_ROW / 2) + ALIEN_SIDE_SPACE) as f32 * (0.0 - ALIEN_WIDTH)
-          {
-            hit_wall_right = true;
-          }
-        },
+        Movement::Left | Movement::Right => {},
 
         /* if we're going down then make sure we don't go down too far - just one row */
         Movement::DownLeft =>
@@ -522,28 +869,107 @@ impl Aliens
       }
     }
 
-    /* if one or more of the aliens hit a side wall, then change their directions so they're
-    * all moving downwards */
-    if hit_wall_right == true
+    /* if the squadron hit a side wall, then change everyone's direction so they're all
+     * moving downwards */
+    if let Some(turn) = self.wall_turn()
     {
       for faller in self.squadron.iter_mut()
       {
         faller.drop_steps = 0.0;
-        faller.movement = Movement::DownLeft; /* go down then left */
+        faller.movement = turn;
       }
     }
-    if hit_wall_left == true
+  }
+
+  /* check whether the squadron has reached a side wall. only the living columns at either
+   * end of the formation can get there first
+   * <= the way to turn if so, or None to carry on */
+  fn wall_turn(&self) -> Option<Movement>
+  {
+    let reached = |column: Option<i32>, heading: Movement, wall: &dyn Fn(f32) -> bool|
     {
-      for faller in self.squadron.iter_mut()
+      match column
       {
-        faller.drop_steps = 0.0;
-        faller.movement = Movement::DownRight; /* go down then left */
+        Some(column) => self.column(column).any(|a| a.state == State::Alive && a.movement == heading && wall(a.x) == true),
+        None => false
       }
+    };
+
+    if reached(self.leftmost_column(), Movement::Left, &|x| x > WALL_LEFT) == true
+    {
+      return Some(Movement::DownRight); /* go down then right */
+    }
+    if reached(self.rightmost_column(), Movement::Right, &|x| x < WALL_RIGHT) == true
+    {
+      return Some(Movement::DownLeft); /* go down then left */
+    }
+    return None;
+  }
+
+  /* move one alien, taking turns from the bottom left of the formation to the top right.
+   * every alien moves the same distance when its turn comes, so the fewer there are, the
+   * more often each one moves and the faster the squadron goes. when an alien hits a side
+   * wall, the whole squadron turns once the ripple reaches the top right, and then each
+   * alien drops a row on its next turn */
+  fn march_classic(&mut self, difficulty: &Difficulty)
+  {
+    /* everyone spins, animates and explodes as usual, but only one alien moves */
+    for baddie in self.squadron.iter_mut().filter(|f| f.state != State::Dead)
+    {
+      baddie.animate(0.0);
+    }
+
+    if self.squadron.iter().any(|f| f.state == State::Alive) == false
+    {
+      return;
+    }
+
+    /* a full squadron moves as fast as it would in a smooth march */
+    let mut step = difficulty.march_step * self.enlisted() as f32;
+    if self.bursting() == true
+    {
+      step = step * BURST_SPEED;
+    }
+
+    /* screen left is the positive x direction, ie: higher columns */
+    let order: Vec<usize> = (0..ALIEN_ROWS).flat_map(|row| (0..ALIENS_PER_ROW).rev().map(move |column| Aliens::index(column, row)))
+                                           .collect();
+
+    /* find the next alien still alive, starting a new ripple if we reach the end */
+    let index = loop
+    {
+      if self.ripple_next >= order.len()
+      {
+        self.ripple_next = 0;
+        if let Some(turn) = self.ripple_turn.take()
+        {
+          for faller in self.squadron.iter_mut()
+          {
+            faller.drop_steps = 0.0;
+            faller.movement = turn;
+          }
+        }
+      }
+
+      let index = order[self.ripple_next];
+      self.ripple_next = self.ripple_next + 1;
+      if self.squadron[index].state == State::Alive
+      {
+        break index;
+      }
+    };
+
+    self.squadron[index].march(step);
+
+    /* same walls as the smooth march */
+    if let Some(turn) = self.wall_turn()
+    {
+      self.ripple_turn = Some(turn);
     }
   }
 
   /* return true if all aliens in the squadron are finally dead */
-  pub fn all_dead(&mut self) -> bool
+  pub fn all_dead(&self) -> bool
   {
     match self.squadron.iter().filter(|f| f.state != State::Dead).count()
     {
@@ -553,23 +979,35 @@ impl Aliens
   }
 
   /* return the lowest Y coord of the alien squadron */
-  pub fn lowest_y(&mut self) -> f32
+  pub fn lowest_y(&self) -> f32
   {
-    let mut lowest = ALIEN_Y_CEILING;
-    for baddie in self.squadron.iter()
+    /* only the lowest living row can be lowest, though aliens in it may be part way through
+     * dropping down during a classic march */
+    let row = match self.lowest_row()
     {
-      if baddie.y < lowest
-      {
-        lowest = baddie.y;
-      }
-    }
+      Some(row) => row,
+      None => return ALIEN_Y_CEILING
+    };
 
-    return lowest;
+    (0..ALIENS_PER_ROW).map(|column| &self.squadron[Aliens::index(column, row)])
+                       .filter(|a| a.state == State::Alive)
+                       .fold(ALIEN_Y_CEILING, |lowest, a| lowest.min(a.y))
   }
 
   /* check to see if any alive aliens collide with the thing at x,y. if one does,
    * then blow up the alien, removing it from the game, and return a hit */
   pub fn collision(&mut self, x: f32, y: f32) -> collision::CollisionOutcome
+  {
+    match self.hit(x, y)
+    {
+      Some(_) => collision::CollisionOutcome::Hit,
+      None => collision::CollisionOutcome::Miss
+    }
+  }
+
+  /* like collision(), but return the (column, row) of the formation the alien that was
+   * blown up came from and its species, or None for a miss */
+  pub fn hit(&mut self, x: f32, y: f32) -> Option<(i32, i32, Species)>
   {
     for baddie in self.squadron.iter_mut().filter(|b| b.state == State::Alive)
     {
@@ -579,18 +1017,192 @@ impl Aliens
         b: collision::CollisionObject{ x: baddie.x, y: baddie.y }
       };
 
-      match collision::check(scenario)
+      if collision::check(scenario) == collision::CollisionOutcome::Hit
       {
-        collision::CollisionOutcome::Hit =>
-        {
-          baddie.die();
-          return collision::CollisionOutcome::Hit;
-        },
+        baddie.die();
+        self.column_alive[baddie.column as usize] = self.column_alive[baddie.column as usize] - 1;
+        let (column, row) = baddie.get_position();
+        return Some((column, row, baddie.species));
+      }
+    }
 
-        _ => {}
+    return None;
+  }
+
+  /* write the squadron and its bomb to a saved game */
+  pub fn save(&self, writer: &mut save::Writer)
+  {
+    writer.label("aliens");
+    writer.option(self.burst_ticks);
+    writer.value(self.rng.get_state());
+    writer.value(self.squadron.len());
+
+    writer.label("ripple");
+    writer.value(self.ripple_next);
+    writer.option(self.ripple_turn.map(movement_name));
+
+    for baddie in self.squadron.iter()
+    {
+      baddie.save(writer);
+    }
+
+    /* the species of each alien in grid order, by letter */
+    writer.label("species");
+    writer.value(self.squadron.iter().map(|a| a.species.letter()).collect::<String>());
+
+    writer.label("bomb");
+    match self.bomb.as_ref()
+    {
+      Some(bomb) => bomb.save(writer),
+      None => writer.none()
+    }
+  }
+
+  /* recreate the squadron and its bomb from a saved game */
+  pub fn restore(reader: &mut save::Reader) -> Result<Aliens, save::SaveError>
+  {
+    reader.label("aliens")?;
+    let burst_ticks = reader.option()?;
+    let rng = Prng::new(reader.value()?);
+
+    let count: usize = reader.value()?;
+
+    /* files from before the classic march leave out the ripple */
+    let (mut ripple_next, mut ripple_turn) = (0, None);
+    if reader.next_is("ripple") == true
+    {
+      reader.label("ripple")?;
+      ripple_next = reader.value()?;
+      ripple_turn = match reader.option::<String>()?
+      {
+        Some(name) => Some(movement_from_name(reader, name.as_str())?),
+        None => None
       };
     }
 
-    return collision::CollisionOutcome::Miss;
+    /* older files list the squadron from the top down, so put it back into grid order,
+     * checking every place in the formation is filled exactly once */
+    let mut squadron = Vec::with_capacity(count);
+    for _ in 0..count
+    {
+      squadron.push(Alien::restore(reader)?);
+    }
+    squadron.sort_by_key(|a: &Alien| (a.row, a.column));
+    let complete = squadron.len() == (ALIENS_PER_ROW * ALIEN_ROWS) as usize &&
+                   squadron.iter().enumerate().all(|(index, a)| a.column >= 0 && a.column < ALIENS_PER_ROW &&
+                                                                Aliens::index(a.column, a.row) == index);
+    if complete == false
+    {
+      return Err(save::SaveError::Corrupt(String::from("squadron doesn't fill the formation")));
+    }
+
+    /* files from before there was more than one species leave them out, and had only crabs */
+    if reader.next_is("species") == true
+    {
+      reader.label("species")?;
+      let letters = reader.value::<String>()?;
+      if letters.chars().count() != squadron.len()
+      {
+        return Err(save::SaveError::Corrupt(String::from("species don't match the squadron")));
+      }
+      for (baddie, letter) in squadron.iter_mut().zip(letters.chars())
+      {
+        baddie.species = match Species::from_letter(letter)
+        {
+          Some(species) => species,
+          None => return Err(reader.unexpected("species", letter.to_string().as_str()))
+        };
+      }
+    }
+
+    let mut column_alive = vec![ 0; ALIENS_PER_ROW as usize ];
+    for baddie in squadron.iter().filter(|a| a.state == State::Alive)
+    {
+      column_alive[baddie.column as usize] = column_alive[baddie.column as usize] + 1;
+    }
+
+    reader.label("bomb")?;
+    let bomb = match reader.none()
+    {
+      true => None,
+      false => Some(bullet::Bullet::restore(reader)?)
+    };
+
+    Ok(Aliens
+    {
+      squadron: squadron,
+      column_alive: column_alive,
+      bomb: bomb,
+      burst_ticks: burst_ticks,
+      rng: rng,
+      ripple_next: ripple_next,
+      ripple_turn: ripple_turn
+    })
+  }
+}
+
+/* ------------------------------------------------------------------------------ */
+
+#[cfg(test)]
+mod tests
+{
+  use super::*;
+  use super::super::difficulty::Profile;
+
+  /* march the squadron one alien at a time
+   * <= the (column, row) of the alien that moved each time */
+  fn ripple(aliens: &mut Aliens, difficulty: &Difficulty, moves: usize) -> Vec<(i32, i32)>
+  {
+    let mut moved = Vec::new();
+    for _ in 0..moves
+    {
+      let before: Vec<f32> = aliens.squadron.iter().map(|a| a.x).collect();
+      aliens.march_classic(difficulty);
+
+      let movers: Vec<(i32, i32)> = aliens.squadron.iter().zip(before.iter())
+                                                   .filter(|(a, x)| a.x != **x)
+                                                   .map(|(a, _)| (a.column, a.row))
+                                                   .collect();
+      assert_eq!(movers.len(), 1, "more than one alien moved at once: {:?}", movers);
+      moved.push(movers[0]);
+    }
+    moved
+  }
+
+  #[test]
+  fn classic_march_ripples_from_the_bottom_left()
+  {
+    let mut difficulty = Difficulty::new(Profile::Normal);
+    difficulty.march = March::Classic;
+
+    /* three columns wide, centered on columns 4 to 6, with a gap in the middle of the
+     * bottom row. column 6 is on the left of the screen */
+    let formation = match Formation::parse("test", "row 0 CCC\nrow 0 C.C")
+    {
+      Ok(formation) => formation,
+      Err(reason) => panic!("{}", reason)
+    };
+    let mut aliens = Aliens::new(1, &formation);
+
+    let once = [ (6, 0), (4, 0), (6, 1), (5, 1), (4, 1) ];
+    let twice: Vec<(i32, i32)> = once.iter().chain(once.iter()).cloned().collect();
+    assert_eq!(ripple(&mut aliens, &difficulty, twice.len()), twice);
+  }
+
+  #[test]
+  fn classic_march_skips_the_dead()
+  {
+    let mut difficulty = Difficulty::new(Profile::Normal);
+    difficulty.march = March::Classic;
+
+    let formation = match Formation::parse("test", "row 0 CCC")
+    {
+      Ok(formation) => formation,
+      Err(reason) => panic!("{}", reason)
+    };
+    let mut aliens = Aliens::new(1, &formation);
+    aliens.squadron[Aliens::index(5, 0)].state = State::Dead;
+
+    assert_eq!(ripple(&mut aliens, &difficulty, 4), vec![ (6, 0), (4, 0), (6, 0), (4, 0) ]);
   }
 }
@@ -0,0 +1,246 @@
+/* Space invaders in Rust
+ *
+ * Find and load the game's assets, once
+ *
+ * Game concept by Tomohiro Nishikado / Taito
+ * Rust code By Chris Williams <diodesign@tuta.io>
+ *
+ * Written for fun. See LICENSE.
+ *
+ */
+
+/* assets are looked for in each directory of the RUSTINVADERS_ASSETS environment variable,
+ * separated like PATH, then in media/ in the current directory, then in media/ alongside
+ * the executable or the project root above it. if an asset isn't found on disk, the copy
+ * built into the executable is used, so the game runs from anywhere. a file that's found
+ * but can't be used is reported rather than quietly swapped for the built-in copy, so a
+ * broken replacement asset doesn't go unnoticed.
+ *
+ * call load() once at startup. assets are then kept for the rest of the run and handed
+ * out from there, rather than passed through every screen of the game.
+ *
+ * only the font and the formation files are assets, and that's deliberate. the aliens
+ * and the ships are drawn from pixel designs in the code, which the explosions and the
+ * animation frames depend on, so they aren't loaded as models. the game has no sound, so
+ * there's nothing to load for it. either would be a new kind of asset with its own
+ * loader, like the formations */
+
+extern crate kiss3d;
+extern crate rusttype;
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::env;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::rc::Rc;
+
+use kiss3d::text::Font;
+
+pub const GAME_FONT: &str = "gameplay.ttf";
+
+const ASSET_PATH_VARIABLE: &str = "RUSTINVADERS_ASSETS";
+const ASSET_DIRECTORY: &str = "media";
+
+/* copies of the assets built into the executable */
+const EMBEDDED: &[(&str, &[u8])] =
+&[
+  (GAME_FONT, include_bytes!("../media/gameplay.ttf")),
+  ("formations/waves.txt", include_bytes!("../media/formations/waves.txt")),
+  ("formations/classic.txt", include_bytes!("../media/formations/classic.txt")),
+  ("formations/chevron.txt", include_bytes!("../media/formations/chevron.txt")),
+  ("formations/diamond.txt", include_bytes!("../media/formations/diamond.txt"))
+];
+
+/* fonts the game needs, loaded by load() */
+const FONTS: &[&str] = &[ GAME_FONT ];
+
+/* things that can go wrong loading an asset */
+pub enum AssetError
+{
+  Missing(String),              /* not on disk and not built in */
+  Unreadable(PathBuf, io::Error), /* found on disk, but couldn't be read */
+  Corrupt(String),              /* read, but not usable */
+  Invalid(String, String)       /* read, but with a mistake in it, and the reason why */
+}
+
+impl fmt::Display for AssetError
+{
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
+  {
+    match self
+    {
+      AssetError::Missing(name) => write!(f, "can't find asset {}", name),
+      AssetError::Unreadable(path, e) => write!(f, "can't read asset {}: {}", path.display(), e),
+      AssetError::Corrupt(name) => write!(f, "asset {} is damaged or the wrong kind of file", name),
+      AssetError::Invalid(name, reason) => write!(f, "asset {} can't be used: {}", name, reason)
+    }
+  }
+}
+
+thread_local!
+{
+  /* fonts loaded so far, by name */
+  static LOADED_FONTS: RefCell<HashMap<String, Rc<Font>>> = RefCell::new(HashMap::new());
+}
+
+/* returns the directories to search for assets, in order */
+fn search_path() -> Vec<PathBuf>
+{
+  let mut directories = Vec::new();
+
+  if let Some(paths) = env::var_os(ASSET_PATH_VARIABLE)
+  {
+    directories.extend(env::split_paths(&paths));
+  }
+
+  directories.push(PathBuf::from(ASSET_DIRECTORY));
+
+  /* cargo puts the executable in target/release or target/debug, below the project root */
+  if let Ok(executable) = env::current_exe()
+  {
+    for directory in executable.ancestors().skip(1).take(3)
+    {
+      directories.push(directory.join(ASSET_DIRECTORY));
+    }
+  }
+
+  return directories;
+}
+
+/* returns an asset's contents, from the first place it's found on disk or else the built-in copy
+ * => name = file name of the asset, relative to the asset directories */
+pub fn read(name: &str) -> Result<Vec<u8>, AssetError>
+{
+  for directory in search_path()
+  {
+    let path = directory.join(name);
+    if path.is_file() == true
+    {
+      return fs::read(&path).map_err(|e| AssetError::Unreadable(path, e));
+    }
+  }
+
+  match built_in(name)
+  {
+    Some(contents) => Ok(contents.to_vec()),
+    None => Err(AssetError::Missing(String::from(name)))
+  }
+}
+
+/* returns the copy of an asset built into the executable, or None if there isn't one */
+pub fn built_in(name: &str) -> Option<&'static [u8]>
+{
+  EMBEDDED.iter().find(|(embedded, _)| *embedded == name).map(|(_, contents)| *contents)
+}
+
+/* returns true if a font file holds the whole of every table its directory lists. a font
+ * cut short can otherwise get past rusttype and only fail once text is drawn with it */
+fn font_is_whole(contents: &[u8]) -> bool
+{
+  /* big-endian number of the given size at the given offset, if the file is long enough */
+  let number = |offset: usize, size: usize| -> Option<usize>
+  {
+    let bytes = contents.get(offset..offset + size)?;
+    Some(bytes.iter().fold(0, |n, byte| (n << 8) | *byte as usize))
+  };
+
+  /* the directory starts with the number of tables, then a 16-byte entry for each one,
+   * holding its offset and length at bytes 8 and 12 of the entry */
+  let tables = match number(4, 2)
+  {
+    Some(tables) => tables,
+    None => return false
+  };
+  for table in 0..tables
+  {
+    let entry = 12 + (table * 16);
+    match (number(entry + 8, 4), number(entry + 12, 4))
+    {
+      (Some(offset), Some(length)) if offset + length <= contents.len() => {},
+      _ => return false
+    }
+  }
+
+  return tables > 0;
+}
+
+/* turn the contents of a font file into a font
+ * => name = file name of the font, for reporting problems
+ *    contents = the file's contents */
+fn font_from_bytes(name: &str, contents: &[u8]) -> Result<Rc<Font>, AssetError>
+{
+  /* kiss3d panics on a font rusttype can't read, so check it first */
+  if font_is_whole(contents) == false || rusttype::Font::from_bytes(contents.to_vec()).is_err() == true
+  {
+    return Err(AssetError::Corrupt(String::from(name)));
+  }
+
+  match Font::from_bytes(contents)
+  {
+    Some(font) => Ok(font),
+    None => Err(AssetError::Corrupt(String::from(name)))
+  }
+}
+
+/* load every asset the game needs, so they're ready to use and any problems show up
+ * straight away rather than midway through a game */
+pub fn load() -> Result<(), AssetError>
+{
+  for name in FONTS.iter()
+  {
+    let contents = read(name)?;
+    let font = font_from_bytes(name, contents.as_slice())?;
+    LOADED_FONTS.with(|fonts| fonts.borrow_mut().insert(String::from(*name), font));
+  }
+
+  return Ok(());
+}
+
+/* returns the named font, which must have been loaded by load(). if it wasn't, kiss3d's
+ * own font is used rather than stopping the game */
+pub fn font(name: &str) -> Rc<Font>
+{
+  LOADED_FONTS.with(|fonts| fonts.borrow().get(name).cloned()).unwrap_or_else(Font::default)
+}
+
+/* ------------------------------------------------------------------------------ */
+
+#[cfg(test)]
+mod tests
+{
+  use super::*;
+
+  #[test]
+  fn reads_the_built_in_font()
+  {
+    assert!(font_from_bytes(GAME_FONT, built_in(GAME_FONT).unwrap()).is_ok() == true);
+  }
+
+  #[test]
+  fn damaged_fonts_are_corrupt()
+  {
+    let font = built_in(GAME_FONT).unwrap();
+    let damaged: Vec<&[u8]> = vec!
+    [
+      &[],
+      &font[..4],
+      &font[..200],
+      &font[..font.len() / 2],
+      &font[..font.len() - 4],
+      b"something else entirely, not a font at all",
+      b"ttcf"
+    ];
+
+    for contents in damaged.iter()
+    {
+      match font_from_bytes(GAME_FONT, contents)
+      {
+        Err(AssetError::Corrupt(name)) => assert_eq!(name, GAME_FONT),
+        _ => panic!("a damaged font {} bytes long wasn't reported as corrupt", contents.len())
+      }
+    }
+  }
+}
@@ -0,0 +1,826 @@
+/* Space invaders in Rust
+ *
+ * The mothership: a boss built from sections that are shot away one at a time
+ *
+ * Game concept by Tomohiro Nishikado / Taito
+ * Rust code By Chris Williams <diodesign@tuta.io>
+ *
+ * Written for fun. See LICENSE.
+ *
+ */
+
+/* the mothership arrives from above the playfield and sweeps from side to side, bobbing as
+ * it goes. it's built from a core, two wings and two cannons, each with its own health. the
+ * core is shielded while either cannon still stands, and the fight is won when the core is
+ * destroyed. as it loses health, the mothership moves through three attack phases: dropping
+ * spreads of bombs from its cannons, then also launching escorts of small aliens that dive
+ * at the ships, then doing both faster. like the squadron, the mothership is pure game
+ * state: what it looks like on screen is up to the renderer */
+
+extern crate rand;
+
+use rand::Rng;
+
+use super::aliens::{ self, Alien, Frame, Pixel, Species, State };
+use super::bullet;
+use super::collision;
+use super::difficulty::Difficulty;
+use super::hero::HERO_Y_FLOOR;
+use super::prng::Prng;
+use super::save;
+use super::game::TICKS_PER_SECOND;
+
+pub const MOTHERSHIP_POINTS: i32 = 5000; /* for destroying the core, and with it the mothership */
+pub const SECTION_POINTS: i32 = 250;     /* for each of the other sections shot away */
+
+const START_Y: f32 = 110.0;     /* height of the center when the mothership appears, above the playfield */
+const CRUISE_Y: f32 = 62.0;     /* height of the center once it has arrived */
+const DESCENT: f32 = 0.25;      /* distance moved down each tick while arriving */
+const WALL: f32 = 70.0;         /* furthest the center goes either side of the middle of the playfield */
+const SWEEP_FACTOR: f32 = 4.0;  /* sideways speed, as a multiple of a full squadron's march speed */
+const FURY_SPEEDUP: f32 = 1.6;  /* sideways speed multiplier in the fury phase */
+const BOB_HEIGHT: f32 = 4.0;    /* how far the mothership bobs up and down */
+const BOB_TICKS: u32 = 4 * TICKS_PER_SECOND;   /* time for one bob up and down */
+const FRAME_TICKS: u32 = TICKS_PER_SECOND / 2; /* time between animation frames, which blink the lights */
+const SECTION_DEATH_TICKS: u32 = 3 * TICKS_PER_SECOND; /* time a section spends exploding before it's gone */
+const HIT_MARGIN: f32 = 2.0;    /* bullets this close to a section's edges still hit it */
+
+const BOMB_RADIUS: f32 = 4.0;
+const BOMB_COLOR_R: f32 = 1.0;
+const BOMB_COLOR_G: f32 = 0.3;
+const BOMB_COLOR_B: f32 = 0.1;
+pub const MAX_BOMBS: usize = 12; /* most bombs the mothership can have in play at once */
+const SPREAD_GAP: f32 = 12.0;    /* distance between bombs dropped together */
+
+const ESCORT_SPEED: f32 = 0.4;   /* escorts dive twice this far each tick */
+const ESCORTS_PER_LAUNCH: usize = 2;
+const MAX_ESCORTS: usize = 16;   /* most escorts launched in one fight */
+
+/* the sections the mothership is built from */
+#[derive(Clone, Copy, PartialEq)]
+pub enum Part
+{
+  Core,
+  LeftWing,
+  RightWing,
+  LeftCannon,
+  RightCannon
+}
+
+/* every section, in the order they're kept in the mothership */
+pub const PARTS: [Part; 5] = [ Part::Core, Part::LeftWing, Part::RightWing, Part::LeftCannon, Part::RightCannon ];
+
+/* the core, with a dome on top and a row of lights around its middle */
+const CORE_ART: [&str; 11] =
+[
+  "........########........",
+  "......############......",
+  ".....##############.....",
+  "..####################..",
+  ".######################.",
+  "########################",
+  "#o####o####oo####o####o#",
+  "########################",
+  ".######################.",
+  "..####....####....####..",
+  "...##......##......##..."
+];
+
+/* the wing on the left of the screen. the right wing is its mirror image */
+const WING_ART: [&str; 6] =
+[
+  ".........###########",
+  "......##############",
+  "...#################",
+  "####################",
+  "...#################",
+  "......##############"
+];
+
+const CANNON_ART: [&str; 6] =
+[
+  "######",
+  "######",
+  ".####.",
+  ".####.",
+  "..##..",
+  "..##.."
+];
+
+impl Part
+{
+  /* returns the section's name, as written in saved games */
+  fn name(&self) -> &'static str
+  {
+    match self
+    {
+      Part::Core => "core",
+      Part::LeftWing => "leftwing",
+      Part::RightWing => "rightwing",
+      Part::LeftCannon => "leftcannon",
+      Part::RightCannon => "rightcannon"
+    }
+  }
+
+  /* returns the rows of the section's design, from the top down, and whether to mirror it */
+  fn art(&self) -> (&'static [&'static str], bool)
+  {
+    match self
+    {
+      Part::Core => (&CORE_ART, false),
+      Part::LeftWing => (&WING_ART, false),
+      Part::RightWing => (&WING_ART, true),
+      Part::LeftCannon | Part::RightCannon => (&CANNON_ART, false)
+    }
+  }
+
+  /* returns where the section's center is, relative to the mothership's center.
+   * screen left is the positive x direction */
+  fn offset(&self) -> (f32, f32)
+  {
+    match self
+    {
+      Part::Core => (0.0, 0.0),
+      Part::LeftWing => (22.0, 0.0),
+      Part::RightWing => (-22.0, 0.0),
+      Part::LeftCannon => (18.0, -6.0),
+      Part::RightCannon => (-18.0, -6.0)
+    }
+  }
+
+  /* returns the number of hits it takes to destroy the section */
+  fn toughness(&self) -> i32
+  {
+    match self
+    {
+      Part::Core => 20,
+      Part::LeftWing | Part::RightWing => 8,
+      Part::LeftCannon | Part::RightCannon => 6
+    }
+  }
+
+  /* returns the (r, g, b) color of the section's hull */
+  fn color(&self) -> (f32, f32, f32)
+  {
+    match self
+    {
+      Part::Core => (0.9, 0.2, 0.2),
+      Part::LeftWing | Part::RightWing => (0.7, 0.3, 1.0),
+      Part::LeftCannon | Part::RightCannon => (0.7, 0.7, 0.7)
+    }
+  }
+
+  /* returns the width and height of the section */
+  fn size(&self) -> (f32, f32)
+  {
+    let (art, _) = self.art();
+    (art.iter().map(|row| row.len()).max().unwrap_or(0) as f32, art.len() as f32)
+  }
+
+  /* return the pixels making up the section, relative to the section's center. runs of
+   * hull in each row of the art become one pixel each. each light sits in front of the
+   * hull, and ducks behind it in the translated frame, so the lights blink */
+  pub fn design(&self) -> Vec<Pixel>
+  {
+    let (art, mirrored) = self.art();
+    let (width, height) = self.size();
+    let (r, g, b) = self.color();
+    let mut pixels = Vec::new();
+
+    for (row, line) in art.iter().enumerate()
+    {
+      let mut cells: Vec<char> = line.chars().collect();
+      if mirrored == true
+      {
+        cells.reverse();
+      }
+
+      /* art runs from screen left to right, which is the negative x direction */
+      let x = |across: f32| ((width - 1.0) / 2.0) - across;
+      let y = ((height - 1.0) / 2.0) - row as f32;
+
+      let mut across = 0;
+      while across < cells.len()
+      {
+        if cells[across] == '.'
+        {
+          across = across + 1;
+          continue;
+        }
+
+        let start = across;
+        while across < cells.len() && cells[across] != '.'
+        {
+          if cells[across] == 'o'
+          {
+            pixels.push(Pixel { width: 1.0, height: 1.0, depth: 1.0, x: x(across as f32), y: y, z: -1.0,
+                                tx: 0.0, ty: 0.0, tz: 2.0, r: 1.0, g: 1.0, b: 0.3 });
+          }
+          across = across + 1;
+        }
+
+        let run = (across - start) as f32;
+        pixels.push(Pixel { width: run, height: 1.0, depth: 1.0, x: x(start as f32 + ((run - 1.0) / 2.0)), y: y, z: 0.0,
+                            tx: 0.0, ty: 0.0, tz: 0.0, r: r, g: g, b: b });
+      }
+    }
+
+    return pixels;
+  }
+}
+
+/* one of the sections making up the mothership */
+pub struct Section
+{
+  part: Part,                /* which section this is */
+  health: i32,               /* hits left before it's destroyed */
+  state: State,              /* whether the section is intact, exploding, or gone */
+  death_ticks: Option<u32>   /* ticks since the section was destroyed */
+}
+
+impl Section
+{
+  /* returns which section this is */
+  pub fn get_part(&self) -> Part
+  {
+    self.part
+  }
+
+  /* returns whether the section is intact, exploding, or gone */
+  pub fn get_state(&self) -> State
+  {
+    self.state
+  }
+
+  /* returns ticks since the section was destroyed, or None if it's still intact */
+  pub fn ticks_since_death(&self) -> Option<u32>
+  {
+    self.death_ticks
+  }
+
+  /* blow up the section */
+  fn die(&mut self)
+  {
+    if self.state == State::Alive
+    {
+      self.health = 0;
+      self.state = State::Dying;
+      self.death_ticks = Some(0);
+    }
+  }
+}
+
+/* the attack phases the mothership goes through as it loses health */
+#[derive(Clone, Copy, PartialEq)]
+pub enum Phase
+{
+  Spread, /* drops spreads of bombs */
+  Escort, /* takes turns dropping spreads and launching escorts */
+  Fury    /* moves faster, and attacks more often with wider spreads */
+}
+
+/* what a bullet hit, if it hit the mothership or one of its escorts */
+#[derive(Clone, Copy, PartialEq)]
+pub enum Hit
+{
+  Shielded,   /* the core, while a cannon still stands to shield it */
+  Damaged,    /* a section, which took damage but is still standing */
+  Section,    /* a section, which was destroyed */
+  Mothership, /* the core, which was destroyed along with the whole mothership */
+  Escort(Species) /* an escort of the given species, which was shot down */
+}
+
+/* the boss, its bombs and the escorts it launches */
+pub struct Mothership
+{
+  x: f32, y: f32,            /* center of the mothership on the playfield */
+  heading: f32,              /* 1.0 when moving to the left of the screen, -1.0 for the right */
+  ticks: u32,                /* ticks since the mothership arrived, for bobbing */
+  frame: Frame,              /* the animation frame on show */
+  frame_ticks: u32,          /* ticks since we last switched animation frame */
+  reload_ticks: u32,         /* ticks until the mothership can attack again */
+  attacks: u32,              /* attacks made so far, to take turns between them */
+  rng: Prng,                 /* decides when and from where to attack */
+  sections: Vec<Section>,    /* in the order of PARTS */
+  pub bombs: Vec<bullet::Bullet>,
+  pub escorts: Vec<Alien>    /* every escort launched so far, alive or dead, in launch order */
+}
+
+impl Mothership
+{
+  /* create the mothership above the playfield, ready to fly in
+   * => seed = starting point for the mothership's random decisions */
+  pub fn new(seed: u64) -> Mothership
+  {
+    Mothership
+    {
+      x: 0.0, y: START_Y,
+      heading: 1.0,
+      ticks: 0,
+      frame: Frame::Base,
+      frame_ticks: 0,
+      reload_ticks: 0,
+      attacks: 0,
+      rng: Prng::new(seed),
+      sections: PARTS.iter().map(|part| Section
+      {
+        part: *part,
+        health: part.toughness(),
+        state: State::Alive,
+        death_ticks: None
+      }).collect(),
+      bombs: Vec::new(),
+      escorts: Vec::new()
+    }
+  }
+
+  /* returns the sections of the mothership, for drawing */
+  pub fn get_sections(&self) -> &Vec<Section>
+  {
+    &self.sections
+  }
+
+  /* returns (x, y, z) coords of the given section's center */
+  pub fn section_coords(&self, section: &Section) -> (f32, f32, f32)
+  {
+    let (x, y) = section.part.offset();
+    (self.x + x, self.y + y, 0.0)
+  }
+
+  /* returns the animation frame on show */
+  pub fn get_frame(&self) -> Frame
+  {
+    self.frame
+  }
+
+  /* returns the fraction of the mothership's health left, from 1.0 down to 0.0 */
+  pub fn health(&self) -> f32
+  {
+    let total: i32 = PARTS.iter().map(|p| p.toughness()).sum();
+    let left: i32 = self.sections.iter().map(|s| s.health).sum();
+    left as f32 / total as f32
+  }
+
+  /* returns the mothership's attack phase, which depends on how much health it has left */
+  pub fn phase(&self) -> Phase
+  {
+    let health = self.health();
+    if health > 2.0 / 3.0
+    {
+      return Phase::Spread;
+    }
+    if health > 1.0 / 3.0
+    {
+      return Phase::Escort;
+    }
+    return Phase::Fury;
+  }
+
+  /* returns the section for the given part */
+  fn section(&self, part: Part) -> &Section
+  {
+    &self.sections[PARTS.iter().position(|p| *p == part).unwrap_or(0)]
+  }
+
+  /* returns the positions of everything that can be shot: the escorts, and the sections
+   * that can take damage */
+  pub fn targets(&self) -> Vec<(f32, f32)>
+  {
+    let shielded = self.shielded();
+    let mut targets: Vec<(f32, f32)> = self.sections.iter()
+                                       .filter(|s| s.state == State::Alive && (s.part != Part::Core || shielded == false))
+                                       .map(|s| { let (x, y, _) = self.section_coords(s); (x, y) })
+                                       .collect();
+    targets.extend(self.escorts.iter().filter(|e| e.get_state() == State::Alive).map(|e| { let (x, y, _) = e.get_coords(); (x, y) }));
+    return targets;
+  }
+
+  /* returns true once the core has been destroyed */
+  pub fn defeated(&self) -> bool
+  {
+    self.section(Part::Core).state != State::Alive
+  }
+
+  /* returns true once the mothership and its escorts have finished exploding */
+  pub fn finished(&self) -> bool
+  {
+    self.sections.iter().all(|s| s.state == State::Dead) && self.escorts.iter().all(|e| e.get_state() == State::Dead)
+  }
+
+  /* returns true while the core is shielded by a cannon */
+  fn shielded(&self) -> bool
+  {
+    self.section(Part::LeftCannon).state == State::Alive || self.section(Part::RightCannon).state == State::Alive
+  }
+
+  /* returns true once the mothership has flown into place and can fight */
+  fn arrived(&self) -> bool
+  {
+    self.y <= CRUISE_Y
+  }
+
+  /* call for each simulation tick to move the mothership, its bombs and its escorts
+   * => difficulty = sets how fast the mothership moves */
+  pub fn animate(&mut self, difficulty: &Difficulty)
+  {
+    for bomb in self.bombs.iter_mut()
+    {
+      bomb.animate();
+    }
+
+    /* escorts dive until they hit the ground, and blow up there */
+    for escort in self.escorts.iter_mut()
+    {
+      escort.animate(ESCORT_SPEED);
+      let (_, y, _) = escort.get_coords();
+      if y < HERO_Y_FLOOR
+      {
+        escort.die();
+      }
+    }
+
+    for section in self.sections.iter_mut().filter(|s| s.state == State::Dying)
+    {
+      let ticks = section.death_ticks.unwrap_or(0) + 1;
+      section.death_ticks = Some(ticks);
+      if ticks > SECTION_DEATH_TICKS
+      {
+        section.state = State::Dead;
+      }
+    }
+
+    if self.defeated() == true
+    {
+      return;
+    }
+
+    self.frame_ticks = self.frame_ticks + 1;
+    if self.frame_ticks > FRAME_TICKS
+    {
+      self.frame = match self.frame
+      {
+        Frame::Base => Frame::Translated,
+        Frame::Translated => Frame::Base
+      };
+      self.frame_ticks = 0;
+    }
+
+    if self.reload_ticks > 0
+    {
+      self.reload_ticks = self.reload_ticks - 1;
+    }
+
+    /* fly down into place before doing anything else */
+    if self.arrived() == false
+    {
+      self.y = (self.y - DESCENT).max(CRUISE_Y);
+      return;
+    }
+
+    /* sweep from side to side, turning at the walls */
+    let mut speed = difficulty.march_step * SWEEP_FACTOR;
+    if self.phase() == Phase::Fury
+    {
+      speed = speed * FURY_SPEEDUP;
+    }
+    self.x = self.x + (self.heading * speed);
+    if (self.heading > 0.0 && self.x > WALL) || (self.heading < 0.0 && self.x < 0.0 - WALL)
+    {
+      self.heading = 0.0 - self.heading;
+    }
+
+    /* bob up and down, starting and ending each bob at the cruising height */
+    self.ticks = self.ticks.wrapping_add(1);
+    let quarters = ((self.ticks % BOB_TICKS) as f32 * 4.0) / BOB_TICKS as f32;
+    let bob = match quarters
+    {
+      q if q < 1.0 => q,
+      q if q < 3.0 => 2.0 - q,
+      q => q - 4.0
+    };
+    self.y = CRUISE_Y + (bob * BOB_HEIGHT);
+  }
+
+  /* attack if the mothership has reloaded since its last attack
+   * => difficulty = sets how fast the bombs fall
+   * <= true if the mothership attacked, false if not */
+  pub fn attack(&mut self, difficulty: &Difficulty) -> bool
+  {
+    if self.arrived() == false || self.defeated() == true || self.reload_ticks > 0
+    {
+      return false;
+    }
+
+    let phase = self.phase();
+    self.attacks = self.attacks + 1;
+    let (spread, reload) = match phase
+    {
+      Phase::Spread => (3, 5 * TICKS_PER_SECOND / 2),
+      Phase::Escort => (3, 2 * TICKS_PER_SECOND),
+      Phase::Fury => (5, 3 * TICKS_PER_SECOND / 2)
+    };
+
+    match phase != Phase::Spread && self.attacks % 2 == 0 && self.escorts.len() < MAX_ESCORTS
+    {
+      true => self.launch(),
+      false => self.drop_spread(spread, difficulty.bomb_speed)
+    }
+
+    /* vary the time between attacks by up to a second, so they can't be learned by rote */
+    self.reload_ticks = reload + (self.rng.gen::<u32>() % TICKS_PER_SECOND);
+    return true;
+  }
+
+  /* drop a spread of bombs below each cannon still standing, or below the core if
+   * there aren't any
+   * => count = number of bombs in each spread
+   *    speed = distance the bombs fall each tick */
+  fn drop_spread(&mut self, count: usize, speed: f32)
+  {
+    let mut guns: Vec<(f32, f32)> = self.sections.iter()
+                                    .filter(|s| s.state == State::Alive && (s.part == Part::LeftCannon || s.part == Part::RightCannon))
+                                    .map(|s| s.part.offset())
+                                    .collect();
+    if guns.is_empty() == true
+    {
+      guns.push(Part::Core.offset());
+    }
+
+    for (gun_x, gun_y) in guns
+    {
+      let bottom = self.y + gun_y - (Part::LeftCannon.size().1 / 2.0);
+      for index in 0..count
+      {
+        if self.bombs.len() >= MAX_BOMBS
+        {
+          return;
+        }
+
+        let across = (index as f32 - ((count - 1) as f32 / 2.0)) * SPREAD_GAP;
+        self.bombs.push(bullet::Bullet::new(self.x + gun_x + across, bottom, BOMB_RADIUS,
+                                            BOMB_COLOR_R, BOMB_COLOR_G, BOMB_COLOR_B,
+                                            0.0 - speed));
+      }
+    }
+  }
+
+  /* send escorts diving at the ships from random places along the underside */
+  fn launch(&mut self)
+  {
+    let (width, _) = Part::Core.size();
+    let span = (Part::LeftWing.offset().0 * 2.0) + width;
+    for _ in 0..ESCORTS_PER_LAUNCH
+    {
+      if self.escorts.len() >= MAX_ESCORTS
+      {
+        return;
+      }
+
+      let across = ((self.rng.gen::<u32>() % 1000) as f32 / 1000.0 - 0.5) * span;
+      let species = aliens::SPECIES[self.rng.gen::<usize>() % aliens::SPECIES.len()];
+      self.escorts.push(Alien::diver(self.x + across, self.y + Part::LeftCannon.offset().1, species));
+    }
+  }
+
+  /* check whether the thing at x, y hit the mothership or one of its escorts, and if so,
+   * damage or blow up whatever it hit
+   * => x, y = where the thing is
+   *    damage = hits' worth of damage the thing does
+   * <= what was hit, or None for a miss */
+  pub fn hit(&mut self, x: f32, y: f32, damage: u32) -> Option<Hit>
+  {
+    /* escorts fly below the mothership, so they get in the way first */
+    if let Some(species) = self.escort_hit(x, y)
+    {
+      return Some(Hit::Escort(species));
+    }
+
+    let shielded = self.shielded();
+    let (ship_x, ship_y) = (self.x, self.y);
+    let index = self.sections.iter().position(|s|
+    {
+      let (width, height) = s.part.size();
+      let (offset_x, offset_y) = s.part.offset();
+      s.state == State::Alive &&
+      (x - (ship_x + offset_x)).abs() < (width / 2.0) + HIT_MARGIN &&
+      (y - (ship_y + offset_y)).abs() < (height / 2.0) + HIT_MARGIN
+    });
+
+    let section = match index
+    {
+      Some(index) => &mut self.sections[index],
+      None => return None
+    };
+
+    if section.part == Part::Core && shielded == true
+    {
+      return Some(Hit::Shielded);
+    }
+
+    section.health = (section.health - damage as i32).max(0);
+    if section.health > 0
+    {
+      return Some(Hit::Damaged);
+    }
+
+    /* losing the core takes the whole mothership with it */
+    if section.part == Part::Core
+    {
+      for section in self.sections.iter_mut()
+      {
+        section.die();
+      }
+      return Some(Hit::Mothership);
+    }
+
+    section.die();
+    return Some(Hit::Section);
+  }
+
+  /* check to see if any alive escorts collide with the thing at x, y. if one does,
+   * then blow up the escort and return a hit */
+  pub fn collision(&mut self, x: f32, y: f32) -> collision::CollisionOutcome
+  {
+    match self.escort_hit(x, y)
+    {
+      Some(_) => collision::CollisionOutcome::Hit,
+      None => collision::CollisionOutcome::Miss
+    }
+  }
+
+  /* like collision(), but return the species of the escort that was blown up, or None
+   * for a miss */
+  fn escort_hit(&mut self, x: f32, y: f32) -> Option<Species>
+  {
+    for escort in self.escorts.iter_mut().filter(|e| e.get_state() == State::Alive)
+    {
+      let (escort_x, escort_y, _) = escort.get_coords();
+      let scenario = collision::Collision
+      {
+        a: collision::CollisionObject{ x: x, y: y },
+        b: collision::CollisionObject{ x: escort_x, y: escort_y }
+      };
+
+      if collision::check(scenario) == collision::CollisionOutcome::Hit
+      {
+        escort.die();
+        return Some(escort.get_species());
+      }
+    }
+
+    return None;
+  }
+
+  /* remove bombs that have fallen past the ships
+   * <= number of bombs removed */
+  pub fn remove_fallen_bombs(&mut self) -> u32
+  {
+    let before = self.bombs.len();
+    self.bombs.retain(|b| b.get_coords().1 >= HERO_Y_FLOOR);
+    (before - self.bombs.len()) as u32
+  }
+
+  /* write the mothership, its bombs and its escorts to a saved game */
+  pub fn save(&self, writer: &mut save::Writer)
+  {
+    writer.label("mothership");
+    writer.value(self.x);
+    writer.value(self.y);
+    writer.value(self.heading);
+    writer.value(self.ticks);
+    writer.value(match self.frame
+    {
+      Frame::Base => "base",
+      Frame::Translated => "translated"
+    });
+    writer.value(self.frame_ticks);
+    writer.value(self.reload_ticks);
+    writer.value(self.attacks);
+    writer.value(self.rng.get_state());
+
+    for section in self.sections.iter()
+    {
+      writer.label("section");
+      writer.value(section.part.name());
+      writer.value(section.health);
+      writer.value(state_name(section.state));
+      writer.option(section.death_ticks);
+    }
+
+    writer.label("bombs");
+    writer.value(self.bombs.len());
+    for bomb in self.bombs.iter()
+    {
+      writer.label("bomb");
+      bomb.save(writer);
+    }
+
+    writer.label("escorts");
+    writer.value(self.escorts.len());
+    for escort in self.escorts.iter()
+    {
+      writer.label("escort");
+      writer.value(escort.get_species().letter());
+      escort.save(writer);
+    }
+  }
+
+  /* recreate the mothership, its bombs and its escorts from a saved game */
+  pub fn restore(reader: &mut save::Reader) -> Result<Mothership, save::SaveError>
+  {
+    reader.label("mothership")?;
+    let (x, y, heading, ticks) = (reader.value()?, reader.value()?, reader.value()?, reader.value()?);
+    let frame = match reader.value::<String>()?.as_str()
+    {
+      "base" => Frame::Base,
+      "translated" => Frame::Translated,
+      other => return Err(reader.unexpected("animation frame", other))
+    };
+    let (frame_ticks, reload_ticks, attacks) = (reader.value()?, reader.value()?, reader.value()?);
+    let rng = Prng::new(reader.value()?);
+
+    let mut sections = Vec::with_capacity(PARTS.len());
+    for part in PARTS.iter()
+    {
+      reader.label("section")?;
+      let name = reader.value::<String>()?;
+      if name != part.name()
+      {
+        return Err(reader.unexpected("mothership section", name.as_str()));
+      }
+      let health = reader.value()?;
+      let state = reader.value::<String>()?;
+      sections.push(Section
+      {
+        part: *part,
+        health: health,
+        state: state_from_name(reader, state.as_str())?,
+        death_ticks: reader.option()?
+      });
+    }
+
+    reader.label("bombs")?;
+    let count: usize = reader.value()?;
+    let mut bombs = Vec::with_capacity(count);
+    for _ in 0..count
+    {
+      reader.label("bomb")?;
+      bombs.push(bullet::Bullet::restore(reader)?);
+    }
+
+    reader.label("escorts")?;
+    let count: usize = reader.value()?;
+    let mut escorts = Vec::with_capacity(count);
+    for _ in 0..count
+    {
+      reader.label("escort")?;
+      let letter = reader.value::<char>()?;
+      let species = match Species::from_letter(letter)
+      {
+        Some(species) => species,
+        None => return Err(reader.unexpected("species", letter.to_string().as_str()))
+      };
+      let mut escort = Alien::restore(reader)?;
+      escort.set_species(species);
+      escorts.push(escort);
+    }
+
+    Ok(Mothership
+    {
+      x: x, y: y,
+      heading: heading,
+      ticks: ticks,
+      frame: frame,
+      frame_ticks: frame_ticks,
+      reload_ticks: reload_ticks,
+      attacks: attacks,
+      rng: rng,
+      sections: sections,
+      bombs: bombs,
+      escorts: escorts
+    })
+  }
+}
+
+/* returns a section's state, as written in saved games */
+fn state_name(state: State) -> &'static str
+{
+  match state
+  {
+    State::Alive => "alive",
+    State::Dying => "dying",
+    State::Dead => "dead"
+  }
+}
+
+/* returns the section state with the given name, read from a saved game */
+fn state_from_name(reader: &save::Reader, name: &str) -> Result<State, save::SaveError>
+{
+  match name
+  {
+    "alive" => Ok(State::Alive),
+    "dying" => Ok(State::Dying),
+    "dead" => Ok(State::Dead),
+    other => Err(reader.unexpected("mothership section state", other))
+  }
+}
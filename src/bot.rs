@@ -0,0 +1,226 @@
+/* Space invaders in Rust
+ *
+ * Autopilot: a computer player for the hero's ship
+ *
+ * Game concept by Tomohiro Nishikado / Taito
+ * Rust code By Chris Williams <diodesign@tuta.io>
+ *
+ * Written for fun. See LICENSE.
+ *
+ */
+
+use super::aliens;
+use super::hero;
+use super::game::{ Game, Playfield, LevelOutcome };
+use super::input::Controls;
+
+const DANGER_WIDTH: f32 = 9.0;    /* bombs closer than this on the x-axis are a threat */
+const DANGER_TICKS: f32 = 40.0;   /* bombs due to arrive sooner than this are a threat */
+const DANGER_BELOW: f32 = 6.0;    /* bombs can still hit us until they're this far below the ship */
+const DODGE_RANGE: f32 = 100.0;   /* furthest the bot will look for somewhere safe from bombs */
+const AIM_TOLERANCE: f32 = 1.5;   /* how close to the aiming point we need to be to fire */
+const COLUMN_WIDTH: f32 = 1.0;    /* aliens closer than this on the x-axis share a column */
+const CAPSULE_REACH: f32 = 60.0;  /* go after capsules that have fallen to within this height of the ship */
+
+pub const AUTOPLAY_MAX_LEVELS: i32 = 100; /* stop a headless game that's going on forever */
+
+/* the bot only looks at what a human player can see on screen: where the aliens are,
+ * where their bombs are, and where its own ship is. it remembers where the squadron was
+ * on the last tick so it can work out which way it's moving, as a human would */
+pub struct Bot
+{
+  hero: usize,               /* which of the playfield's ships this bot is flying */
+  last_squadron_x: Option<f32> /* average x position of the alive aliens on the last tick */
+}
+
+impl Bot
+{
+  /* create a bot to fly the given ship in the playfield */
+  pub fn new(hero: usize) -> Bot
+  {
+    Bot
+    {
+      hero: hero,
+      last_squadron_x: None
+    }
+  }
+
+  /* decide what to hold down this tick
+   * => playfield = the current state of play
+   * <= controls the bot wants to press */
+  pub fn think(&mut self, playfield: &Playfield) -> Controls
+  {
+    let mut controls = Controls::default();
+    let ship = match playfield.heroes.get(self.hero)
+    {
+      Some(ship) => ship,
+      None => return controls
+    };
+
+    let (hero_x, hero_y, _) = ship.get_coords();
+
+    /* gather up the positions of the aliens still in the fight, and the parts of the
+     * mothership and its escorts that can be shot, if there is one */
+    let mut alive: Vec<(f32, f32)> = playfield.aliens.get_squadron().iter()
+                                     .filter(|a| a.get_state() == aliens::State::Alive)
+                                     .map(|a| { let (x, y, _) = a.get_coords(); (x, y) })
+                                     .collect();
+    if let Some(boss) = playfield.boss.as_ref()
+    {
+      alive.extend(boss.targets());
+    }
+
+    /* work out how fast and which way the squadron is drifting */
+    let squadron_x = match alive.len()
+    {
+      0 => None,
+      n => Some(alive.iter().map(|a| a.0).sum::<f32>() / n as f32)
+    };
+    let drift = match (squadron_x, self.last_squadron_x)
+    {
+      (Some(now), Some(before)) => now - before,
+      _ => 0.0
+    };
+    self.last_squadron_x = squadron_x;
+
+    if ship.state != hero::State::Alive
+    {
+      return controls;
+    }
+
+    /* with inertia, the ship carries on for a bit after it's let go, so steer by where it
+     * would coast to a stop */
+    let accel = playfield.difficulty.ship_accel;
+    let velocity = ship.get_velocity();
+    let stop_x = match accel > 0.0
+    {
+      true => hero_x + (velocity * velocity.abs() / (2.0 * accel)),
+      false => hero_x
+    };
+
+    /* getting out of the way of bombs comes before everything else. bombs can fall
+     * side by side, so head for the nearest spot that's clear of all of them rather than
+     * simply away from the closest */
+    let threats: Vec<f32> = playfield.bombs().into_iter()
+                            .map(|bomb| { let (x, y, _) = bomb.get_coords(); (x, y, (y - hero_y) / (0.0 - bomb.get_speed()).max(1.0)) })
+                            .filter(|(_, y, ticks)| *y > hero_y - DANGER_BELOW && *ticks < DANGER_TICKS)
+                            .map(|(x, _, _)| x)
+                            .collect();
+    let safe = |x: f32| threats.iter().all(|bomb_x| (bomb_x - x).abs() >= DANGER_WIDTH);
+    if safe(hero_x) == false || safe(stop_x) == false
+    {
+      /* already on the way somewhere safe, so keep going */
+      if safe(stop_x) == true
+      {
+        controls.left = velocity > 0.0;
+        controls.right = velocity < 0.0;
+        return controls;
+      }
+
+      /* remember, screen left is the positive x direction. the ship can't go past the walls */
+      let step = playfield.difficulty.ship_speed;
+      let mut distance = step;
+      loop
+      {
+        if safe(stop_x + distance) == true && stop_x + distance <= aliens::WALL_LEFT
+        {
+          controls.left = true;
+          break;
+        }
+        if (safe(stop_x - distance) == true && stop_x - distance >= aliens::WALL_RIGHT) || distance > DODGE_RANGE
+        {
+          controls.right = true;
+          break;
+        }
+        distance = distance + step;
+      }
+      return controls;
+    }
+
+    /* catch the nearest capsule on its way down, if there is one */
+    let capsule = playfield.powerups.capsules.iter()
+                  .map(|c| c.get_coords())
+                  .filter(|(_, y, _)| *y < hero_y + CAPSULE_REACH)
+                  .min_by(|a, b| (a.0 - hero_x).abs().partial_cmp(&(b.0 - hero_x).abs()).unwrap());
+    if let Some((capsule_x, _, _)) = capsule
+    {
+      if capsule_x > stop_x + AIM_TOLERANCE
+      {
+        controls.left = true;
+      }
+      else if capsule_x < stop_x - AIM_TOLERANCE
+      {
+        controls.right = true;
+      }
+      controls.fire = true;
+      return controls;
+    }
+
+    /* pick the lowest alien in the column nearest our ship */
+    let column_x = match alive.iter().map(|a| a.0).min_by(|a, b| (a - hero_x).abs().partial_cmp(&(b - hero_x).abs()).unwrap())
+    {
+      Some(x) => x,
+      None => return controls /* nothing left to shoot */
+    };
+    let target = alive.iter().filter(|a| (a.0 - column_x).abs() < COLUMN_WIDTH)
+                             .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap())
+                             .cloned()
+                             .unwrap();
+
+    /* aim where the target will be by the time a bullet reaches it, or as near as the
+     * walls let us get */
+    let flight_ticks = (target.1 - hero_y) / playfield.difficulty.bullet_speed;
+    let aim_x = (target.0 + (drift * flight_ticks)).max(aliens::WALL_RIGHT).min(aliens::WALL_LEFT);
+
+    /* the target drifts with the squadron, so with inertia, steer by where the ship would
+     * come to a stop alongside it. fire whenever the ship is lined up, even on the move */
+    let chase_x = match accel > 0.0
+    {
+      true => hero_x + ((velocity - drift) * (velocity - drift).abs() / (2.0 * accel)),
+      false => hero_x
+    };
+    if aim_x > chase_x + AIM_TOLERANCE
+    {
+      controls.left = true;
+    }
+    else if aim_x < chase_x - AIM_TOLERANCE
+    {
+      controls.right = true;
+    }
+    controls.fire = (aim_x - hero_x).abs() <= AIM_TOLERANCE;
+
+    return controls;
+  }
+}
+
+/* ------------------------------------------------------------------------------ */
+
+/* play a whole single-player game with the bot at the controls, without drawing anything.
+ * this is handy for playing lots of games quickly to see how balanced the game is
+ * => state = the new game to play
+ * <= final state of the game */
+pub fn autoplay(mut state: Game) -> Game
+{
+  let mut bot = Bot::new(0);
+
+  while state.level < AUTOPLAY_MAX_LEVELS
+  {
+    let mut playfield = Playfield::new(&mut state);
+    let outcome = loop
+    {
+      let controls = [ bot.think(&playfield) ];
+      if let Some(outcome) = playfield.step(&mut state, &controls)
+      {
+        break outcome;
+      }
+    };
+
+    match outcome
+    {
+      LevelOutcome::Victory => {},
+      _ => break
+    }
+  }
+
+  return state;
+}
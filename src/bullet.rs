@@ -9,63 +9,89 @@
  *
  */
 
-extern crate glfw;
-extern crate kiss3d;
-extern crate nalgebra as na;
-extern crate rand;
-
-use na::Translation3;
-use kiss3d::window::Window;
-use kiss3d::scene::SceneNode;
+use super::save;
 
 pub struct Bullet
 {
   x: f32, y: f32, z: f32,
-  bullet: SceneNode,
+  radius: f32,            /* size of the bullet's sphere */
+  r: f32, g: f32, b: f32, /* color of the bullet's sphere */
   speed: f32
 }
 
 impl Bullet
 {
-  /* create a new bullet
-   * => window  = graphics context in which to create the bullet 
-   *    x, y    = coords on where to start the bullet (z = 0.0)
+  /* create a new bullet. this just describes the bullet: it's up to the renderer to draw it
+   * => x, y    = coords on where to start the bullet (z = 0.0)
    *    rad     = radius of the bullet's sphere
    *    r, g, b = color of the bullet,
    *    speed   = rate at which bullet will move in y direction
    */
-  pub fn new(window: &mut Window, x: f32, y: f32, rad: f32, r: f32, g: f32, b: f32, speed: f32) -> Bullet
+  pub fn new(x: f32, y: f32, rad: f32, r: f32, g: f32, b: f32, speed: f32) -> Bullet
   {
-    let mut shell = Bullet
+    Bullet
     {
       x: x, y: y, z: 0.0,
-      bullet: window.add_sphere(rad),
+      radius: rad,
+      r: r, g: g, b: b,
       speed: speed
-    };
-
-    shell.bullet.append_translation(&Translation3::new(shell.x, shell.y, shell.z));
-    shell.bullet.set_color(r, g, b); 
-
-    return shell;
+    }
   }
 
-  /* remove the bullet's sphere from the screen */
-  pub fn destroy(&mut self)
+  /* move the bullet along one tick */
+  pub fn animate(&mut self)
   {
-    self.bullet.unlink();
+    self.y = self.y + self.speed;
   }
 
-  /* if a bullet is in play then move it. if the ship is blowing up, then blow it up some more */
-  pub fn animate(&mut self)
+  /* move the bullet sideways by the given distance */
+  pub fn slide(&mut self, distance: f32)
   {
-    self.y = self.y + self.speed;
-    self.bullet.append_translation(&Translation3::new(0.0, self.speed, 0.0));
+    self.x = self.x + distance;
   }
 
-  /* returns Some(x, y, z) coords of active bullet, or None if no bullet */ 
+  /* returns (x, y, z) coords of the bullet */
   pub fn get_coords(&self) -> (f32, f32, f32)
   {
     return (self.x, self.y, self.z);
   }
-}
 
+  /* returns how far the bullet moves along the y-axis each tick */
+  pub fn get_speed(&self) -> f32
+  {
+    self.speed
+  }
+
+  /* returns the radius of the bullet's sphere */
+  pub fn get_radius(&self) -> f32
+  {
+    self.radius
+  }
+
+  /* returns the (r, g, b) color of the bullet's sphere */
+  pub fn get_color(&self) -> (f32, f32, f32)
+  {
+    (self.r, self.g, self.b)
+  }
+
+  /* add the bullet's values to the current line of a saved game */
+  pub fn save(&self, writer: &mut save::Writer)
+  {
+    for value in [ self.x, self.y, self.z, self.radius, self.r, self.g, self.b, self.speed ].iter()
+    {
+      writer.value(value);
+    }
+  }
+
+  /* recreate a bullet from a saved game */
+  pub fn restore(reader: &mut save::Reader) -> Result<Bullet, save::SaveError>
+  {
+    Ok(Bullet
+    {
+      x: reader.value()?, y: reader.value()?, z: reader.value()?,
+      radius: reader.value()?,
+      r: reader.value()?, g: reader.value()?, b: reader.value()?,
+      speed: reader.value()?
+    })
+  }
+}
@@ -0,0 +1,96 @@
+/* Space invaders in Rust
+ *
+ * Versus mode: a second player commanding the alien squadron
+ *
+ * Game concept by Tomohiro Nishikado / Taito
+ * Rust code By Chris Williams <diodesign@tuta.io>
+ *
+ * Written for fun. See LICENSE.
+ *
+ */
+
+use super::aliens;
+use super::save;
+
+/* the alien player picks a column of the squadron to bomb from. the renderer draws a
+ * marker just beneath the lowest alive alien in the selected column so both players
+ * can see it */
+pub struct Commander
+{
+  column: i32 /* formation column currently selected to drop bombs */
+}
+
+impl Commander
+{
+  /* create a commander with the middle column of the squadron selected */
+  pub fn new(aliens: &aliens::Aliens) -> Commander
+  {
+    let columns = aliens.alive_columns();
+    Commander
+    {
+      column: match columns.len()
+      {
+        0 => 0,
+        n => columns[n / 2]
+      }
+    }
+  }
+
+  /* select the next alive column to the left of the screen. the camera looks along
+   * the positive z-axis, so screen left is the positive x direction, ie: higher columns */
+  pub fn select_left(&mut self, aliens: &aliens::Aliens)
+  {
+    if let Some(column) = aliens.alive_columns().into_iter().find(|c| *c > self.column)
+    {
+      self.column = column;
+    }
+  }
+
+  /* select the next alive column to the right of the screen */
+  pub fn select_right(&mut self, aliens: &aliens::Aliens)
+  {
+    if let Some(column) = aliens.alive_columns().into_iter().rev().find(|c| *c < self.column)
+    {
+      self.column = column;
+    }
+  }
+
+  /* drop a bomb from the selected column, if the squadron is able to
+   * => speed = distance the bomb falls each tick
+   * <= true if a bomb was dropped */
+  pub fn fire(&mut self, aliens: &mut aliens::Aliens, speed: f32) -> bool
+  {
+    return aliens.fire_column(self.column, speed);
+  }
+
+  /* move to the nearest alive column if the selected one has been wiped out */
+  pub fn animate(&mut self, aliens: &aliens::Aliens)
+  {
+    if aliens.column_coords(self.column).is_none() == true
+    {
+      let columns = aliens.alive_columns();
+      if let Some(nearest) = columns.iter().min_by_key(|c| (**c - self.column).abs())
+      {
+        self.column = *nearest;
+      }
+    }
+  }
+
+  /* returns the formation column currently selected */
+  pub fn get_column(&self) -> i32
+  {
+    self.column
+  }
+
+  /* add the commander to the current line of a saved game */
+  pub fn save(&self, writer: &mut save::Writer)
+  {
+    writer.value(self.column);
+  }
+
+  /* recreate a commander from a saved game */
+  pub fn restore(reader: &mut save::Reader) -> Result<Commander, save::SaveError>
+  {
+    Ok(Commander { column: reader.value()? })
+  }
+}
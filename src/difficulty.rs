@@ -0,0 +1,242 @@
+/* Space invaders in Rust
+ *
+ * Difficulty profiles: how fast everything moves and how many lives a game starts with
+ *
+ * Game concept by Tomohiro Nishikado / Taito
+ * Rust code By Chris Williams <diodesign@tuta.io>
+ *
+ * Written for fun. See LICENSE.
+ *
+ */
+
+/* a game's difficulty is picked when it starts and kept with it, in saved games,
+ * recordings and network games, so everyone plays by the same rules. the named profiles
+ * can be tweaked from the command line, which turns them into a custom profile so scores
+ * made with the tweaks aren't mixed up with scores made without */
+
+use super::save;
+
+/* the named profiles, plus custom for any other settings */
+#[derive(Clone, Copy, PartialEq)]
+pub enum Profile
+{
+  Easy,   /* slower aliens and bombs, and more lives */
+  Normal, /* how the game has always played */
+  Arcade, /* closer to the original: a rippling squadron that gets frantic as it thins out */
+  Insane, /* fast from the start, with one life */
+  Custom  /* tweaked on the command line */
+}
+
+/* how the squadron marches across the screen */
+#[derive(Clone, Copy, PartialEq)]
+pub enum March
+{
+  Smooth,  /* every alien moves a little each tick, in unison */
+  Classic  /* one alien moves each tick, from the bottom left to the top right, like the
+            * original. the squadron ripples, and speeds up by itself as it thins out */
+}
+
+impl March
+{
+  /* returns the march's name, as used on the command line and in files */
+  pub fn name(&self) -> &'static str
+  {
+    match self
+    {
+      March::Smooth => "smooth",
+      March::Classic => "classic"
+    }
+  }
+
+  /* returns the march with the given name, or None if there isn't one */
+  pub fn from_name(name: &str) -> Option<March>
+  {
+    match name
+    {
+      "smooth" => Some(March::Smooth),
+      "classic" => Some(March::Classic),
+      _ => None
+    }
+  }
+}
+
+/* the profiles to choose from on the title screen, in order */
+pub const PROFILES: [Profile; 4] = [ Profile::Easy, Profile::Normal, Profile::Arcade, Profile::Insane ];
+
+impl Profile
+{
+  /* returns the profile's name, as used on the command line and in files */
+  pub fn name(&self) -> &'static str
+  {
+    match self
+    {
+      Profile::Easy => "easy",
+      Profile::Normal => "normal",
+      Profile::Arcade => "arcade",
+      Profile::Insane => "insane",
+      Profile::Custom => "custom"
+    }
+  }
+
+  /* returns the profile's name as shown on screen */
+  pub fn title(&self) -> &'static str
+  {
+    match self
+    {
+      Profile::Easy => "Easy",
+      Profile::Normal => "Normal",
+      Profile::Arcade => "Arcade",
+      Profile::Insane => "Insane",
+      Profile::Custom => "Custom"
+    }
+  }
+
+  /* returns the profile with the given name, or None if there isn't one */
+  pub fn from_name(name: &str) -> Option<Profile>
+  {
+    match name
+    {
+      "easy" => Some(Profile::Easy),
+      "normal" => Some(Profile::Normal),
+      "arcade" => Some(Profile::Arcade),
+      "insane" => Some(Profile::Insane),
+      "custom" => Some(Profile::Custom),
+      _ => None
+    }
+  }
+}
+
+/* the settings that make a game harder or easier. speeds are in 3d units per tick */
+#[derive(Clone, Copy, PartialEq)]
+pub struct Difficulty
+{
+  pub profile: Profile,    /* which profile these settings come from */
+  pub march: March,        /* how the squadron moves */
+  pub march_step: f32,     /* speed of a full squadron */
+  pub march_speedup: f32,  /* extra squadron speed for each alien shot down, in a smooth march */
+  pub bomb_speed: f32,     /* how fast alien bombs fall */
+  pub bullet_speed: f32,   /* how fast the heroes' bullets climb */
+  pub ship_speed: f32,     /* top speed of the heroes' ships */
+  pub ship_accel: f32,     /* how much the ships speed up or slow down each tick, or 0 to go straight to top speed and stop dead */
+  pub lives: i32           /* lives each game starts with */
+}
+
+impl Difficulty
+{
+  /* returns the settings of a profile. custom starts out the same as normal */
+  pub fn new(profile: Profile) -> Difficulty
+  {
+    let (march, march_step, march_speedup, bomb_speed, bullet_speed, ship_speed, lives) = match profile
+    {
+      Profile::Easy   => (March::Smooth, 0.08, 0.005, 0.75, 2.5, 1.25, 5),
+      Profile::Normal | Profile::Custom => (March::Smooth, 0.1, 0.008, 1.0, 2.0, 1.0, 3),
+      Profile::Arcade => (March::Classic, 0.1, 0.012, 1.25, 2.0, 1.0, 3),
+      Profile::Insane => (March::Smooth, 0.15, 0.016, 1.75, 2.0, 1.0, 1)
+    };
+
+    Difficulty
+    {
+      profile: profile,
+      march: march,
+      march_step: march_step,
+      march_speedup: march_speedup,
+      bomb_speed: bomb_speed,
+      bullet_speed: bullet_speed,
+      ship_speed: ship_speed,
+      ship_accel: 0.0,
+      lives: lives
+    }
+  }
+
+  /* returns the squadron's speed in a smooth march when the given number of aliens have been shot down */
+  pub fn march_speed(&self, aliens_down: usize) -> f32
+  {
+    self.march_step + (aliens_down as f32 * self.march_speedup)
+  }
+
+  /* describe the difficulty as a JSON object, for the stats file. games with the same
+   * difficulty always get the same text, so it can be matched when picking out scores.
+   * the ships' acceleration is left out unless they have inertia, so custom games from
+   * before there was a choice still match */
+  pub fn to_json(self) -> String
+  {
+    match self.profile
+    {
+      Profile::Custom =>
+      {
+        let inertia = match self.ship_accel > 0.0
+        {
+          true => format!(",\"ship_accel\":{}", self.ship_accel),
+          false => String::new()
+        };
+        format!("{{\"name\":\"custom\",\"march\":\"{}\",\"march_step\":{},\"march_speedup\":{},\
+                 \"bomb_speed\":{},\"bullet_speed\":{},\"ship_speed\":{}{},\"lives\":{}}}",
+                self.march.name(), self.march_step, self.march_speedup, self.bomb_speed,
+                self.bullet_speed, self.ship_speed, inertia, self.lives)
+      },
+      profile => format!("{{\"name\":\"{}\"}}", profile.name())
+    }
+  }
+
+  /* write the difficulty to a saved game. named profiles are written out in full too,
+   * so a game carries on by the rules it started with even if a profile is retuned */
+  pub fn save(&self, writer: &mut save::Writer)
+  {
+    writer.label("difficulty");
+    writer.value(self.profile.name());
+    writer.value(self.march_step);
+    writer.value(self.march_speedup);
+    writer.value(self.bomb_speed);
+    writer.value(self.bullet_speed);
+    writer.value(self.ship_speed);
+    writer.value(self.lives);
+
+    writer.label("march");
+    writer.value(self.march.name());
+
+    writer.label("inertia");
+    writer.value(self.ship_accel);
+  }
+
+  /* recreate a difficulty from a saved game. files from before there was a choice of
+   * march leave it out, and used the smooth march. files from before the ships had
+   * inertia leave that out too */
+  pub fn restore(reader: &mut save::Reader) -> Result<Difficulty, save::SaveError>
+  {
+    reader.label("difficulty")?;
+    let name = reader.value::<String>()?;
+    let profile = match Profile::from_name(name.as_str())
+    {
+      Some(profile) => profile,
+      None => return Err(reader.unexpected("difficulty", name.as_str()))
+    };
+
+    let mut difficulty = Difficulty
+    {
+      profile: profile,
+      march: March::Smooth,
+      march_step: reader.value()?, march_speedup: reader.value()?,
+      bomb_speed: reader.value()?, bullet_speed: reader.value()?,
+      ship_speed: reader.value()?, ship_accel: 0.0, lives: reader.value()?
+    };
+
+    if reader.next_is("march") == true
+    {
+      reader.label("march")?;
+      let name = reader.value::<String>()?;
+      difficulty.march = match March::from_name(name.as_str())
+      {
+        Some(march) => march,
+        None => return Err(reader.unexpected("march", name.as_str()))
+      };
+    }
+
+    if reader.next_is("inertia") == true
+    {
+      reader.label("inertia")?;
+      difficulty.ship_accel = reader.value()?;
+    }
+
+    return Ok(difficulty);
+  }
+}
@@ -0,0 +1,174 @@
+/* Space invaders in Rust
+ *
+ * Camera direction: choose where the camera looks from, and move it smoothly
+ *
+ * Game concept by Tomohiro Nishikado / Taito
+ * Rust code By Chris Williams <diodesign@tuta.io>
+ *
+ * Written for fun. See LICENSE.
+ *
+ */
+
+extern crate kiss3d;
+extern crate nalgebra as na;
+extern crate rand;
+
+use kiss3d::camera::ArcBall;
+use na::{ Point3, Vector3 };
+use rand::Rng;
+
+use super::game::Playfield;
+
+/* remember, the camera looks along the positive z-axis, so the camera sits at negative z */
+const SMOOTHING: f32 = 0.05;     /* fraction of the way to the wanted view moved each frame */
+
+const CHASE_HEIGHT: f32 = -190.0;  /* y position of the camera in the behind-the-ship view */
+const CHASE_BACK: f32 = 0.5;       /* how far back the behind-the-ship camera is, as a fraction of the distance */
+const CHASE_LOOK_Y: f32 = -10.0;   /* y position the behind-the-ship camera looks at */
+const CHASE_FOLLOW: f32 = 0.6;     /* how closely the behind-the-ship camera's target follows the ship's x */
+
+const ORBIT_SPEED: f32 = 0.004;  /* radians the showcase camera circles by each frame */
+const ORBIT_RISE: f32 = 0.25;    /* height of the showcase camera, as a fraction of the distance */
+
+const SHAKE_FRAMES: u32 = 45;    /* how long the camera shakes for when a ship is destroyed */
+const SHAKE_SIZE: f32 = 5.0;     /* furthest the camera is thrown when the shake starts */
+
+/* the ways the camera can look at the playfield */
+#[derive(Clone, Copy, PartialEq)]
+pub enum CameraMode
+{
+  Classic, /* straight on, like the arcade original */
+  Chase,   /* low down behind the hero's ship, following it */
+  Orbit    /* slowly circling the middle of the playfield */
+}
+
+impl CameraMode
+{
+  /* returns the mode after this one, for cycling through the modes */
+  pub fn next(&self) -> CameraMode
+  {
+    match *self
+    {
+      CameraMode::Classic => CameraMode::Chase,
+      CameraMode::Chase => CameraMode::Orbit,
+      CameraMode::Orbit => CameraMode::Classic
+    }
+  }
+
+  /* returns the mode's name as shown on screen */
+  pub fn title(&self) -> &'static str
+  {
+    match *self
+    {
+      CameraMode::Classic => "Classic",
+      CameraMode::Chase => "Behind the ship",
+      CameraMode::Orbit => "Orbiting"
+    }
+  }
+}
+
+/* the director owns the camera and glides it towards wherever the current mode wants it,
+ * so switching modes is a smooth move rather than a jump. it also shakes the camera
+ * when a ship is blown up */
+pub struct Director
+{
+  camera: ArcBall,
+  mode: CameraMode,
+  distance: f32,                /* how far the camera sits from the middle of the playfield */
+  eye: Point3<f32>,             /* where the camera is, before any shake */
+  at: Point3<f32>,              /* where the camera is looking, before any shake */
+  orbit: f32,                   /* angle around the showcase orbit */
+  hero_x: f32,                  /* where the ships are, for the behind-the-ship view */
+  shake: u32,                   /* frames of shaking left */
+  rng: rand::rngs::ThreadRng
+}
+
+impl Director
+{
+  /* create a camera starting in the classic straight-on position
+   * => mode = the view to move into
+   *    distance = distance from the middle of the playfield to the camera */
+  pub fn new(mode: CameraMode, distance: f32) -> Director
+  {
+    let eye = Point3::new(0.0, 0.0, 0.0 - distance);
+    let at = Point3::origin();
+
+    Director
+    {
+      camera: ArcBall::new(eye, at),
+      mode: mode,
+      distance: distance,
+      eye: eye,
+      at: at,
+      orbit: 0.0,
+      hero_x: 0.0,
+      shake: 0,
+      rng: rand::thread_rng()
+    }
+  }
+
+  /* returns the camera to render with */
+  pub fn camera(&mut self) -> &mut ArcBall
+  {
+    &mut self.camera
+  }
+
+  pub fn get_mode(&self) -> CameraMode
+  {
+    self.mode
+  }
+
+  /* start moving the camera into the given mode */
+  pub fn set_mode(&mut self, mode: CameraMode)
+  {
+    self.mode = mode;
+  }
+
+  /* keep an eye on the playfield: follow the ships, and shake the camera if one has
+   * just been destroyed */
+  pub fn watch(&mut self, playfield: &Playfield)
+  {
+    if playfield.heroes.is_empty() == false
+    {
+      self.hero_x = playfield.heroes.iter().map(|p| p.get_coords().0).sum::<f32>() / playfield.heroes.len() as f32;
+    }
+
+    if playfield.heroes.iter().any(|p| p.ticks_since_death() == Some(0)) == true
+    {
+      self.shake = SHAKE_FRAMES;
+    }
+  }
+
+  /* move the camera one frame closer to where the current mode wants it */
+  pub fn update(&mut self)
+  {
+    let (eye, at) = match self.mode
+    {
+      CameraMode::Classic => (Point3::new(0.0, 0.0, 0.0 - self.distance), Point3::origin()),
+
+      CameraMode::Chase => (Point3::new(self.hero_x, CHASE_HEIGHT, 0.0 - (self.distance * CHASE_BACK)),
+                            Point3::new(self.hero_x * CHASE_FOLLOW, CHASE_LOOK_Y, 0.0)),
+
+      CameraMode::Orbit =>
+      {
+        self.orbit = self.orbit + ORBIT_SPEED;
+        (Point3::new(self.distance * self.orbit.sin(), self.distance * ORBIT_RISE, 0.0 - (self.distance * self.orbit.cos())),
+         Point3::origin())
+      }
+    };
+
+    self.eye = self.eye + ((eye - self.eye) * SMOOTHING);
+    self.at = self.at + ((at - self.at) * SMOOTHING);
+
+    /* throw the camera about, calming down as the shake wears off */
+    let mut shake = Vector3::new(0.0, 0.0, 0.0);
+    if self.shake > 0
+    {
+      let size = SHAKE_SIZE * self.shake as f32 / SHAKE_FRAMES as f32;
+      shake = Vector3::new(self.rng.gen_range(0.0 - size, size), self.rng.gen_range(0.0 - size, size), 0.0);
+      self.shake = self.shake - 1;
+    }
+
+    self.camera.look_at(self.eye + shake, self.at + shake);
+  }
+}
@@ -0,0 +1,503 @@
+/* Space invaders in Rust
+ *
+ * Reinforcement-learning environment: drive the game one tick at a time
+ *
+ * Game concept by Tomohiro Nishikado / Taito
+ * Rust code By Chris Williams <diodesign@tuta.io>
+ *
+ * Written for fun. See LICENSE.
+ *
+ */
+
+/* this wraps the game simulation in the usual reset / step interface used for training
+ * agents, without a window or any graphics. an agent flies the hero in a single-player
+ * game, picking an action each tick, and gets back what it can see plus a reward.
+ *
+ * serve() exposes this over stdin and stdout, one request per line, so agents written
+ * in other languages can drive the game by running it as a child process:
+ *   reset <seed>    start a new game
+ *   step <action>   run one tick, where action is one of noop, left, right or fire
+ *   quit            stop
+ * each request is answered with one line:
+ *   <reward> <done> <count> <value> <value> ...
+ * where done is 1 when the game is over, and count is the number of observation values */
+
+use std::io::{ self, BufRead, Write };
+
+use super::aliens;
+use super::difficulty::{ Difficulty, Profile };
+use super::hero;
+use super::game::{ Game, GameMode, Playfield, LevelOutcome };
+use super::input::Controls;
+
+const FIELD_SCALE: f32 = 100.0;  /* game world units are divided by this in vector observations */
+const LIFE_PENALTY: f32 = 1000.0; /* reward lost when the hero loses a life */
+
+/* area of the playfield covered by grid observations, in game world units */
+const GRID_LEFT: f32   = 120.0;  /* remember, screen left is the positive x direction */
+const GRID_RIGHT: f32  = -120.0;
+const GRID_TOP: f32    = 100.0;
+const GRID_BOTTOM: f32 = -100.0;
+pub const GRID_WIDTH: usize  = 48;
+pub const GRID_HEIGHT: usize = 40;
+
+/* what can be in a cell of a grid observation */
+const CELL_EMPTY: f32  = 0.0;
+const CELL_ALIEN: f32  = 1.0;
+const CELL_HERO: f32   = 2.0;
+const CELL_BULLET: f32 = 3.0;
+const CELL_BOMB: f32   = 4.0;
+
+/* what the agent can do each tick */
+#[derive(Clone, Copy, PartialEq)]
+pub enum Action
+{
+  NoOp,
+  Left,
+  Right,
+  Fire
+}
+
+impl Action
+{
+  /* turn an action's name into an action, or None if it's not recognized */
+  pub fn from_name(name: &str) -> Option<Action>
+  {
+    match name
+    {
+      "noop" => Some(Action::NoOp),
+      "left" => Some(Action::Left),
+      "right" => Some(Action::Right),
+      "fire" => Some(Action::Fire),
+      _ => None
+    }
+  }
+
+  fn controls(&self) -> Controls
+  {
+    let mut controls = Controls::default();
+    match *self
+    {
+      Action::NoOp => {},
+      Action::Left => controls.left = true,
+      Action::Right => controls.right = true,
+      Action::Fire => controls.fire = true
+    }
+    return controls;
+  }
+}
+
+/* how the agent sees the playfield */
+#[derive(Clone, Copy, PartialEq)]
+pub enum ObservationKind
+{
+  /* a vector of numbers describing the playfield, with positions divided by FIELD_SCALE:
+   *   hero x, hero alive (1 or 0),
+   *   hero bullet in play (1 or 0), bullet x, bullet y (the oldest, if there are several),
+   *   bomb in play (1 or 0), bomb x, bomb y (the lowest of the squadron's and the
+   *     mothership's, if there are several),
+   *   leftmost x, rightmost x and lowest y of everything that can be shot: the living
+   *     aliens, and on a boss wave the mothership's exposed sections and its escorts
+   *     (all 0 if there's nothing left to shoot),
+   *   then one value per formation cell, 1 if the alien in that cell is alive or 0 if not,
+   *     going row by row from the bottom row, and column by column from the rightmost,
+   *   then mothership in play (1 or 0), the fraction of its health left (0 if not in play),
+   *     and the number of bombs in play */
+  Vector,
+
+  /* a low-resolution picture of the playfield, GRID_WIDTH by GRID_HEIGHT cells,
+   * row by row from the top left of the screen. each cell holds one of the CELL_* values */
+  Grid
+}
+
+impl ObservationKind
+{
+  /* turn a kind's name into a kind, or None if it's not recognized */
+  pub fn from_name(name: &str) -> Option<ObservationKind>
+  {
+    match name
+    {
+      "vector" => Some(ObservationKind::Vector),
+      "grid" => Some(ObservationKind::Grid),
+      _ => None
+    }
+  }
+}
+
+/* a single-player game driven one tick at a time */
+pub struct Env
+{
+  kind: ObservationKind,
+  state: Game,
+  playfield: Playfield,
+  done: bool
+}
+
+impl Env
+{
+  /* create an environment giving observations of the given kind. call reset() to
+   * start playing */
+  pub fn new(kind: ObservationKind) -> Env
+  {
+    let mut state = Game::new(GameMode::Single, 0, Difficulty::new(Profile::Normal));
+    let playfield = Playfield::new(&mut state);
+    Env
+    {
+      kind: kind,
+      state: state,
+      playfield: playfield,
+      done: false
+    }
+  }
+
+  /* start a new game
+   * => seed = seed for the game's random decisions
+   * <= first observation of the new game */
+  pub fn reset(&mut self, seed: u64) -> Vec<f32>
+  {
+    self.state = Game::new(GameMode::Single, seed, Difficulty::new(Profile::Normal));
+    self.playfield = Playfield::new(&mut self.state);
+    self.done = false;
+    return self.observe();
+  }
+
+  /* run the game for one tick
+   * => action = what the hero does this tick
+   * <= (observation, reward, done) where reward is the points scored this tick, minus
+   *    LIFE_PENALTY for each life lost, and done is true when the game is over */
+  pub fn step(&mut self, action: Action) -> (Vec<f32>, f32, bool)
+  {
+    if self.done == true
+    {
+      return (self.observe(), 0.0, true);
+    }
+
+    let score = self.state.score;
+    let lives = self.state.lives;
+
+    match self.playfield.step(&mut self.state, &[ action.controls() ])
+    {
+      Some(LevelOutcome::Victory) => self.playfield = Playfield::new(&mut self.state), /* on to the next level */
+      Some(_) => self.done = true,
+      None => {}
+    }
+
+    let reward = (self.state.score - score) as f32 - ((lives - self.state.lives) as f32 * LIFE_PENALTY);
+    return (self.observe(), reward, self.done);
+  }
+
+  /* describe the playfield as the agent sees it */
+  fn observe(&self) -> Vec<f32>
+  {
+    match self.kind
+    {
+      ObservationKind::Vector => self.observe_vector(),
+      ObservationKind::Grid => self.observe_grid()
+    }
+  }
+
+  fn observe_vector(&self) -> Vec<f32>
+  {
+    let mut values = Vec::new();
+    let ship = &self.playfield.heroes[0];
+
+    let (x, _, _) = ship.get_coords();
+    values.push(x / FIELD_SCALE);
+    values.push(flag(ship.state == hero::State::Alive));
+
+    push_projectile(&mut values, ship.shots.first().map(|s| s.bullet.get_coords()));
+    let bombs = self.playfield.bombs();
+    let lowest_bomb = bombs.iter().map(|b| b.get_coords()).fold(None, |lowest: Option<(f32, f32, f32)>, bomb| match lowest
+    {
+      Some(lowest) if lowest.1 <= bomb.1 => Some(lowest),
+      _ => Some(bomb)
+    });
+    push_projectile(&mut values, lowest_bomb);
+
+    /* work out the edges of everything that can be shot, and which cells of the formation
+     * are still alive */
+    let mut cells = vec![ 0.0; (aliens::ALIENS_PER_ROW * aliens::ALIEN_ROWS) as usize ];
+    let mut targets = Vec::new();
+    for baddie in self.playfield.aliens.get_squadron().iter().filter(|a| a.get_state() == aliens::State::Alive)
+    {
+      let (x, y, _) = baddie.get_coords();
+      targets.push((x, y));
+
+      let (column, row) = baddie.get_position();
+      if let Some(cell) = cells.get_mut((row * aliens::ALIENS_PER_ROW + column) as usize)
+      {
+        *cell = 1.0;
+      }
+    }
+    if let Some(boss) = self.playfield.boss.as_ref()
+    {
+      targets.extend(boss.targets());
+    }
+
+    let edges = targets.iter().fold(None, |edges, (x, y)| match edges
+    {
+      None => Some((*x, *x, *y)),
+      Some((left, right, lowest)) => Some((f32::max(left, *x), f32::min(right, *x), f32::min(lowest, *y)))
+    });
+
+    let (left, right, lowest) = edges.unwrap_or((0.0, 0.0, 0.0));
+    values.push(left / FIELD_SCALE);
+    values.push(right / FIELD_SCALE);
+    values.push(lowest / FIELD_SCALE);
+    values.extend(cells);
+
+    match self.playfield.boss.as_ref()
+    {
+      Some(boss) => values.extend(&[ 1.0, boss.health() ]),
+      None => values.extend(&[ 0.0, 0.0 ])
+    }
+    values.push(bombs.len() as f32);
+
+    return values;
+  }
+
+  fn observe_grid(&self) -> Vec<f32>
+  {
+    let mut grid = vec![ CELL_EMPTY; GRID_WIDTH * GRID_HEIGHT ];
+
+    for baddie in self.playfield.aliens.get_squadron().iter().filter(|a| a.get_state() == aliens::State::Alive)
+    {
+      let (x, y, _) = baddie.get_coords();
+      plot(&mut grid, x, y, CELL_ALIEN);
+    }
+
+    /* the mothership's sections still standing, shielded or not, and its escorts */
+    if let Some(boss) = self.playfield.boss.as_ref()
+    {
+      for section in boss.get_sections().iter().filter(|s| s.get_state() == aliens::State::Alive)
+      {
+        let (x, y, _) = boss.section_coords(section);
+        plot(&mut grid, x, y, CELL_ALIEN);
+      }
+      for (x, y) in boss.targets()
+      {
+        plot(&mut grid, x, y, CELL_ALIEN);
+      }
+    }
+
+    for ship in self.playfield.heroes.iter().filter(|p| p.state == hero::State::Alive)
+    {
+      let (x, y, _) = ship.get_coords();
+      plot(&mut grid, x, y, CELL_HERO);
+    }
+
+    for bullet in self.playfield.bullets()
+    {
+      let (x, y, _) = bullet.get_coords();
+      plot(&mut grid, x, y, CELL_BULLET);
+    }
+
+    for bomb in self.playfield.bombs()
+    {
+      let (x, y, _) = bomb.get_coords();
+      plot(&mut grid, x, y, CELL_BOMB);
+    }
+
+    return grid;
+  }
+}
+
+/* turn a yes or no into a number for an observation */
+fn flag(value: bool) -> f32
+{
+  match value
+  {
+    true => 1.0,
+    false => 0.0
+  }
+}
+
+/* add whether a bullet or bomb is in play, and its x, y position, to a vector observation */
+fn push_projectile(values: &mut Vec<f32>, coords: Option<(f32, f32, f32)>)
+{
+  match coords
+  {
+    Some((x, y, _)) =>
+    {
+      values.push(1.0);
+      values.push(x / FIELD_SCALE);
+      values.push(y / FIELD_SCALE);
+    },
+    None => values.extend(&[ 0.0, 0.0, 0.0 ])
+  }
+}
+
+/* mark the grid cell covering the game world position x, y. positions off the grid are ignored */
+fn plot(grid: &mut [f32], x: f32, y: f32, cell: f32)
+{
+  let column = (x - GRID_LEFT) / (GRID_RIGHT - GRID_LEFT) * GRID_WIDTH as f32;
+  let row = (y - GRID_TOP) / (GRID_BOTTOM - GRID_TOP) * GRID_HEIGHT as f32;
+
+  if column >= 0.0 && row >= 0.0 && (column as usize) < GRID_WIDTH && (row as usize) < GRID_HEIGHT
+  {
+    grid[(row as usize * GRID_WIDTH) + column as usize] = cell;
+  }
+}
+
+/* ------------------------------------------------------------------------------ */
+
+/* run an environment driven by requests on stdin, answering on stdout. see the top of
+ * this file for the protocol
+ * => kind = kind of observations to give */
+pub fn serve(kind: ObservationKind) -> io::Result<()>
+{
+  let mut env = Env::new(kind);
+  let stdin = io::stdin();
+  let stdout = io::stdout();
+  let mut out = stdout.lock();
+
+  for line in stdin.lock().lines()
+  {
+    let line = line?;
+    let words: Vec<&str> = line.split_whitespace().collect();
+
+    let (observation, reward, done) = match words.as_slice()
+    {
+      [ "reset", seed ] => match seed.parse::<u64>()
+      {
+        Ok(seed) => (env.reset(seed), 0.0, false),
+        Err(_) =>
+        {
+          writeln!(out, "error bad seed {}", seed)?;
+          continue;
+        }
+      },
+      [ "step", action ] => match Action::from_name(action)
+      {
+        Some(action) => env.step(action),
+        None =>
+        {
+          writeln!(out, "error unknown action {}", action)?;
+          continue;
+        }
+      },
+      [ "quit" ] => return Ok(()),
+      _ =>
+      {
+        writeln!(out, "error unknown request {}", line)?;
+        continue;
+      }
+    };
+
+    write!(out, "{} {} {}", reward, flag(done) as u32, observation.len())?;
+    for value in observation.iter()
+    {
+      write!(out, " {}", value)?;
+    }
+    writeln!(out)?;
+    out.flush()?;
+  }
+
+  return Ok(());
+}
+
+/* ------------------------------------------------------------------------------ */
+
+#[cfg(test)]
+mod tests
+{
+  use super::*;
+
+  const ACTIONS: [Action; 4] = [ Action::Left, Action::Fire, Action::Right, Action::NoOp ];
+
+  /* play a game with a fixed run of actions
+   * <= every observation, reward and done flag along the way */
+  fn play(env: &mut Env, seed: u64, ticks: usize) -> Vec<(Vec<f32>, f32, bool)>
+  {
+    let mut steps = vec![ (env.reset(seed), 0.0, false) ];
+    for tick in 0..ticks
+    {
+      steps.push(env.step(ACTIONS[(tick / 30) % ACTIONS.len()]));
+    }
+    steps
+  }
+
+  #[test]
+  fn same_seed_same_game()
+  {
+    for kind in [ ObservationKind::Vector, ObservationKind::Grid ].iter()
+    {
+      let mut env = Env::new(*kind);
+      let first = play(&mut env, 5, 1500);
+      let again = play(&mut env, 5, 1500);
+      assert!(first == again, "replaying a seed gave a different game");
+      assert!(play(&mut Env::new(*kind), 5, 1500) == first, "a fresh environment gave a different game");
+      assert!(play(&mut env, 6, 1500) != first, "a different seed gave the same game");
+    }
+  }
+
+  #[test]
+  fn observations_keep_their_size()
+  {
+    let mut env = Env::new(ObservationKind::Grid);
+    assert!(play(&mut env, 1, 300).iter().all(|(observation, _, _)| observation.len() == GRID_WIDTH * GRID_HEIGHT));
+
+    let mut env = Env::new(ObservationKind::Vector);
+    let size = env.reset(1).len();
+    assert!(play(&mut env, 1, 300).iter().all(|(observation, _, _)| observation.len() == size));
+  }
+
+  #[test]
+  fn mothership_can_be_seen()
+  {
+    /* the fifth level of the built-in waves is a boss fight */
+    super::super::formation::load().ok().unwrap();
+    let mut env = Env::new(ObservationKind::Vector);
+    env.reset(2);
+    env.state.level = 4;
+    env.playfield = Playfield::new(&mut env.state);
+    assert!(env.playfield.boss.is_some() == true);
+
+    /* give the mothership time to arrive and start bombing */
+    let mut seen_bomb = false;
+    for _ in 0..1200
+    {
+      let (observation, _, done) = env.step(Action::NoOp);
+      let tail = &observation[observation.len() - 3..];
+      assert_eq!(tail[0], 1.0, "mothership missing from the observation");
+      assert!(tail[1] > 0.0);
+      assert!(observation[8] != 0.0 || observation[9] != 0.0 || observation[10] != 0.0, "nothing to shoot at");
+      seen_bomb = seen_bomb == true || (observation[5] == 1.0 && tail[2] >= 1.0);
+      if done == true
+      {
+        break;
+      }
+    }
+    assert!(seen_bomb == true, "the mothership's bombs were never observed");
+
+    let mut env = Env::new(ObservationKind::Grid);
+    env.reset(2);
+    env.state.level = 4;
+    env.playfield = Playfield::new(&mut env.state);
+    for _ in 0..600
+    {
+      env.step(Action::NoOp);
+    }
+    assert!(env.observe().iter().any(|cell| *cell == CELL_ALIEN), "the mothership doesn't show on the grid");
+  }
+
+  #[test]
+  fn finished_game_stays_finished()
+  {
+    let mut env = Env::new(ObservationKind::Vector);
+    env.reset(3);
+
+    /* sitting still and never firing, the squadron gets the hero in the end */
+    let mut ticks = 0;
+    while env.step(Action::NoOp).2 == false
+    {
+      ticks = ticks + 1;
+      assert!(ticks < 100000, "game never ended");
+    }
+
+    let (_, reward, done) = env.step(Action::Fire);
+    assert!(done == true && reward == 0.0);
+    assert!(env.reset(3).is_empty() == false);
+    assert!(env.step(Action::NoOp).2 == false);
+  }
+}
@@ -0,0 +1,1335 @@
+/* Space invaders in Rust
+ *
+ * The screens shown in the window, and the flow from one to the next
+ *
+ * Game concept by Tomohiro Nishikado / Taito
+ * Rust code By Chris Williams <diodesign@tuta.io>
+ *
+ * Written for fun. See LICENSE.
+ *
+ */
+
+/* the window moves through a handful of states: the title menu, the options menu, playing
+ * a level, paused, level complete, game over, entering initials for the high-score table,
+ * and finally quitting. each state has its own screen, which adds whatever it needs to
+ * the scene when it starts and takes it all away again before handing over to the next
+ * state. a game in progress, and the level being played, are carried from state to state
+ * in a Match. closing the window in any state quits, saving the game first if it can be
+ * continued later. left alone on the title menu, the window goes into attract mode like
+ * an arcade machine: a demo game, the high-score table and the score advance table, in
+ * turn, until a key is pressed */
+
+extern crate kiss3d;
+extern crate nalgebra as na;
+extern crate rand;
+
+use std::rc::Rc;
+
+use na::{ Point3, Point2 };
+use kiss3d::window::Window;
+use kiss3d::event::{ Event, WindowEvent, Key, Action };
+use kiss3d::text::Font;
+
+use super::aliens;
+use super::assets;
+use super::boss;
+use super::bot;
+use super::difficulty::{ self, Difficulty, Profile };
+use super::director::{ Director, CameraMode };
+use super::game::{ self, Game, GameMode, LevelOutcome };
+use super::hud;
+use super::input;
+use super::net;
+use super::options::{ Network, Options };
+use super::overlay::Overlay;
+use super::particles;
+use super::render;
+use super::replay;
+use super::save;
+use super::stats;
+
+const ATTRACT_DELAY: u64 = 10;        /* seconds left alone on the title menu before attract mode starts */
+const DEMO_LENGTH: u64 = 40;          /* seconds of demo game in attract mode, unless the bot dies first */
+const ATTRACT_PAGE_LENGTH: u64 = 8;   /* seconds to show each of attract mode's tables */
+const MENU_BACKGROUND: (f32, f32, f32) = (0.6, 0.6, 0.6); /* r, g, b color behind the menus */
+
+/* where the flow is up to */
+enum State
+{
+  Title,                  /* the title menu */
+  Options,                /* the options menu */
+  Playing(Box<Match>),    /* a level being played */
+  Paused(Box<Match>),     /* a level on hold, with a menu over it */
+  LevelComplete(Box<Match>), /* a level has been cleared, and the next is about to start */
+  GameOver(Box<Match>),   /* the game has ended */
+  HighScoreEntry(Box<Game>), /* a finished game made the high-score table, and needs the player's initials */
+  Demo,                   /* attract mode: the bot plays a game */
+  HighScoreTable,         /* attract mode: the best scores at the picked difficulty */
+  ScoreAdvanceTable,      /* attract mode: the points each invader is worth */
+  Quit                    /* the player has had enough */
+}
+
+/* who is flying the hero's ship */
+#[derive(Clone, Copy, PartialEq)]
+enum Pilot
+{
+  Human, /* player(s) at the keyboard */
+  Cpu,   /* the bot */
+  Replay /* a recording made with --record */
+}
+
+/* what happened to a menu */
+enum Pick
+{
+  Chosen(usize), /* an item was picked with space or return */
+  Back,          /* escape was pressed */
+  Idle,          /* nothing was pressed for a while */
+  Closed         /* the window was closed */
+}
+
+/* what the states share, kept while the window is open */
+struct Flow
+{
+  options: Options,              /* command-line options. the difficulty is changed in the options menu */
+  difficulties: Vec<Difficulty>, /* difficulties to pick from in the options menu */
+  director: Director,            /* the camera. the V key cycles through its views */
+  overlay: Overlay,              /* debug overlay, switched on and off with F3 */
+  ticks_per_frame: f32,          /* simulation ticks to run for each frame drawn */
+  one_off: bool                  /* true to quit once the game is over, rather than go back to the title */
+}
+
+impl Flow
+{
+  fn new(options: Options, one_off: bool) -> Flow
+  {
+    /* difficulties to pick from: the named ones, and the one from the command line if it's
+     * been tweaked. the pick carries on from game to game */
+    let mut difficulties: Vec<Difficulty> = difficulty::PROFILES.iter().map(|p| Difficulty::new(*p)).collect();
+    if options.difficulty.profile == Profile::Custom
+    {
+      difficulties.push(options.difficulty);
+    }
+
+    Flow
+    {
+      ticks_per_frame: game::TICKS_PER_SECOND as f32 / options.fps as f32,
+      options: options,
+      difficulties: difficulties,
+      director: Director::new(CameraMode::Classic, 250.0),
+      overlay: Overlay::new(),
+      one_off: one_off
+    }
+  }
+
+  /* returns where to go once a game is over */
+  fn after_game(&self) -> State
+  {
+    match self.one_off
+    {
+      true => State::Quit,
+      false => State::Title
+    }
+  }
+}
+
+/* a game in progress, carried from state to state until it's over */
+struct Match
+{
+  state: Game,                   /* score, lives, level and so on */
+  level: Option<Level>,          /* the level being played, or None between levels */
+  pilot: Pilot,                  /* who is flying the hero's ship */
+  tape: Option<replay::Tape>,    /* recording to play back if the pilot is Pilot::Replay, otherwise to add the game to */
+  session: Option<net::Session>, /* connection to the other player in a network game */
+  ending: Option<String>         /* why the game ended early, eg: the other player left */
+}
+
+impl Match
+{
+  /* start a game, or carry on with a saved one, and set up its first level
+   * => window = graphics context
+   *    state = new game to play, or a saved game to continue
+   *    resume = playfield of the level to continue in a saved game, or None to start a new level
+   *    pilot = who is flying the hero's ship
+   *    tape = recording to play back or add to, or None to not record the game
+   *    session = connection to the other player in a network game, or None for a local game */
+  fn new(window: &mut Window, mut state: Game, resume: Option<game::Playfield>, pilot: Pilot,
+         tape: Option<replay::Tape>, session: Option<net::Session>) -> Box<Match>
+  {
+    let level = Level::new(window, &mut state, resume, pilot);
+    Box::new(Match
+    {
+      state: state,
+      level: Some(level),
+      pilot: pilot,
+      tape: tape,
+      session: session,
+      ending: None
+    })
+  }
+
+  /* returns true if the game can be saved and continued later. network games and games
+   * flown by the bot can't */
+  fn saveable(&self) -> bool
+  {
+    self.session.is_none() == true && self.pilot == Pilot::Human
+  }
+
+  /* set up the next level */
+  fn next_level(&mut self, window: &mut Window)
+  {
+    self.end_level();
+    self.level = Some(Level::new(window, &mut self.state, None, self.pilot));
+  }
+
+  /* take the level being played out of the scene */
+  fn end_level(&mut self)
+  {
+    if let Some(mut level) = self.level.take()
+    {
+      level.scene.delete();
+    }
+  }
+
+  /* save the game so it can be continued from the title screen. between levels, the
+   * next level is saved, ready to start */
+  fn save(&mut self) -> Result<(), save::SaveError>
+  {
+    match self.level.as_ref()
+    {
+      Some(level) => save::save(&self.state, &level.playfield),
+      None =>
+      {
+        let playfield = game::Playfield::new(&mut self.state);
+        save::save(&self.state, &playfield)
+      }
+    }
+  }
+
+  /* wind up the game: take its level out of the scene, write out its recording if it was
+   * being recorded, and say goodbye to the other player in a network game
+   * => record = file to write the recording to, if there is one
+   * <= final state of the game */
+  fn finish(mut self, record: Option<&String>) -> Game
+  {
+    self.end_level();
+
+    if let (Some(path), Some(tape), true) = (record, self.tape.as_ref(), self.pilot != Pilot::Replay)
+    {
+      if let Err(e) = tape.save(path)
+      {
+        eprintln!("rustinvaders: could not save the recording to {}: {}", path, e);
+      }
+    }
+
+    if let Some(session) = self.session.as_mut()
+    {
+      session.close();
+    }
+
+    return self.state;
+  }
+}
+
+/* a level being played, and everything drawn for it */
+struct Level
+{
+  playfield: game::Playfield,
+  scene: render::Scene,       /* the level's nodes in the window's scene */
+  hud: hud::Hud,              /* scores, level and lives drawn over the scene */
+  keyboard: input::Keyboard,  /* keys and joysticks held down by the players */
+  bot: Option<bot::Bot>,      /* flies the hero's ship if the pilot is Pilot::Cpu */
+  ticks_due: f32,             /* ticks to run, carried over from frame to frame */
+  waiting: bool               /* true while waiting on the other player in a network game */
+}
+
+impl Level
+{
+  /* create the baddies and hero for a level, and add them to the scene
+   * => window = graphics context
+   *    state = game the level is part of
+   *    resume = playfield to carry on with from a saved game, or None to start the next level
+   *    pilot = who is flying the hero's ship */
+  fn new(window: &mut Window, state: &mut Game, resume: Option<game::Playfield>, pilot: Pilot) -> Level
+  {
+    let playfield = match resume
+    {
+      Some(playfield) => playfield,
+      None => game::Playfield::new(state)
+    };
+
+    Level
+    {
+      scene: render::Scene::new(window, &playfield),
+      playfield: playfield,
+      hud: hud::Hud::new(assets::font(assets::GAME_FONT), &state.difficulty),
+      keyboard: input::Keyboard::new(),
+      bot: match pilot
+      {
+        Pilot::Cpu => Some(bot::Bot::new(0)),
+        Pilot::Human | Pilot::Replay => None
+      },
+      ticks_due: 0.0,
+      waiting: false
+    }
+  }
+}
+
+/* the backdrop of the menus and messages: a single black alien spinning in place, watched
+ * by a slowly orbiting camera. its nodes are added to the scene when it's created, and
+ * taken away by delete() */
+struct Backdrop
+{
+  director: Director,
+  alien: aliens::Alien,
+  model: render::AlienModel,
+  effects: particles::Particles
+}
+
+impl Backdrop
+{
+  /* => window = graphics context
+   *    r, g, b = background color */
+  fn new(window: &mut Window, r: f32, g: f32, b: f32) -> Backdrop
+  {
+    window.set_background_color(r, g, b);
+
+    let mut alien = aliens::Alien::new();
+    let mut model = render::AlienModel::new(window, &render::AlienMeshes::new(), aliens::Species::Crab);
+    let effects = particles::Particles::new(window.scene_mut(), 0); /* the alien never blows up */
+    alien.spawn(0.0, 10.0, 0.0, 0.0);
+    model.override_color(0.0, 0.0, 0.0);
+
+    Backdrop
+    {
+      director: Director::new(CameraMode::Orbit, 100.0),
+      alien: alien,
+      model: model,
+      effects: effects
+    }
+  }
+
+  /* show the last frame, and spin the alien a little further for the next
+   * <= false if the window has been closed */
+  fn frame(&mut self, window: &mut Window) -> bool
+  {
+    if window.render_with_camera(self.director.camera()) == false
+    {
+      return false;
+    }
+
+    self.director.update();
+    self.alien.animate(0.0); /* step = 0: don't move the alien */
+    self.model.update(&self.alien, &mut self.effects);
+    return true;
+  }
+
+  /* take the alien out of the scene */
+  fn delete(&mut self)
+  {
+    self.model.delete();
+  }
+}
+
+/* ------------------------------------------------------------------------------ */
+
+/* run the game in the window until the player quits or closes it
+ * => window = graphics context
+ *    options = command-line options
+ *    network = network game to host or join, or None to play locally */
+pub fn run(window: &mut Window, options: Options, network: Option<Network>)
+{
+  /* network games and replays are one-offs: play them and then quit */
+  let one_off = network.is_some() == true || options.replay.is_some() == true;
+  let mut flow = Flow::new(options, one_off);
+
+  let mut state = match (network, flow.options.replay.clone())
+  {
+    (Some(network), _) => start_network_game(window, &flow, network),
+    (None, Some(path)) => start_replay(window, path.as_str()),
+    (None, None) => State::Title
+  };
+
+  loop
+  {
+    state = match state
+    {
+      State::Title => title(window, &mut flow),
+      State::Options => options_menu(window, &mut flow),
+      State::Playing(game) => playing(window, &mut flow, game),
+      State::Paused(game) => paused(window, &mut flow, game),
+      State::LevelComplete(game) => level_complete(window, &mut flow, game),
+      State::GameOver(game) => game_over(window, &mut flow, game),
+      State::HighScoreEntry(state) => high_score_entry(window, &mut flow, state),
+      State::Demo => demo(window, &mut flow),
+      State::HighScoreTable => high_score_table(window, &flow),
+      State::ScoreAdvanceTable => score_advance_table(window, &flow),
+      State::Quit => return
+    };
+  }
+}
+
+/* the title menu: start a game, change the options, or quit. if there's a saved game,
+ * offer to continue it first. left alone, it goes into attract mode */
+fn title(window: &mut Window, flow: &mut Flow) -> State
+{
+  let saved = save::exists();
+  let mut items = vec![ "One player", "Versus: player two is the aliens", "Co-op: two ships", "CPU player", "Options", "Quit" ];
+  if saved == true
+  {
+    items.insert(0, "Continue saved game");
+  }
+
+  let idle = ATTRACT_DELAY * flow.options.fps;
+  let choice = match fullscreen_menu(window, "Welcome to Rust Invaders", &items, 0, Some(idle))
+  {
+    Pick::Chosen(choice) => choice,
+    Pick::Idle => return State::Demo,
+    Pick::Back | Pick::Closed => return State::Quit
+  };
+
+  let option = match (saved, choice)
+  {
+    (true, 0) => return continue_game(window),
+    (true, n) => n - 1,
+    (false, n) => n
+  };
+
+  let (mode, pilot) = match option
+  {
+    0 => (GameMode::Single, Pilot::Human),
+    1 => (GameMode::Versus, Pilot::Human),
+    2 => (GameMode::Coop, Pilot::Human),
+    3 => (GameMode::Single, Pilot::Cpu),
+    4 => return State::Options,
+    _ => return State::Quit
+  };
+
+  /* record new games if asked to */
+  let state = flow.options.new_game(mode, flow.options.pick_seed());
+  let tape = flow.options.record.as_ref().map(|_| replay::Tape::new(&state));
+  return State::Playing(Match::new(window, state, None, pilot, tape, None));
+}
+
+/* carry on with the saved game. games continued from a save can't be recorded as the
+ * recording has to start from the beginning of a level */
+fn continue_game(window: &mut Window) -> State
+{
+  match save::load()
+  {
+    Ok((state, playfield)) =>
+    {
+      /* a saved game can only be continued once */
+      save::discard();
+      State::Playing(Match::new(window, state, Some(playfield), Pilot::Human, None, None))
+    },
+    Err(e) =>
+    {
+      /* throw away saves we'll never be able to read */
+      if let save::SaveError::Io(_) = e {} else { save::discard(); }
+      match fullscreen_message(window, format!("Can't continue: {}", e).as_str(), 0.4, 0.0, 0.0)
+      {
+        true => State::Title,
+        false => State::Quit
+      }
+    }
+  }
+}
+
+/* the options menu: step through the difficulties and camera views */
+fn options_menu(window: &mut Window, flow: &mut Flow) -> State
+{
+  let mut selected = 0;
+
+  loop
+  {
+    let difficulty = format!("Difficulty: {}", flow.options.difficulty.profile.title());
+    let camera = format!("Camera view: {}", flow.director.get_mode().title());
+    let items = [ difficulty.as_str(), camera.as_str(), "Back" ];
+
+    /* picking an option steps it along, then shows the menu again */
+    selected = match fullscreen_menu(window, "Options", &items, selected, None)
+    {
+      Pick::Chosen(0) =>
+      {
+        let next = match flow.difficulties.iter().position(|d| *d == flow.options.difficulty)
+        {
+          Some(index) => (index + 1) % flow.difficulties.len(),
+          None => 0
+        };
+        flow.options.difficulty = flow.difficulties[next];
+        0
+      },
+      Pick::Chosen(1) =>
+      {
+        let mode = flow.director.get_mode().next();
+        flow.director.set_mode(mode);
+        1
+      },
+      Pick::Chosen(_) | Pick::Back | Pick::Idle => return State::Title,
+      Pick::Closed => return State::Quit
+    };
+  }
+}
+
+/* host or join a network game
+ * => window = graphics context
+ *    flow = what the states share, for the difficulty to host at
+ *    network = which network game to play
+ * <= the game to play, or Quit if it couldn't be set up or the window was closed while waiting */
+fn start_network_game(window: &mut Window, flow: &Flow, network: Network) -> State
+{
+  let session = match network
+  {
+    Network::Host(port, mode) => match net::host(port, mode, rand::random::<u64>(), flow.options.difficulty)
+    {
+      Ok(listener) => wait_for_guest(window, &listener, port),
+      Err(e) => Err(e)
+    },
+    Network::Join(address) => net::join(address.as_str()).map(Some)
+  };
+
+  match session
+  {
+    Ok(Some(session)) =>
+    {
+      let state = Game::new(session.mode, session.seed, session.difficulty);
+      State::Playing(Match::new(window, state, None, Pilot::Human, None, Some(session)))
+    },
+    Ok(None) => State::Quit, /* window was closed while waiting */
+    Err(e) =>
+    {
+      fullscreen_message(window, e.to_string().as_str(), 0.4, 0.0, 0.0);
+      State::Quit
+    }
+  }
+}
+
+/* wait for another player to join a hosted game
+ * => window = graphics context
+ *    listener = host waiting for the guest
+ *    port = port number being listened on, to show the player
+ * <= Some(session) when the guest connects, None if the window was closed */
+fn wait_for_guest(window: &mut Window, listener: &net::Listener, port: u16) -> Result<Option<net::Session>, net::NetError>
+{
+  window.set_background_color(0.0, 0.0, 0.4);
+  let font = assets::font(assets::GAME_FONT);
+  let mut director = Director::new(CameraMode::Orbit, 100.0);
+  let text = format!("Waiting for player on port {}", port);
+  let x_start = 100.0 - (text.len() as f32 * 10.0 * 0.5);
+
+  while window.render_with_camera(director.camera())
+  {
+    director.update();
+    window.draw_text(text.as_str(), &Point2::new(x_start, 50.0), 64.0, &font, &Point3::new(1.0, 1.0, 1.0));
+
+    if let Some(session) = listener.poll()?
+    {
+      return Ok(Some(session));
+    }
+  }
+
+  return Ok(None);
+}
+
+/* play back a game recorded with --record
+ * <= the game to play, or Quit if the recording can't be read */
+fn start_replay(window: &mut Window, path: &str) -> State
+{
+  match replay::Tape::load(path)
+  {
+    Ok((state, tape)) => State::Playing(Match::new(window, state, None, Pilot::Replay, Some(tape), None)),
+    Err(e) =>
+    {
+      fullscreen_message(window, format!("Can't play replay: {}", e).as_str(), 0.4, 0.0, 0.0);
+      State::Quit
+    }
+  }
+}
+
+/* play the match's level until it's won or lost, or the players pause the game
+ * => window = graphics context
+ *    flow = camera to view the level with, where the V key cycles through its modes,
+ *           debug overlay, where F3 shows and hides it and F4 turns its profile log on
+ *           and off, and the number of ticks to run each frame
+ *    game = the game being played */
+fn playing(window: &mut Window, flow: &mut Flow, mut game: Box<Match>) -> State
+{
+  window.set_background_color(0.0, 0.0, 0.0);
+  let font = assets::font(assets::GAME_FONT);
+
+  /* network games can't be paused: the other player would be left waiting */
+  let pausable = game.session.is_none();
+  let mut pause = false;
+  let mut closed = false;
+  let mut outcome = None;
+
+  {
+    let Match { state, level, pilot, tape, session, .. } = &mut *game;
+    let level = match level.as_mut()
+    {
+      Some(level) => level,
+      None => return State::GameOver(game)
+    };
+
+    while outcome.is_none() == true && pause == false
+    {
+      if window.render_with_camera(flow.director.camera()) == false
+      {
+        closed = true;
+        break;
+      }
+
+      /* draw the scores, level and lives */
+      level.hud.draw(window, state, &level.playfield);
+
+      if level.waiting == true
+      {
+        window.draw_text("Waiting for other player", &Point2::new(10.0, window.height() as f32 / 3.0), 64.0, &font, &Point3::new(1.0, 1.0, 0.0));
+      }
+
+      flow.overlay.frame(window, &font, &level.scene, &level.playfield);
+
+      /* check events for things like keypresses */
+      for mut event in window.events().iter()
+      {
+        match event.value
+        {
+          /* escape pauses the game, and mustn't go on to close the window when it's let go */
+          WindowEvent::Key(Key::Escape, action, _) if pausable == true =>
+          {
+            pause = pause == true || action == Action::Press;
+            event.inhibited = true;
+          },
+
+          /* show or hide the debug overlay, and start or stop logging it */
+          WindowEvent::Key(Key::F3, Action::Press, _) =>
+          {
+            flow.overlay.toggle();
+            event.inhibited = true;
+          },
+          WindowEvent::Key(Key::F4, Action::Press, _) =>
+          {
+            if let Err(e) = flow.overlay.toggle_log()
+            {
+              eprintln!("rustinvaders: could not start the profile log: {}", e);
+            }
+            event.inhibited = true;
+          },
+
+          /* switch to the next camera view */
+          WindowEvent::Key(Key::V, Action::Press, _) =>
+          {
+            let mode = flow.director.get_mode().next();
+            flow.director.set_mode(mode);
+            event.inhibited = true;
+          },
+
+          /* handle a keypress, and stop keypresses going through to the default handler */
+          WindowEvent::Key(_, _, _) =>
+          {
+            level.keyboard.handle(&event.value);
+            event.inhibited = true;
+          },
+
+          /* ignore mouse events */
+          WindowEvent::MouseButton(_, _, _) => event.inhibited = true,
+          WindowEvent::Scroll(_, _, _) => event.inhibited = true,
+
+          _ => {} /* pass on other events to the default handlers */
+        }
+      }
+
+      if pause == true
+      {
+        break;
+      }
+
+      /* the joysticks don't send events, so see what's pushed on them once a frame */
+      level.keyboard.poll_sticks();
+
+      /* run as many ticks as are due, so the game goes at the same speed whatever the frame rate */
+      level.ticks_due = level.ticks_due + flow.ticks_per_frame;
+      while level.ticks_due >= 1.0 && outcome.is_none() == true
+      {
+        level.ticks_due = level.ticks_due - 1.0;
+
+        /* work out what each player is holding down for this tick. in a network game,
+         * this means swapping controls with the other player */
+        let controls = match session.as_mut()
+        {
+          None => match (*pilot, level.bot.as_mut())
+          {
+            (Pilot::Replay, _) => match tape.as_mut().and_then(|t| t.play(state.roles().len()))
+            {
+              Some(controls) => Some(controls),
+              None =>
+              {
+                /* the recording has run out */
+                outcome = Some(LevelOutcome::Suspended);
+                break;
+              }
+            },
+            (_, Some(bot)) => Some(vec![ bot.think(&level.playfield) ]),
+            _ => Some(local_controls(state, &level.keyboard))
+          },
+          Some(session) => match session.exchange(level.keyboard.either())
+          {
+            Ok(controls) => controls,
+            Err(e) =>
+            {
+              outcome = Some(LevelOutcome::Disconnected(e.to_string()));
+              break;
+            }
+          }
+        };
+
+        /* if we're waiting on the network, try again next frame rather than piling up ticks */
+        level.waiting = controls.is_none();
+        let controls = match controls
+        {
+          Some(controls) => controls,
+          None =>
+          {
+            level.ticks_due = 0.0;
+            break;
+          }
+        };
+
+        if *pilot != Pilot::Replay
+        {
+          if let Some(tape) = tape.as_mut()
+          {
+            tape.record(&controls);
+          }
+        }
+
+        /* run the game for a tick and update the scene */
+        let playfield = &mut level.playfield;
+        outcome = flow.overlay.time_tick(|| playfield.step(state, &controls));
+
+        if let Some(session) = session.as_mut()
+        {
+          if let Err(e) = session.verify(level.playfield.state_hash())
+          {
+            outcome = Some(LevelOutcome::Disconnected(e.to_string()));
+          }
+        }
+
+        level.scene.update(&level.playfield);
+        flow.director.watch(&level.playfield);
+      }
+
+      flow.director.update();
+    }
+  }
+
+  if closed == true
+  {
+    return quit(flow, game);
+  }
+  if pause == true
+  {
+    return State::Paused(game);
+  }
+
+  match outcome
+  {
+    Some(LevelOutcome::Victory) =>
+    {
+      game.end_level();
+      State::LevelComplete(game)
+    },
+    Some(LevelOutcome::Suspended) =>
+    {
+      game.ending = Some(String::from("Replay ended"));
+      State::GameOver(game)
+    },
+    Some(LevelOutcome::Disconnected(reason)) =>
+    {
+      game.ending = Some(reason);
+      State::GameOver(game)
+    },
+    Some(LevelOutcome::Died) | None => State::GameOver(game)
+  }
+}
+
+/* hold the game with a menu over the frozen playfield: carry on, save the game for later
+ * if it can be, or end it. escape carries on too */
+fn paused(window: &mut Window, flow: &mut Flow, mut game: Box<Match>) -> State
+{
+  let font = assets::font(assets::GAME_FONT);
+  let mut items = vec![ "Resume" ];
+  if game.saveable() == true
+  {
+    items.push("Save and quit to title");
+  }
+  items.push("End game");
+
+  let mut selected = 0;
+  let mut chosen = None;
+  while chosen.is_none() == true
+  {
+    if window.render_with_camera(flow.director.camera()) == false
+    {
+      return quit(flow, game);
+    }
+    flow.director.update();
+
+    if let Some(level) = game.level.as_ref()
+    {
+      level.hud.draw(window, &game.state, &level.playfield);
+    }
+    draw_menu(window, &font, "Paused", &items, selected);
+
+    for mut event in window.events().iter()
+    {
+      match event.value
+      {
+        WindowEvent::Key(Key::Escape, Action::Press, _) => chosen = Some(0),
+        WindowEvent::Key(code, Action::Press, _) =>
+        {
+          if steer_menu(code, &mut selected, items.len()) == true
+          {
+            chosen = Some(selected);
+          }
+        },
+        _ => {}
+      }
+      ignore_input(&mut event);
+    }
+  }
+
+  match chosen.unwrap_or(0)
+  {
+    /* keys may have been let go while paused */
+    0 =>
+    {
+      if let Some(level) = game.level.as_mut()
+      {
+        level.keyboard.clear();
+      }
+      State::Playing(game)
+    },
+
+    /* the last option ends the game */
+    n if n + 1 == items.len() => State::GameOver(game),
+
+    _ =>
+    {
+      let saved = game.save();
+      game.finish(flow.options.record.as_ref());
+      match saved
+      {
+        Ok(()) => State::Title,
+        Err(e) => match fullscreen_message(window, format!("Could not save the game: {}", e).as_str(), 0.4, 0.0, 0.0)
+        {
+          true => State::Title,
+          false => State::Quit
+        }
+      }
+    }
+  }
+}
+
+/* show end of level congratulations with white, and how the level went, then start the next */
+fn level_complete(window: &mut Window, flow: &mut Flow, mut game: Box<Match>) -> State
+{
+  if fullscreen_report(window, "Level complete :)", &game.state.stats.level_summary(), 0.0, 0.4, 0.0) == false
+  {
+    return quit(flow, game);
+  }
+
+  game.next_level(window);
+  return State::Playing(game);
+}
+
+/* show the bad news with white on red, and how the game went. in versus mode, show the
+ * final scores. games played by people good enough for the high-score table go on to have
+ * the player's initials added */
+fn game_over(window: &mut Window, flow: &mut Flow, game: Box<Match>) -> State
+{
+  let pilot = game.pilot;
+  let ending = game.ending.clone();
+  let state = game.finish(flow.options.record.as_ref());
+
+  let text = match (ending, state.mode)
+  {
+    (Some(reason), _) => reason,
+    (None, GameMode::Single) | (None, GameMode::Coop) => format!("Game over :(  Score {:07}", state.score),
+    (None, GameMode::Versus) => format!("Hero {:07} Aliens {:07}", state.score, state.alien_score)
+  };
+  let shown = fullscreen_report(window, text.as_str(), &state.stats.game_summary(), 0.4, 0.0, 0.0);
+
+  /* only keep stats on games played by people */
+  if pilot == Pilot::Human
+  {
+    if shown == true && stats::makes_table(&state) == true
+    {
+      return State::HighScoreEntry(Box::new(state));
+    }
+    record_stats(&state, None);
+  }
+
+  match shown
+  {
+    true => flow.after_game(),
+    false => State::Quit
+  }
+}
+
+/* ask for the player's initials for the high-score table, then record the game with them.
+ * return, or escape, signs off. leaving the initials blank records the game unsigned */
+fn high_score_entry(window: &mut Window, flow: &mut Flow, state: Box<Game>) -> State
+{
+  let mut backdrop = Backdrop::new(window, 0.0, 0.0, 0.4);
+  let font = assets::font(assets::GAME_FONT);
+  let score = format!("Score {:07}", state.score);
+  let mut initials = String::new();
+  let mut signed = false;
+  let mut closed = false;
+
+  while signed == false
+  {
+    if backdrop.frame(window) == false
+    {
+      closed = true;
+      break;
+    }
+
+    window.draw_text("New high score!", &Point2::new(50.0, 50.0), 64.0, &font, &Point3::new(1.0, 1.0, 1.0));
+    window.draw_text(score.as_str(), &Point2::new(50.0, 80.0), 64.0, &font, &Point3::new(0.9, 0.9, 0.9));
+    window.draw_text("Type your initials and press return", &Point2::new(50.0, 110.0), 48.0, &font, &Point3::new(0.8, 0.8, 0.8));
+    window.draw_text(format!("{:_<width$}", initials, width = stats::MAX_INITIALS).as_str(),
+                     &Point2::new(50.0, 140.0), 96.0, &font, &Point3::new(1.0, 0.8, 0.2));
+
+    for mut event in window.events().iter()
+    {
+      match event.value
+      {
+        WindowEvent::Char(letter) if letter.is_ascii_alphanumeric() == true && initials.len() < stats::MAX_INITIALS =>
+          initials.push(letter.to_ascii_uppercase()),
+        WindowEvent::Key(Key::Back, Action::Press, _) =>
+        {
+          initials.pop();
+        },
+        WindowEvent::Key(Key::Return, Action::Press, _) | WindowEvent::Key(Key::Escape, Action::Press, _) => signed = true,
+        _ => {}
+      }
+      ignore_input(&mut event);
+    }
+  }
+
+  backdrop.delete();
+
+  let initials = match initials.is_empty()
+  {
+    true => None,
+    false => Some(initials.as_str())
+  };
+  record_stats(&state, initials);
+
+  match closed
+  {
+    true => State::Quit,
+    false => flow.after_game()
+  }
+}
+
+/* attract mode, part one: the bot plays a game at the picked difficulty with GAME OVER
+ * shown over it, like an arcade machine waiting for someone to put a coin in. it plays
+ * until it dies or the demo's time is up. any key goes back to the title menu */
+fn demo(window: &mut Window, flow: &mut Flow) -> State
+{
+  window.set_background_color(0.0, 0.0, 0.0);
+  let font = assets::font(assets::GAME_FONT);
+  let mut state = flow.options.new_game(GameMode::Single, rand::random::<u64>());
+  let mut level = Level::new(window, &mut state, None, Pilot::Cpu);
+  let length = DEMO_LENGTH * flow.options.fps;
+  let mut frames = 0;
+  let mut next = None;
+
+  while next.is_none() == true
+  {
+    if window.render_with_camera(flow.director.camera()) == false
+    {
+      next = Some(State::Quit);
+      break;
+    }
+
+    level.hud.draw(window, &state, &level.playfield);
+    let y = window.height() as f32 / 3.0;
+    window.draw_text("GAME OVER", &Point2::new(10.0, y), 96.0, &font, &Point3::new(1.0, 0.2, 0.2));
+    draw_press_start(window, &font, frames, flow.options.fps, y + 40.0);
+
+    for mut event in window.events().iter()
+    {
+      if let WindowEvent::Key(_, Action::Press, _) = event.value
+      {
+        next = Some(State::Title);
+      }
+      ignore_input(&mut event);
+    }
+
+    /* the bot carries on to the next level if it clears one */
+    level.ticks_due = level.ticks_due + flow.ticks_per_frame;
+    while level.ticks_due >= 1.0 && next.is_none() == true
+    {
+      level.ticks_due = level.ticks_due - 1.0;
+
+      let controls = match level.bot.as_mut()
+      {
+        Some(bot) => vec![ bot.think(&level.playfield) ],
+        None => break
+      };
+
+      match level.playfield.step(&mut state, &controls)
+      {
+        Some(LevelOutcome::Victory) =>
+        {
+          level.scene.delete();
+          level = Level::new(window, &mut state, None, Pilot::Cpu);
+        },
+        Some(_) => next = Some(State::HighScoreTable),
+        None => {}
+      }
+
+      level.scene.update(&level.playfield);
+      flow.director.watch(&level.playfield);
+    }
+
+    flow.director.update();
+
+    frames = frames + 1;
+    if frames >= length && next.is_none() == true
+    {
+      next = Some(State::HighScoreTable);
+    }
+  }
+
+  level.scene.delete();
+  return next.unwrap_or(State::Title);
+}
+
+/* attract mode, part two: the best scores at the picked difficulty */
+fn high_score_table(window: &mut Window, flow: &Flow) -> State
+{
+  let mut lines: Vec<String> = stats::high_scores(&flow.options.difficulty).iter().enumerate()
+    .map(|(rank, (initials, score))| format!("{:>2}. {:<3} {:07}", rank + 1, initials, score))
+    .collect();
+  if lines.is_empty() == true
+  {
+    lines.push(String::from("No high scores yet"));
+  }
+
+  let title = format!("High scores: {}", flow.options.difficulty.profile.title());
+  return attract_page(window, flow, title.as_str(), &lines, State::ScoreAdvanceTable);
+}
+
+/* attract mode, part three: the points scored for shooting down each species of invader,
+ * and the parts of the mothership */
+fn score_advance_table(window: &mut Window, flow: &Flow) -> State
+{
+  let mut lines: Vec<String> = aliens::SPECIES.iter()
+    .map(|species| format!("{:<18} {:>5} points", species.title(), aliens::ALIEN_POINTS))
+    .collect();
+  lines.push(format!("{:<18} {:>5} points", "Mothership section", boss::SECTION_POINTS));
+  lines.push(format!("{:<18} {:>5} points", "Mothership core", boss::MOTHERSHIP_POINTS));
+
+  return attract_page(window, flow, "Score advance table", &lines, State::Title);
+}
+
+/* the window's been closed mid-game. save the game to carry on with later if it can be,
+ * otherwise record how it went if it was played by people, then quit */
+fn quit(flow: &mut Flow, mut game: Box<Match>) -> State
+{
+  let pilot = game.pilot;
+  if game.saveable() == true
+  {
+    /* there's no window left to show an error in */
+    if let Err(e) = game.save()
+    {
+      eprintln!("rustinvaders: could not save the game: {}", e);
+    }
+    game.finish(flow.options.record.as_ref());
+    return State::Quit;
+  }
+
+  let state = game.finish(flow.options.record.as_ref());
+  if pilot == Pilot::Human
+  {
+    record_stats(&state, None);
+  }
+  return State::Quit;
+}
+
+/* add a finished game's stats to the stats file. losing stats isn't worth stopping for
+ * => state = the finished game
+ *    initials = the player's initials if the game made the high-score table, or None */
+fn record_stats(state: &Game, initials: Option<&str>)
+{
+  if let Err(e) = stats::record(state, initials)
+  {
+    eprintln!("rustinvaders: could not record game stats: {}", e);
+  }
+}
+
+/* return what each player at this keyboard is holding down, in player order. with one
+ * player, either set of keys will do. with two, player one has the primary keys and
+ * player two the secondary keys */
+fn local_controls(state: &Game, keyboard: &input::Keyboard) -> Vec<input::Controls>
+{
+  match state.roles().len()
+  {
+    1 => vec![ keyboard.either() ],
+    _ => vec![ keyboard.get(input::Layout::Primary), keyboard.get(input::Layout::Secondary) ]
+  }
+}
+
+/* ------------------------------------------------------------------------------ */
+
+/* fullscreen_message
+   render basic fullscreen text message with spinning black alien at the top.
+   => window = graphics context
+      text = message to display using white characters
+      r, g, b = background color
+  <= returns true when space is pressed, or false if the window was closed
+*/
+fn fullscreen_message(window: &mut Window, text: &str, r: f32, g: f32, b: f32) -> bool
+{
+  fullscreen_report(window, text, &[], r, g, b)
+}
+
+/* fullscreen_report
+   like fullscreen_message, with extra lines of smaller text below the message.
+   => window = graphics context
+      text = message to display using white characters
+      lines = details to list under the message
+      r, g, b = background color
+  <= returns true when space is pressed, or false if the window was closed
+*/
+fn fullscreen_report(window: &mut Window, text: &str, lines: &[String], r: f32, g: f32, b: f32) -> bool
+{
+  let mut backdrop = Backdrop::new(window, r, g, b);
+  let font = assets::font(assets::GAME_FONT);
+  let x_start = 100.0 - (text.len() as f32 * 10.0 * 0.5);
+  let mut key_press = false;
+  let mut open = true;
+
+  while key_press == false
+  {
+    open = backdrop.frame(window);
+    if open == false
+    {
+      break;
+    }
+
+    window.draw_text(text, &Point2::new(x_start, 50.0), 64.0, &font, &Point3::new(1.0, 1.0, 1.0));
+    window.draw_text("Press space to continue",
+                     &Point2::new(50.0, 80.0), 64.0, &font, &Point3::new(0.9, 0.9, 0.9));
+
+    for (index, line) in lines.iter().enumerate()
+    {
+      window.draw_text(line.as_str(), &Point2::new(50.0, 110.0 + (index as f32 * 15.0)), 48.0, &font,
+                       &Point3::new(0.8, 0.8, 0.8));
+    }
+
+    for mut event in window.events().iter()
+    {
+      if let WindowEvent::Key(Key::Space, Action::Press, _) = event.value
+      {
+        key_press = true;
+      }
+      ignore_input(&mut event);
+    }
+  }
+
+  /* destroy the alien immediately */
+  backdrop.delete();
+  return open;
+}
+
+/* fullscreen_menu
+   render a fullscreen list of options with spinning black alien at the top.
+   => window = graphics context
+      title = heading to display using white characters
+      items = options to list under the heading
+      selected = index into items of the option to start with selected
+      idle = frames to wait for a keypress before giving up, or None to wait forever
+  <= returns the index into items of the option chosen with space or return, or whether
+     escape was pressed, nothing was pressed in time, or the window closed instead. up and
+     down move the selection
+*/
+fn fullscreen_menu(window: &mut Window, title: &str, items: &[&str], mut selected: usize, idle: Option<u64>) -> Pick
+{
+  let (r, g, b) = MENU_BACKGROUND;
+  let mut backdrop = Backdrop::new(window, r, g, b);
+  let font = assets::font(assets::GAME_FONT);
+  let mut pick = None;
+  let mut frames = 0;
+
+  while pick.is_none() == true
+  {
+    if backdrop.frame(window) == false
+    {
+      pick = Some(Pick::Closed);
+      break;
+    }
+
+    /* give up after a while without a keypress. any key starts the wait over */
+    frames = frames + 1;
+    if let Some(idle) = idle
+    {
+      if frames > idle
+      {
+        pick = Some(Pick::Idle);
+        break;
+      }
+    }
+
+    draw_menu(window, &font, title, items, selected);
+
+    for mut event in window.events().iter()
+    {
+      match event.value
+      {
+        WindowEvent::Key(Key::Escape, Action::Press, _) => pick = Some(Pick::Back),
+        WindowEvent::Key(code, Action::Press, _) =>
+        {
+          if steer_menu(code, &mut selected, items.len()) == true
+          {
+            pick = Some(Pick::Chosen(selected));
+          }
+          frames = 0;
+        },
+        _ => {}
+      }
+      ignore_input(&mut event);
+    }
+  }
+
+  /* destroy the alien immediately */
+  backdrop.delete();
+
+  return pick.unwrap_or(Pick::Closed);
+}
+
+/* show a page of attract mode over the spinning black alien for a few seconds
+ * => window = graphics context
+ *    flow = what the states share, for the frame rate
+ *    title = heading to display using white characters
+ *    lines = the page's table, listed under the heading
+ *    next = state to go to once the page has been shown
+ * <= next, or Title if a key was pressed, or Quit if the window was closed */
+fn attract_page(window: &mut Window, flow: &Flow, title: &str, lines: &[String], next: State) -> State
+{
+  let mut backdrop = Backdrop::new(window, 0.0, 0.0, 0.0);
+  let font = assets::font(assets::GAME_FONT);
+  let x_start = 100.0 - (title.len() as f32 * 10.0 * 0.5);
+  let mut woken = None;
+
+  for frame in 0..ATTRACT_PAGE_LENGTH * flow.options.fps
+  {
+    if backdrop.frame(window) == false
+    {
+      woken = Some(State::Quit);
+      break;
+    }
+
+    window.draw_text(title, &Point2::new(x_start, 50.0), 64.0, &font, &Point3::new(1.0, 1.0, 1.0));
+    for (index, line) in lines.iter().enumerate()
+    {
+      window.draw_text(line.as_str(), &Point2::new(50.0, 80.0 + (index as f32 * 20.0)), 64.0, &font,
+                       &Point3::new(0.9, 0.9, 0.9));
+    }
+    draw_press_start(window, &font, frame, flow.options.fps, 90.0 + (lines.len() as f32 * 20.0));
+
+    for mut event in window.events().iter()
+    {
+      if let WindowEvent::Key(_, Action::Press, _) = event.value
+      {
+        woken = Some(State::Title);
+      }
+      ignore_input(&mut event);
+    }
+
+    if woken.is_some() == true
+    {
+      break;
+    }
+  }
+
+  /* destroy the alien immediately */
+  backdrop.delete();
+
+  return woken.unwrap_or(next);
+}
+
+/* flash PRESS START on and off once a second, as arcade machines do between games
+ * => window = graphics context
+ *    font = font to draw with
+ *    frame = frames drawn since the page started
+ *    fps = frames drawn each second
+ *    y = how far down the window to draw it */
+fn draw_press_start(window: &mut Window, font: &Rc<Font>, frame: u64, fps: u64, y: f32)
+{
+  if frame % fps.max(1) < fps / 2
+  {
+    window.draw_text("PRESS START", &Point2::new(50.0, y), 64.0, font, &Point3::new(1.0, 0.8, 0.2));
+  }
+}
+
+/* draw a heading, and a list of options under it with the selected one marked
+ * => window = graphics context
+ *    font = font to draw with
+ *    title = heading to display using white characters
+ *    items = options to list under the heading
+ *    selected = index into items of the selected option */
+fn draw_menu(window: &mut Window, font: &Rc<Font>, title: &str, items: &[&str], selected: usize)
+{
+  let x_start = 100.0 - (title.len() as f32 * 10.0 * 0.5);
+  window.draw_text(title, &Point2::new(x_start, 50.0), 64.0, font, &Point3::new(1.0, 1.0, 1.0));
+
+  for (index, item) in items.iter().enumerate()
+  {
+    let (marker, shade) = match index == selected
+    {
+      true => ("> ", 1.0),
+      false => ("  ", 0.7)
+    };
+
+    window.draw_text(format!("{}{}", marker, item).as_str(),
+                     &Point2::new(50.0, 80.0 + (index as f32 * 20.0)), 64.0, font,
+                     &Point3::new(shade, shade, shade));
+  }
+}
+
+/* move a menu's selection with up and down
+ * => code = key pressed
+ *    selected = index of the selected option, updated if it moves
+ *    count = number of options in the menu
+ * <= true if the selected option was chosen with space or return */
+fn steer_menu(code: Key, selected: &mut usize, count: usize) -> bool
+{
+  match code
+  {
+    Key::Up => *selected = selected.saturating_sub(1),
+    Key::Down => *selected = (*selected + 1).min(count.saturating_sub(1)),
+    Key::Space | Key::Return => return true,
+    _ => {}
+  }
+  return false;
+}
+
+/* stop keypresses and the mouse reaching the window's default handlers, which would
+ * otherwise close the window when escape is let go and move the camera with the mouse */
+fn ignore_input(event: &mut Event)
+{
+  match event.value
+  {
+    WindowEvent::Key(_, _, _) => event.inhibited = true,
+    WindowEvent::Char(_) => event.inhibited = true,
+    WindowEvent::MouseButton(_, _, _) => event.inhibited = true,
+    WindowEvent::Scroll(_, _, _) => event.inhibited = true,
+    _ => {} /* pass on other events to the default handlers */
+  }
+}
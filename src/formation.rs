@@ -0,0 +1,436 @@
+/* Space invaders in Rust
+ *
+ * Formations: which alien sits where in the squadron at the start of each level
+ *
+ * Game concept by Tomohiro Nishikado / Taito
+ * Rust code By Chris Williams <diodesign@tuta.io>
+ *
+ * Written for fun. See LICENSE.
+ *
+ */
+
+/* formations are described in text files, so new waves can be designed without touching
+ * the code. the files live in formations/ among the game's assets. waves.txt lists the
+ * formation to use for each level, one name per line, starting over from the top after
+ * the last. a level listed as mothership is a boss fight instead. each formation is in a
+ * file of its own, named after it, made up of lines of:
+ *
+ *   top <height>           height of the top row, in whole aliens above the playfield's center
+ *   twist <rotation>       extra starting rotation for each column across, in radians
+ *   row <phase> <cells>    a row of the formation, from the top row down. phase is the row's
+ *                          starting rotation, and cells has a letter for each alien in the row
+ *                          from screen left to right: S for a squid, C for a crab, O for an
+ *                          octopus, or . to leave a gap
+ *
+ * anything after a # is a comment. every row must be the same width, and a formation
+ * narrower than the squadron is centered on the playfield. call load() once at startup */
+
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::str;
+
+use super::aliens::{ Species, ALIENS_PER_ROW, ALIEN_ROWS, ALIEN_TOP_Y };
+use super::assets::{ self, AssetError };
+
+/* the asset listing the formation for each level */
+pub const WAVES: &str = "formations/waves.txt";
+
+/* the formation used when none have been loaded, built into the executable */
+const DEFAULT_FORMATION: &str = "classic";
+
+/* the name in the list of waves that stands for a boss fight */
+pub const MOTHERSHIP: &str = "mothership";
+
+/* a row of a formation */
+struct Row
+{
+  phase: f32,                  /* starting rotation of the row's aliens */
+  cells: Vec<Option<Species>>  /* from screen left to right, None for a gap */
+}
+
+/* the layout of a squadron at the start of a level */
+pub struct Formation
+{
+  name: String,
+  top: i32,      /* height of the top row, in whole number of aliens from game world center */
+  twist: f32,    /* extra starting rotation for each column */
+  rows: Vec<Row> /* from the top row down */
+}
+
+/* what each level has in store */
+#[derive(Clone)]
+pub enum Wave
+{
+  Squadron(Rc<Formation>), /* a squadron lined up in the given formation */
+  Mothership               /* a boss fight against the mothership */
+}
+
+thread_local!
+{
+  /* the wave for each level in turn, loaded by load() */
+  static LOADED_WAVES: RefCell<Vec<Wave>> = RefCell::new(Vec::new());
+}
+
+/* returns the path of the asset describing the named formation */
+fn asset_name(name: &str) -> String
+{
+  format!("formations/{}.txt", name)
+}
+
+/* returns the words of each line of a formation or wave file that has something on it,
+ * numbered from 1, with comments stripped out */
+fn lines(text: &str) -> Vec<(usize, Vec<&str>)>
+{
+  text.lines().enumerate()
+              .map(|(number, line)| (number + 1, line.split('#').next().unwrap_or("").split_whitespace().collect::<Vec<&str>>()))
+              .filter(|(_, words)| words.is_empty() == false)
+              .collect()
+}
+
+/* read in an asset as text */
+fn read_text(name: &str) -> Result<String, AssetError>
+{
+  match String::from_utf8(assets::read(name)?)
+  {
+    Ok(text) => Ok(text),
+    Err(_) => Err(AssetError::Corrupt(String::from(name)))
+  }
+}
+
+impl Formation
+{
+  /* make sense of a formation file
+   * => name = the formation's name
+   *    text = contents of the formation's file
+   * <= the formation, or the reason the file can't be used */
+  pub fn parse(name: &str, text: &str) -> Result<Formation, String>
+  {
+    let mut formation = Formation
+    {
+      name: String::from(name),
+      top: ALIEN_TOP_Y - 1,
+      twist: 0.0,
+      rows: Vec::new()
+    };
+
+    for (number, words) in lines(text)
+    {
+      let number_of = |word: &str| match word.parse::<f32>()
+      {
+        Ok(value) if value.is_finite() == true => Ok(value),
+        _ => Err(format!("line {}: expected a number, found {}", number, word))
+      };
+
+      match words.as_slice()
+      {
+        [ "top", height ] => formation.top = match height.parse::<i32>()
+        {
+          Ok(height) => height,
+          Err(_) => return Err(format!("line {}: expected a whole number of aliens, found {}", number, height))
+        },
+
+        [ "twist", twist ] => formation.twist = number_of(twist)?,
+
+        [ "row", phase, cells ] =>
+        {
+          let mut row = Row { phase: number_of(phase)?, cells: Vec::new() };
+          for letter in cells.chars()
+          {
+            row.cells.push(match letter
+            {
+              '.' => None,
+              letter => match Species::from_letter(letter)
+              {
+                Some(species) => Some(species),
+                None => return Err(format!("line {}: {} isn't a kind of alien", number, letter))
+              }
+            });
+          }
+          formation.rows.push(row);
+        },
+
+        _ => return Err(format!("line {}: can't make sense of {}", number, words.join(" ")))
+      }
+    }
+
+    /* check the formation fits in the squadron and on the playfield */
+    let width = match formation.rows.first()
+    {
+      Some(row) => row.cells.len(),
+      None => return Err(String::from("there are no rows"))
+    };
+    if formation.rows.iter().any(|row| row.cells.len() != width) == true
+    {
+      return Err(String::from("the rows aren't all the same width"));
+    }
+    if width > ALIENS_PER_ROW as usize || formation.rows.len() > ALIEN_ROWS as usize
+    {
+      return Err(format!("it's bigger than {} aliens across by {} rows", ALIENS_PER_ROW, ALIEN_ROWS));
+    }
+    if formation.rows.iter().all(|row| row.cells.iter().all(|cell| cell.is_none())) == true
+    {
+      return Err(String::from("there are no aliens in it"));
+    }
+
+    let rows = formation.rows.len() as i32;
+    if formation.top < rows - 1 || formation.top > ALIEN_TOP_Y - 1
+    {
+      return Err(format!("top must be from {} to {} for {} rows", rows - 1, ALIEN_TOP_Y - 1, rows));
+    }
+
+    return Ok(formation);
+  }
+
+  /* returns a squadron of crabs with no gaps, laid out like the squadron was before there
+   * were formations, in case even the built-in formation can't be read */
+  fn full() -> Formation
+  {
+    Formation
+    {
+      name: String::from("full"),
+      top: ALIEN_TOP_Y - 1,
+      twist: 0.4,
+      /* rows are listed from the top down, but row 0 is the bottom, with the smallest phase */
+      rows: (0..ALIEN_ROWS).rev().map(|row| Row
+      {
+        phase: 0.4 * (ALIEN_TOP_Y - ALIEN_ROWS + row) as f32,
+        cells: vec![ Some(Species::Crab); ALIENS_PER_ROW as usize ]
+      }).collect()
+    }
+  }
+
+  /* returns a formation with no aliens in it, for levels where the squadron sits out */
+  pub fn empty() -> Formation
+  {
+    Formation
+    {
+      name: String::from("empty"),
+      top: ALIEN_TOP_Y - 1,
+      twist: 0.0,
+      rows: Vec::new()
+    }
+  }
+
+  /* returns the formation's name */
+  pub fn get_name(&self) -> &str
+  {
+    self.name.as_str()
+  }
+
+  /* returns the formation's row at the given row of the squadron, counting from 0 at the bottom */
+  fn row(&self, row: i32) -> Option<&Row>
+  {
+    match row >= 0 && row < self.rows.len() as i32
+    {
+      true => self.rows.get(self.rows.len() - 1 - row as usize),
+      false => None
+    }
+  }
+
+  /* returns the species of the alien at the given column and row of the squadron, or
+   * None if there's a gap there. column 0 is on the right of the screen, and row 0 at the bottom */
+  pub fn species(&self, column: i32, row: i32) -> Option<Species>
+  {
+    let cells = match self.row(row)
+    {
+      Some(row) => &row.cells,
+      None => return None
+    };
+
+    /* center the formation across the squadron. screen left is the highest column */
+    let left = (ALIENS_PER_ROW - cells.len() as i32) / 2;
+    let across = ALIENS_PER_ROW - 1 - column - left;
+    match across >= 0 && across < cells.len() as i32
+    {
+      true => cells[across as usize],
+      false => None
+    }
+  }
+
+  /* returns the height of the given row of the squadron, in whole number of aliens from
+   * game world center */
+  pub fn height(&self, row: i32) -> i32
+  {
+    self.top - (self.rows.len() as i32 - 1) + row
+  }
+
+  /* returns the starting rotation of the alien at the given column and row of the squadron */
+  pub fn rotation(&self, column: i32, row: i32) -> f32
+  {
+    let phase = self.row(row).map(|r| r.phase).unwrap_or(0.0);
+    phase + (self.twist * (column - (ALIENS_PER_ROW / 2)) as f32)
+  }
+}
+
+/* load the list of waves and every formation in it, so any problems show up straight away
+ * rather than on the level that uses the broken formation */
+pub fn load() -> Result<(), AssetError>
+{
+  let text = read_text(WAVES)?;
+  let mut formations: Vec<Rc<Formation>> = Vec::new();
+  let mut waves = Vec::new();
+
+  for (number, words) in lines(text.as_str())
+  {
+    let name = match words.as_slice()
+    {
+      [ name ] => *name,
+      _ => return Err(AssetError::Invalid(String::from(WAVES), format!("line {}: expected one formation name", number)))
+    };
+
+    if name == MOTHERSHIP
+    {
+      waves.push(Wave::Mothership);
+      continue;
+    }
+
+    /* formations can be used more than once, but only need loading the once */
+    let formation = match formations.iter().find(|f| f.get_name() == name)
+    {
+      Some(formation) => formation.clone(),
+      None =>
+      {
+        let file = asset_name(name);
+        let formation = match Formation::parse(name, read_text(file.as_str())?.as_str())
+        {
+          Ok(formation) => Rc::new(formation),
+          Err(reason) => return Err(AssetError::Invalid(file, reason))
+        };
+        formations.push(formation.clone());
+        formation
+      }
+    };
+    waves.push(Wave::Squadron(formation));
+  }
+
+  if waves.is_empty() == true
+  {
+    return Err(AssetError::Invalid(String::from(WAVES), String::from("no waves are listed")));
+  }
+
+  LOADED_WAVES.with(|loaded| *loaded.borrow_mut() = waves);
+  return Ok(());
+}
+
+/* returns the wave for the given level, counting from 1. if load() hasn't been called,
+ * the built-in classic formation is used for every level */
+pub fn for_level(level: i32) -> Wave
+{
+  let loaded = LOADED_WAVES.with(|waves|
+  {
+    let waves = waves.borrow();
+    match waves.is_empty()
+    {
+      true => None,
+      false => Some(waves[(level.max(1) - 1) as usize % waves.len()].clone())
+    }
+  });
+
+  match loaded
+  {
+    Some(wave) => wave,
+    None =>
+    {
+      let text = assets::built_in(asset_name(DEFAULT_FORMATION).as_str()).unwrap_or(&[]);
+      let formation = Formation::parse(DEFAULT_FORMATION, str::from_utf8(text).unwrap_or(""));
+      Wave::Squadron(Rc::new(formation.unwrap_or_else(|_| Formation::full())))
+    }
+  }
+}
+
+/* ------------------------------------------------------------------------------ */
+
+#[cfg(test)]
+mod tests
+{
+  use super::*;
+
+  #[test]
+  fn parses_a_formation()
+  {
+    let text = "# a small test formation\n\
+                top 6\n\
+                twist 0.5  # per column\n\
+                row 1.0 S.S\n\
+                row 2.0 COC\n";
+    let formation = match Formation::parse("test", text)
+    {
+      Ok(formation) => formation,
+      Err(reason) => panic!("{}", reason)
+    };
+
+    /* three columns centered on the squadron, with the top row listed first. column 0
+     * is on the right of the screen, and row 0 at the bottom */
+    assert_eq!(formation.get_name(), "test");
+    assert!(formation.species(6, 1) == Some(Species::Squid));
+    assert!(formation.species(5, 1).is_none() == true);
+    assert!(formation.species(4, 1) == Some(Species::Squid));
+    assert!(formation.species(6, 0) == Some(Species::Crab));
+    assert!(formation.species(5, 0) == Some(Species::Octopus));
+    assert!(formation.species(7, 0).is_none() == true);
+    assert!(formation.species(5, 2).is_none() == true);
+
+    assert_eq!(formation.height(1), 6);
+    assert_eq!(formation.height(0), 5);
+    assert_eq!(formation.rotation(5, 1), 1.0);
+    assert_eq!(formation.rotation(6, 0), 2.5);
+  }
+
+  #[test]
+  fn rejects_broken_formations()
+  {
+    let broken =
+    [
+      ("", "no rows"),
+      ("row 1.0 ...", "no aliens"),
+      ("row 1.0 SS\nrow 1.0 SSS", "uneven rows"),
+      ("row 1.0 SXS", "unknown species"),
+      ("row x SSS", "bad phase"),
+      ("twist", "missing value"),
+      ("top 3.5\nrow 1.0 S", "fractional top"),
+      ("top 9\nrow 1.0 S", "top too high"),
+      ("top 0\nrow 1.0 S\nrow 1.0 S", "top too low"),
+      ("row 1.0 SSSSSSSSSSSS", "too wide"),
+      ("row 1 S\nrow 1 S\nrow 1 S\nrow 1 S\nrow 1 S\nrow 1 S", "too deep"),
+      ("bottom 3", "unknown line")
+    ];
+
+    for (text, why) in broken.iter()
+    {
+      assert!(Formation::parse("broken", text).is_err() == true, "accepted a formation with {}", why);
+    }
+  }
+
+  #[test]
+  fn full_squadron_matches_the_original()
+  {
+    /* before formations, the squadron's top row was one alien below ALIEN_TOP_Y, and each
+     * alien started rotated by 0.4 for each alien it was across and up from the center */
+    let full = Formation::full();
+    for row in 0..ALIEN_ROWS
+    {
+      let y = row + ALIEN_TOP_Y - ALIEN_ROWS;
+      assert_eq!(full.height(row), y);
+      for column in 0..ALIENS_PER_ROW
+      {
+        let x = column - (ALIENS_PER_ROW / 2);
+        assert!(full.species(column, row) == Some(Species::Crab));
+        assert!((full.rotation(column, row) - 0.4 * (x + y) as f32).abs() < 0.0001,
+                "column {} row {} starts at the wrong rotation", column, row);
+      }
+    }
+  }
+
+  #[test]
+  fn built_in_formations_parse()
+  {
+    for name in [ DEFAULT_FORMATION, "chevron", "diamond" ].iter()
+    {
+      let text = read_text(asset_name(name).as_str()).ok().unwrap();
+      if let Err(reason) = Formation::parse(name, text.as_str())
+      {
+        panic!("built-in formation {} is broken: {}", name, reason);
+      }
+    }
+  }
+}
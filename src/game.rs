@@ -0,0 +1,828 @@
+/* Space invaders in Rust
+ *
+ * Game simulation: rules that apply to the playfield each tick
+ *
+ * Game concept by Tomohiro Nishikado / Taito
+ * Rust code By Chris Williams <diodesign@tuta.io>
+ *
+ * Written for fun. See LICENSE.
+ *
+ */
+
+use super::aliens;
+use super::boss;
+use super::bullet;
+use super::hero;
+use super::commander;
+use super::collision;
+use super::difficulty::{ Difficulty, Profile };
+use super::formation;
+use super::input::Controls;
+use super::powerup::{ self, Kind };
+use super::save;
+use super::stats::Stats;
+use super::weapon;
+
+/* the simulation runs at a fixed rate, independent of rendering, so that games are
+ * reproducible and networked peers stay in step */
+pub const TICKS_PER_SECOND: u32 = 60;
+
+pub const MAX_SCORE: i32 = 9999999; /* seems like a cool number */
+pub const MAX_LIVES: i32 = 99; /* also a cool number */
+
+/* points awarded to the alien player in versus mode */
+const VERSUS_HIT_POINTS: i32 = 500;       /* for each time the hero is destroyed */
+const VERSUS_INVASION_POINTS: i32 = 2500; /* for getting below the hero before the wave is cleared */
+
+const COOP_HERO_SPACING: f32 = 30.0; /* distance between the two ships at the start of a co-op level */
+
+/* who is playing */
+#[derive(Clone, Copy, PartialEq)]
+pub enum GameMode
+{
+  Single, /* one player against the computer-controlled squadron */
+  Versus, /* player one is the hero, player two commands the squadron */
+  Coop    /* two heroes against the computer-controlled squadron */
+}
+
+/* what each player in a game controls */
+#[derive(Clone, Copy, PartialEq)]
+pub enum Role
+{
+  Hero(usize), /* one of the heroes in the playfield */
+  Commander    /* the alien squadron */
+}
+
+/* maintain state from level to level */
+pub struct Game
+{
+  pub mode: GameMode, /* single player, versus, or co-op */
+  pub seed: u64, /* every level's random decisions are derived from this */
+  pub level: i32, /* number of levels started, from 1 */
+  pub score: i32, /* player's current points score */
+  pub lives: i32, /* player's current number of lives */
+  pub player_x_pos: f32, /* player's ship x-position (y and z are fixed) */
+  pub alien_score: i32, /* alien player's points score in versus mode */
+  pub difficulty: Difficulty, /* how fast things move, and the lives the game started with */
+  pub stats: Stats, /* how the game has been played so far */
+  pub weapons: Vec<(weapon::Kind, u32)>, /* weapon and its level each ship carries into the next level */
+}
+
+impl Game
+{
+  /* start a new game
+   * => mode = who is playing
+   *    seed = seed for the game's random decisions
+   *    difficulty = how hard the game is, including the lives it starts with */
+  pub fn new(mode: GameMode, seed: u64, difficulty: Difficulty) -> Game
+  {
+    Game
+    {
+      mode: mode, seed: seed, level: 0,
+      score: 0, lives: difficulty.lives, player_x_pos: 0.0, alien_score: 0,
+      difficulty: difficulty, stats: Stats::new(), weapons: Vec::new()
+    }
+  }
+
+  /* return what each player controls, in player order */
+  pub fn roles(&self) -> Vec<Role>
+  {
+    match self.mode
+    {
+      GameMode::Single => vec![ Role::Hero(0) ],
+      GameMode::Versus => vec![ Role::Hero(0), Role::Commander ],
+      GameMode::Coop   => vec![ Role::Hero(0), Role::Hero(1) ]
+    }
+  }
+
+  /* return the score line shown at the top of the screen during play */
+  pub fn score_line(&self) -> String
+  {
+    match self.mode
+    {
+      GameMode::Versus => format!("Score: {:07}    Lives: {:02}    Aliens: {:07}",
+                                  self.score, self.lives, self.alien_score),
+      _ => format!("Score: {:07}    Lives: {:02}", self.score, self.lives)
+    }
+  }
+
+  /* write the game's state to a saved game */
+  pub fn save(&self, writer: &mut save::Writer)
+  {
+    writer.label("game");
+    writer.value(match self.mode
+    {
+      GameMode::Single => "single",
+      GameMode::Versus => "versus",
+      GameMode::Coop => "coop"
+    });
+    writer.value(self.seed);
+    writer.value(self.level);
+    writer.value(self.score);
+    writer.value(self.lives);
+    writer.value(self.player_x_pos);
+    writer.value(self.alien_score);
+
+    writer.label("weapons");
+    writer.value(self.weapons.len());
+    for (kind, level) in self.weapons.iter()
+    {
+      writer.value(kind.name());
+      writer.value(level);
+    }
+  }
+
+  /* recreate a game's state from a saved game. the difficulty and stats are kept
+   * separately, so they start out as normal and empty. files from before there were
+   * weapons leave them out, and every ship starts with the classic */
+  pub fn restore(reader: &mut save::Reader) -> Result<Game, save::SaveError>
+  {
+    reader.label("game")?;
+    let mode = match reader.value::<String>()?.as_str()
+    {
+      "single" => GameMode::Single,
+      "versus" => GameMode::Versus,
+      "coop" => GameMode::Coop,
+      other => return Err(reader.unexpected("game mode", other))
+    };
+
+    let mut state = Game
+    {
+      mode: mode, seed: reader.value()?, level: reader.value()?,
+      score: reader.value()?, lives: reader.value()?, player_x_pos: reader.value()?,
+      alien_score: reader.value()?, difficulty: Difficulty::new(Profile::Normal), stats: Stats::new(),
+      weapons: Vec::new()
+    };
+
+    if reader.next_is("weapons") == true
+    {
+      reader.label("weapons")?;
+      let count: usize = reader.value()?;
+      for _ in 0..count
+      {
+        let name = reader.value::<String>()?;
+        match weapon::Kind::from_name(name.as_str())
+        {
+          Some(kind) => state.weapons.push((kind, reader.value()?)),
+          None => return Err(reader.unexpected("weapon", name.as_str()))
+        }
+      }
+    }
+
+    return Ok(state);
+  }
+
+  /* add points to the player's score */
+  fn award_points(&mut self, points: i32)
+  {
+    self.score = self.score + points;
+    if self.score > MAX_SCORE
+    {
+      self.score = MAX_SCORE;
+    }
+  }
+
+  /* give the alien player points in versus mode. does nothing in other modes */
+  fn award_alien_points(&mut self, points: i32)
+  {
+    if self.mode == GameMode::Versus
+    {
+      self.alien_score = self.alien_score + points;
+      if self.alien_score > MAX_SCORE
+      {
+        self.alien_score = MAX_SCORE;
+      }
+    }
+  }
+}
+
+pub enum LevelOutcome
+{
+  Victory, /* player beat the level */
+  Died, /* player ran out of lives */
+  Disconnected(String), /* network game ended early, with the reason why */
+  Suspended /* player stopped mid-level, and the game was saved to continue later */
+}
+
+/* collect up the objects in the playfield */
+pub struct Playfield
+{
+  pub aliens: aliens::Aliens,     /* squadron of enemy aliens to shoot down */
+  pub boss: Option<boss::Mothership>, /* the mothership, on levels with a boss fight */
+  pub heroes: Vec<hero::Hero>,    /* our player heroes */
+  pub powerups: powerup::Powerups, /* capsules dropped by aliens shot down */
+  pub commander: Option<commander::Commander>, /* the alien player, if in versus mode */
+  pub difficulty: Difficulty,     /* the game's difficulty, which sets how fast everything moves */
+  previous: Vec<Controls>         /* controls from the previous tick, to spot new keypresses */
+}
+
+impl Playfield
+{
+  /* create the baddies and heroes for the next level of the given game */
+  pub fn new(state: &mut Game) -> Playfield
+  {
+    state.level = state.level + 1;
+    state.stats.level_ticks.push(0);
+
+    let seed = level_seed(state);
+    let (aliens, boss) = match formation::for_level(state.level)
+    {
+      formation::Wave::Squadron(formation) => (aliens::Aliens::new(seed, &formation), None),
+      formation::Wave::Mothership => (aliens::Aliens::new(seed, &formation::Formation::empty()),
+                                      Some(boss::Mothership::new(seed.rotate_left(32))))
+    };
+
+    /* ships start where the last level left off, with the weapons they finished it with */
+    let arm = |index: usize, x: f32|
+    {
+      let (kind, level) = state.weapons.get(index).cloned().unwrap_or((weapon::Kind::Classic, 1));
+      hero::Hero::new(x, kind, level)
+    };
+    let heroes = match state.mode
+    {
+      GameMode::Coop => vec![ arm(0, state.player_x_pos + COOP_HERO_SPACING / 2.0),
+                              arm(1, state.player_x_pos - COOP_HERO_SPACING / 2.0) ],
+      _ => vec![ arm(0, state.player_x_pos) ]
+    };
+
+    /* in versus mode, player two takes control of the squadron */
+    let commander = match state.mode
+    {
+      GameMode::Versus => Some(commander::Commander::new(&aliens)),
+      _ => None
+    };
+
+    Playfield
+    {
+      aliens: aliens,
+      boss: boss,
+      heroes: heroes,
+      powerups: powerup::Powerups::new(seed.rotate_left(16)),
+      commander: commander,
+      difficulty: state.difficulty,
+      previous: vec![ Controls::default(); state.roles().len() ]
+    }
+  }
+
+  /* run the playfield for one tick
+   * => state = game state variables
+   *    controls = what each player is holding down this tick, in player order
+   * <= Some(outcome) if the level is over, or None to keep going */
+  pub fn step(&mut self, state: &mut Game, controls: &[Controls]) -> Option<LevelOutcome>
+  {
+    state.stats.tick();
+
+    /* update aliens, players and any of their bullets / bombs in play. while a ship has
+     * slowed time down, the aliens and the mothership only move every other tick */
+    let slowed = self.heroes.iter().any(|p| p.has_power(Kind::SlowTime));
+    let aliens_move = self.powerups.animate(slowed);
+    if aliens_move == true
+    {
+      self.aliens.animate(&self.difficulty);
+      if let Some(boss) = self.boss.as_mut()
+      {
+        boss.animate(&self.difficulty);
+      }
+    }
+    for player in self.heroes.iter_mut()
+    {
+      player.animate();
+    }
+    if let Some(commander) = self.commander.as_mut()
+    {
+      commander.animate(&self.aliens);
+    }
+
+    /* stop playing the level if the players are alive and the aliens are all dead, or if we're
+     * out of lives. this check means we keep animating enemy and ship explosions when
+     * the player has shot all the aliens or has run out of lives, rather than bailing out
+     * immediately */
+    let all_alive = self.heroes.iter().all(|p| p.state == hero::State::Alive);
+    let any_dying = self.heroes.iter().any(|p| p.state == hero::State::Dying);
+    let boss_finished = self.boss.as_ref().map_or(true, |b| b.finished());
+    if all_alive == true && self.aliens.all_dead() == true && boss_finished == true
+    {
+      state.weapons = self.heroes.iter().map(|p| (p.get_weapon().kind(), p.get_weapon().level())).collect();
+      return Some(LevelOutcome::Victory);
+    }
+    if any_dying == false && state.lives < 1
+    {
+      return Some(LevelOutcome::Died);
+    }
+
+    /* process each player's controls */
+    let roles = state.roles();
+    for (player, role) in roles.iter().enumerate()
+    {
+      let held = controls.get(player).cloned().unwrap_or_default();
+      let previous = self.previous.get(player).cloned().unwrap_or_default();
+
+      match *role
+      {
+        Role::Hero(index) =>
+        {
+          /* only update the player if it's still alive, otherwise all sorts
+           * of inconsistencies will occur (ship hit by a bomb or alien while dying etc) */
+          let ship = &mut self.heroes[index];
+          if ship.state != hero::State::Alive
+          {
+            continue;
+          }
+
+          /* keep moving in the direction held down, or the way the stick is pushed */
+          ship.steer(held.steering(), self.difficulty.ship_speed, self.difficulty.ship_accel);
+
+          /* special switches to the next weapon */
+          if held.special == true && previous.special == false
+          {
+            ship.switch_weapon();
+          }
+
+          /* player can keep fire button held down. the ship's weapon decides whether
+           * it's ready to fire again, and some weapons fire when it's let go */
+          state.stats.shots = state.stats.shots + ship.trigger(held.fire, self.difficulty.bullet_speed);
+        },
+
+        Role::Commander =>
+        {
+          if let Some(commander) = self.commander.as_mut()
+          {
+            /* pick a new column on each press of left or right */
+            if held.left == true && previous.left == false
+            {
+              commander.select_left(&self.aliens);
+            }
+            if held.right == true && previous.right == false
+            {
+              commander.select_right(&self.aliens);
+            }
+            if held.special == true && previous.special == false
+            {
+              self.aliens.burst();
+            }
+          }
+        }
+      }
+    }
+
+    /* skip firing and collision detection while no players are alive */
+    if self.heroes.iter().any(|p| p.state == hero::State::Alive) == false
+    {
+      self.previous = controls.to_vec();
+      return None;
+    }
+
+    /* in versus mode, aliens only drop bombs and the mothership only attacks when player
+     * two says so. otherwise they attack as soon as they are able, unless time's slowed
+     * down and they're sitting this tick out */
+    let attack = match self.commander.as_mut()
+    {
+      Some(commander) =>
+      {
+        let fire = roles.iter().position(|r| *r == Role::Commander)
+                               .and_then(|p| controls.get(p))
+                               .map_or(false, |c| c.fire) && aliens_move;
+        if fire == true
+        {
+          commander.fire(&mut self.aliens, self.difficulty.bomb_speed);
+        }
+        fire
+      },
+      None =>
+      {
+        if aliens_move == true
+        {
+          self.aliens.fire(self.difficulty.bomb_speed);
+        }
+        aliens_move
+      }
+    };
+    if let (Some(boss), true) = (self.boss.as_mut(), attack)
+    {
+      boss.attack(&self.difficulty);
+    }
+
+    for player in self.heroes.iter_mut().filter(|p| p.state == hero::State::Alive)
+    {
+      /* did any of the player's bullets hit an alien? */
+      let mut index = 0;
+      while index < player.shots.len()
+      {
+        let (x, y, _) = player.shots[index].bullet.get_coords();
+        let (piercing, damage) = (player.shots[index].piercing, player.shots[index].damage);
+
+        /* remove bullets that have gone out of bounds */
+        let mut spent = y > aliens::ALIEN_Y_CEILING;
+        if let Some((_, row, species)) = self.aliens.hit(x, y)
+        {
+          /* the call to hit() removes the alien if there is a hit, but we have to tell
+           * the ship's bullet to blow up too, unless it's a piercing shot */
+          spent = spent || piercing == false;
+          state.award_points(aliens::ALIEN_POINTS);
+          state.stats.kill(row, species);
+          self.powerups.alien_down(x, y);
+        }
+        else if let Some(hit) = self.boss.as_mut().and_then(|b| b.hit(x, y, damage))
+        {
+          /* piercing shots go through escorts, but not the mothership itself */
+          spent = spent || piercing == false || match hit { boss::Hit::Escort(_) => false, _ => true };
+          match hit
+          {
+            boss::Hit::Shielded => {},
+            boss::Hit::Damaged => state.stats.hit(),
+            boss::Hit::Section => { state.award_points(boss::SECTION_POINTS); state.stats.hit(); },
+            boss::Hit::Mothership => { state.award_points(boss::MOTHERSHIP_POINTS); state.stats.hit(); },
+            boss::Hit::Escort(species) =>
+            {
+              state.award_points(aliens::ALIEN_POINTS);
+              state.stats.escort_kill(species);
+              self.powerups.alien_down(x, y);
+            }
+          }
+        }
+
+        match spent
+        {
+          true => { player.shots.remove(index); },
+          false => index = index + 1
+        }
+      }
+
+      /* pick up any capsules the ship has flown into */
+      self.powerups.collect(player);
+      let shielded = player.has_power(Kind::Shield);
+
+      /* did an alien bomb hit the player? a shield soaks it up */
+      if self.aliens.bomb.is_some() == true
+      {
+        let (x, y, _) = self.aliens.bomb.as_ref().unwrap().get_coords();
+        if player.collision(x, y) == collision::CollisionOutcome::Hit
+        {
+          /* tell aliens to blow up their bomb, and the player its ship, if there is a hit */
+          self.aliens.destroy_bomb();
+          if shielded == false
+          {
+            player.destroy();
+            state.lives = state.lives - 1;
+            state.stats.lost_to_bombs = state.stats.lost_to_bombs + 1;
+            state.award_alien_points(VERSUS_HIT_POINTS);
+            continue;
+          }
+        }
+      }
+
+      /* did one of the mothership's bombs hit the player? */
+      if let Some(boss) = self.boss.as_mut()
+      {
+        let hit = boss.bombs.iter().position(|b|
+        {
+          let (x, y, _) = b.get_coords();
+          player.collision(x, y) == collision::CollisionOutcome::Hit
+        });
+        if let Some(index) = hit
+        {
+          boss.bombs.remove(index);
+          if shielded == false
+          {
+            player.destroy();
+            state.lives = state.lives - 1;
+            state.stats.lost_to_bombs = state.stats.lost_to_bombs + 1;
+            state.award_alien_points(VERSUS_HIT_POINTS);
+            continue;
+          }
+        }
+      }
+
+      /* get the player's x, y coords */
+      let (player_x_pos, player_y_pos, _) = player.get_coords();
+
+      /* did an alien, or one of the mothership's escorts, fly into the player? either way
+       * the alien blows up, but a shield saves the ship */
+      let escort = self.boss.as_mut().map_or(collision::CollisionOutcome::Miss, |b| b.collision(player_x_pos, player_y_pos));
+      if (self.aliens.collision(player_x_pos, player_y_pos) == collision::CollisionOutcome::Hit ||
+          escort == collision::CollisionOutcome::Hit) && shielded == false
+      {
+        player.destroy();
+        state.lives = state.lives - 1;
+        state.stats.lost_to_aliens = state.stats.lost_to_aliens + 1;
+        state.award_alien_points(VERSUS_HIT_POINTS);
+        continue;
+      }
+
+      /* did the aliens manage to get below the player? if so, that's an instant
+       * game over, I'm afraid */
+      if self.aliens.lowest_y() <= player_y_pos
+      {
+        player.destroy();
+        state.stats.lost_to_invasion = state.stats.lost_to_invasion + state.lives.max(0) as u32;
+        state.lives = 0;
+        state.award_alien_points(VERSUS_INVASION_POINTS);
+      }
+    }
+
+    /* remove the bomb if it goes out of bounds */
+    if let Some((_, y, _)) = self.aliens.bomb.as_ref().map(|b| b.get_coords())
+    {
+      if y < hero::HERO_Y_FLOOR
+      {
+        self.aliens.destroy_bomb();
+        state.stats.bombs_dodged = state.stats.bombs_dodged + 1;
+      }
+    }
+    if let Some(boss) = self.boss.as_mut()
+    {
+      state.stats.bombs_dodged = state.stats.bombs_dodged + boss.remove_fallen_bombs();
+    }
+
+    self.previous = controls.to_vec();
+    return None;
+  }
+
+  /* write the playfield to a saved game */
+  pub fn save(&self, writer: &mut save::Writer)
+  {
+    writer.label("playfield");
+    match self.commander.as_ref()
+    {
+      Some(commander) => commander.save(writer),
+      None => writer.none()
+    }
+    writer.value(self.heroes.len());
+    writer.value(self.previous.len());
+    for controls in self.previous.iter()
+    {
+      writer.value(controls.to_bits());
+    }
+
+    self.aliens.save(writer);
+    writer.label("boss");
+    match self.boss.as_ref()
+    {
+      Some(boss) => boss.save(writer),
+      None => writer.none()
+    }
+    self.powerups.save(writer);
+    for ship in self.heroes.iter()
+    {
+      ship.save(writer);
+    }
+  }
+
+  /* recreate a playfield from a saved game
+   * => state = the game the playfield belongs to, already restored */
+  pub fn restore(reader: &mut save::Reader, state: &Game) -> Result<Playfield, save::SaveError>
+  {
+    reader.label("playfield")?;
+    let commander = match reader.none()
+    {
+      true => None,
+      false => Some(commander::Commander::restore(reader)?)
+    };
+
+    let heroes: usize = reader.value()?;
+    let players: usize = reader.value()?;
+    /* make sure the playfield has what the game mode expects, so play can't go wrong later */
+    let roles = state.roles();
+    let ships = roles.iter().filter(|r| if let Role::Hero(_) = r { true } else { false }).count();
+    if players != roles.len() || heroes != ships || commander.is_some() != (state.mode == GameMode::Versus)
+    {
+      return Err(save::SaveError::Corrupt(String::from("playfield doesn't match the game mode")));
+    }
+
+    let mut previous = Vec::with_capacity(players);
+    for _ in 0..players
+    {
+      previous.push(Controls::from_bits(reader.value()?));
+    }
+
+    let aliens = aliens::Aliens::restore(reader)?;
+
+    /* files from before the mothership leave it out */
+    let mut boss = None;
+    if reader.next_is("boss") == true
+    {
+      reader.label("boss")?;
+      if reader.none() == false
+      {
+        boss = Some(boss::Mothership::restore(reader)?);
+      }
+    }
+
+    /* files from before power-ups leave them out too */
+    let powerups = match reader.next_is("powerups")
+    {
+      true => powerup::Powerups::restore(reader)?,
+      false => powerup::Powerups::new(level_seed(state).rotate_left(16))
+    };
+
+    let mut ships = Vec::with_capacity(heroes);
+    for _ in 0..heroes
+    {
+      ships.push(hero::Hero::restore(reader)?);
+    }
+
+    Ok(Playfield
+    {
+      aliens: aliens,
+      boss: boss,
+      heroes: ships,
+      powerups: powerups,
+      commander: commander,
+      difficulty: state.difficulty,
+      previous: previous
+    })
+  }
+
+  /* returns every bomb in play, dropped by the squadron or the mothership */
+  pub fn bombs(&self) -> Vec<&bullet::Bullet>
+  {
+    let mut bombs: Vec<&bullet::Bullet> = self.aliens.bomb.iter().collect();
+    if let Some(boss) = self.boss.as_ref()
+    {
+      bombs.extend(boss.bombs.iter());
+    }
+    return bombs;
+  }
+
+  /* returns every bullet in play, fired by any of the ships */
+  pub fn bullets(&self) -> Vec<&bullet::Bullet>
+  {
+    self.heroes.iter().flat_map(|p| p.bullets()).collect()
+  }
+
+  /* return a hash of the positions of everything in the playfield. peers running the same
+   * simulation must always arrive at the same hash for the same tick */
+  pub fn state_hash(&self) -> u64
+  {
+    let mut hash = Fnv::new();
+
+    for baddie in self.aliens.get_squadron().iter()
+    {
+      let (x, y, _) = baddie.get_coords();
+      hash.add_f32(x);
+      hash.add_f32(y);
+      hash.add(baddie.get_state() as u64);
+    }
+    if let Some(bomb) = self.aliens.bomb.as_ref()
+    {
+      let (x, y, _) = bomb.get_coords();
+      hash.add_f32(x);
+      hash.add_f32(y);
+    }
+
+    if let Some(boss) = self.boss.as_ref()
+    {
+      for section in boss.get_sections().iter()
+      {
+        let (x, y, _) = boss.section_coords(section);
+        hash.add_f32(x);
+        hash.add_f32(y);
+        hash.add(section.get_state() as u64);
+      }
+      for escort in boss.escorts.iter()
+      {
+        let (x, y, _) = escort.get_coords();
+        hash.add_f32(x);
+        hash.add_f32(y);
+        hash.add(escort.get_state() as u64);
+      }
+      for bomb in boss.bombs.iter()
+      {
+        let (x, y, _) = bomb.get_coords();
+        hash.add_f32(x);
+        hash.add_f32(y);
+      }
+    }
+
+    for player in self.heroes.iter()
+    {
+      let (x, y, _) = player.get_coords();
+      hash.add_f32(x);
+      hash.add_f32(y);
+      hash.add_f32(player.get_velocity());
+      hash.add(player.state as u64);
+      for bullet in player.bullets()
+      {
+        let (x, y, _) = bullet.get_coords();
+        hash.add_f32(x);
+        hash.add_f32(y);
+      }
+      hash.add(player.get_weapon().kind() as u64);
+      hash.add(player.get_weapon().level() as u64);
+      for (kind, ticks) in player.powers()
+      {
+        hash.add(kind as u64);
+        hash.add(ticks as u64);
+      }
+    }
+
+    for capsule in self.powerups.capsules.iter()
+    {
+      let (x, y, _) = capsule.get_coords();
+      hash.add_f32(x);
+      hash.add_f32(y);
+      hash.add(capsule.get_kind() as u64);
+    }
+
+    return hash.get();
+  }
+}
+
+/* returns the seed for the game's current level, derived from the game's seed so every
+ * level plays differently */
+fn level_seed(state: &Game) -> u64
+{
+  state.seed ^ (state.level as u64).wrapping_mul(0x9e3779b97f4a7c15)
+}
+
+/* ------------------------------------------------------------------------------ */
+
+/* 64-bit FNV-1a hash, small and good enough for spotting two simulations drifting apart */
+struct Fnv
+{
+  hash: u64
+}
+
+impl Fnv
+{
+  fn new() -> Fnv
+  {
+    Fnv { hash: 0xcbf29ce484222325 }
+  }
+
+  fn add(&mut self, value: u64)
+  {
+    for byte in value.to_le_bytes().iter()
+    {
+      self.hash = (self.hash ^ (*byte as u64)).wrapping_mul(0x100000001b3);
+    }
+  }
+
+  fn add_f32(&mut self, value: f32)
+  {
+    self.add(value.to_bits() as u64);
+  }
+
+  fn get(&self) -> u64
+  {
+    self.hash
+  }
+}
+
+/* ------------------------------------------------------------------------------ */
+
+#[cfg(test)]
+mod tests
+{
+  use super::*;
+  use super::super::aliens::Species;
+  use super::super::formation::Formation;
+
+  #[test]
+  fn every_species_scores_the_same()
+  {
+    /* hold the squadron and its bombs still, so the ship can stay put and shoot */
+    let mut difficulty = Difficulty::new(Profile::Normal);
+    difficulty.march_step = 0.0;
+    difficulty.march_speedup = 0.0;
+    difficulty.bomb_speed = 0.0;
+    let mut state = Game::new(GameMode::Single, 5, difficulty);
+    let mut playfield = Playfield::new(&mut state);
+
+    /* a single column above the ship, a squid over an octopus. the octopus is in the way,
+     * so it's shot down first */
+    let formation = match Formation::parse("test", "row 0 S\nrow 0 O")
+    {
+      Ok(formation) => formation,
+      Err(reason) => panic!("{}", reason)
+    };
+    playfield.aliens = aliens::Aliens::new(5, &formation);
+
+    let mut shot = Vec::new();
+    for tick in 0..(10 * TICKS_PER_SECOND)
+    {
+      let score = state.score;
+      let kills = state.stats.species_kills.clone();
+      let fire = Controls { fire: tick % 2 == 0, ..Controls::default() };
+      let outcome = playfield.step(&mut state, &[ fire ]);
+
+      if state.score != score
+      {
+        assert_eq!(state.score - score, aliens::ALIEN_POINTS);
+        let species: Vec<Species> = aliens::SPECIES.iter().zip(state.stats.species_kills.iter().zip(kills.iter()))
+                                                   .filter(|(_, (after, before))| after > before)
+                                                   .map(|(species, _)| *species)
+                                                   .collect();
+        assert_eq!(species.len(), 1, "scored without shooting down exactly one alien");
+        shot.push(species[0]);
+      }
+
+      if outcome.is_some() == true
+      {
+        break;
+      }
+    }
+
+    assert!(shot == vec![ Species::Octopus, Species::Squid ], "the octopus and then the squid weren't shot down");
+  }
+}
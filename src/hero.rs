@@ -1,6 +1,6 @@
 /* Space invaders in Rust
  *
- * Player graphics
+ * Player's ship
  *
  * Game concept by Tomohiro Nishikado / Taito
  * Rust code By Chris Williams <diodesign@tuta.io>
@@ -9,44 +9,30 @@
  *
  */
 
-extern crate glfw;
-extern crate kiss3d;
-extern crate nalgebra as na;
-extern crate rand;
-
-use std::time::Instant;
-use na::{ Translation3, UnitQuaternion, Vector3 };
-use kiss3d::window::Window;
-use kiss3d::scene::SceneNode;
-
+use super::aliens::{ WALL_LEFT, WALL_RIGHT };
 use super::bullet;
 use super::collision;
-use super::aliens::random_explosion_vector;
+use super::powerup::{ self, Kind };
+use super::save;
+use super::weapon::{ self, Shot, Weapon };
+use super::game::TICKS_PER_SECOND;
 
-const HERO_HEIGHT:     f32 = 13.0;
-const HERO_RADIUS:     f32 = 5.0;
-const HERO_GRAY:       f32 = 0.8;
-const HERO_MOVE_STEP:  f32 = 1.0;
+pub const HERO_HEIGHT:     f32 = 13.0;
+pub const HERO_RADIUS:     f32 = 5.0;
 const HERO_Y_BASE: f32 = -90.0;
+const HERO_DEATH_TICKS: u32 = 5 * TICKS_PER_SECOND; /* time spent exploding before coming back */
 
 pub const HERO_Y_FLOOR: f32 = HERO_Y_BASE - (HERO_HEIGHT / 2.0);
 
 const BULLET_Y_START: f32 = HERO_Y_BASE + (HERO_HEIGHT / 2.0);
-const BULLET_RADIUS:  f32 = 2.0;
-const BULLET_COLOR_R: f32 = 1.0;
-const BULLET_COLOR_G: f32 = 0.0;
-const BULLET_COLOR_B: f32 = 0.0;
-const BULLET_ASCENT:  f32 = 2.0;
-
-/* when the ship explodes, we need to animate its debris particles */
-struct Debris
-{
-  node: SceneNode, /* the object in the game world */
-  x: f32, y: f32, z: f32 /* movement vector */
-}
+
+const RAPID_RELOAD_TICKS: u32 = TICKS_PER_SECOND / 6; /* time between volleys with rapid fire */
+pub const MAX_SHOTS: usize = 15;  /* most shots a ship can have in play */
+const SPREAD_DRIFT: f32 = 0.4;    /* distance the extra shots of a spread power-up move sideways each tick */
+const SPREAD_SHOTS: usize = 3;    /* a spread power-up turns each shot into this many */
 
 /* Player has 3 game states: alive, exploding, or dead */
-#[derive(PartialEq)]
+#[derive(Clone, Copy, PartialEq)]
 pub enum State
 {
   Alive,   /* playing normally */
@@ -54,151 +40,186 @@ pub enum State
   Dead     /* finished exploding, reseting to alive */
 }
 
+/* the hero is pure game state: the renderer draws the ship and its explosion */
 pub struct Hero
 {
   x: f32, y: f32, z: f32,            /* game world coords of the hero's ship */
-  ship: SceneNode,                   /* the ship in the graphics context */
-  time_of_death: Option<Instant>,    /* when the hero started dying */
-  debris: Vec<Debris>,               /* vector array of debris particles when dying */
+  velocity: f32,                     /* distance the ship moves along the x axis each tick */
+  death_ticks: Option<u32>,          /* ticks since the hero started dying */
   pub state: State,                  /* whether the hero is alive, exploding or dead */
-  pub bullet: Option<bullet::Bullet> /* bullet fired by the ship */
+  pub shots: Vec<Shot>,              /* shots fired by the ship */
+  weapon: Box<dyn Weapon>,           /* what the ship fires */
+  reload_ticks: u32,                 /* ticks until the ship can fire again */
+  powers: [u32; powerup::KINDS.len()] /* ticks left of each power-up, by kind */
 }
 
 impl Hero
 {
-  /* create a new ship model at the given point on the x axis */
-  pub fn new(window: &mut Window, x: f32) -> Hero
+  /* create a new ship at the given point on the x axis, kept between the walls
+   * => x = where to put the ship
+   *    weapon, level = the weapon the ship carries, and how upgraded it is */
+  pub fn new(x: f32, weapon: weapon::Kind, level: u32) -> Hero
   {
-    let mut hero = Hero
+    Hero
     {
       state: State::Alive,
-      x: x, y: HERO_Y_BASE, z: 0.0,
-      ship: window.add_cone(HERO_RADIUS, HERO_HEIGHT),
-      bullet: None,
-      time_of_death: None,
-      debris: Vec::new()
-    };
-    
-    hero.ship.append_translation(&Translation3::new(hero.x, hero.y, hero.z));
-    hero.ship.set_color(HERO_GRAY, HERO_GRAY, HERO_GRAY);
-
-    return hero;
-  }
-
-  /* make sure everything is removed from the game world */
-  pub fn delete(&mut self)
-  {
-    self.ship.unlink();
-    self.destroy_bullet();
-
-    for particle in self.debris.iter_mut()
-    {
-      particle.node.unlink();
+      x: x.max(WALL_RIGHT).min(WALL_LEFT), y: HERO_Y_BASE, z: 0.0,
+      velocity: 0.0,
+      shots: Vec::new(),
+      weapon: weapon.create(level),
+      reload_ticks: 0,
+      powers: [ 0; powerup::KINDS.len() ],
+      death_ticks: None
     }
   }
 
-  /* start blowing up the ship */
-  pub fn destroy(&mut self, mut window: &mut Window)
+  /* start blowing up the ship. any power-ups are lost with it, and the ship comes back
+   * at a standstill */
+  pub fn destroy(&mut self)
   {
-    self.time_of_death = Some(Instant::now());
+    self.death_ticks = Some(0);
+    self.velocity = 0.0;
     self.state = State::Dying;
-
-    /* create particles of exploding debris */
-    let mut rnd = rand::thread_rng();
-    for _ in 0..20
-    {
-      let mut particle = Debris
-      {
-        node: window.add_cube(2.0, 2.0, 2.0),
-        x: random_explosion_vector(&mut rnd),
-        y: random_explosion_vector(&mut rnd).abs(), /* only explode upwards */
-        z: random_explosion_vector(&mut rnd),
-      };
-
-      /* color the debris a firey red and move it into position of the player's ship */ 
-      particle.node.set_color(1.0, 0.2, 0.2);
-      particle.node.append_translation(&Translation3::new(self.x, self.y, self.z));
-
-      self.debris.push(particle);
-    }
+    self.powers = [ 0; powerup::KINDS.len() ];
   }
 
-  /* animate the ship exploding or its bullet */
+  /* animate the ship exploding, or its power-ups wearing off, and its bullets */
   pub fn animate(&mut self)
   {
-    /* if the ship is blowing up then keep it hidden, otherwise visible */
     match self.state
     {
-      State::Alive => self.ship.set_visible(true),
+      State::Alive =>
+      {
+        for ticks in self.powers.iter_mut()
+        {
+          *ticks = ticks.saturating_sub(1);
+        }
+        self.reload_ticks = self.reload_ticks.saturating_sub(1);
+      },
       State::Dying =>
       {
-        /* continue blowing up the ship */
-        self.ship.set_visible(false);
-        self.explode();
-
-        /* after 5 seconds, prepare to ressurrect the hero and also
-         * delete all the flying debris */
-        if self.time_of_death.unwrap().elapsed().as_secs() > 4
+        /* after 5 seconds, prepare to ressurrect the hero */
+        let ticks = self.death_ticks.unwrap_or(0) + 1;
+        self.death_ticks = Some(ticks);
+        if ticks > HERO_DEATH_TICKS
         {
           self.state = State::Dead;
-          while self.debris.len() > 0
-          {
-            let mut particle = self.debris.pop();
-            particle.unwrap().node.unlink();
-          }
         }
       },
       State::Dead =>
       {
         /* prepare to ressurrect the ship. if we're out of lives, let
          * the main game loop catch that */
-        self.time_of_death = None;
+        self.death_ticks = None;
         self.state = State::Alive;
       }
     }
 
-    if self.bullet.is_some() == true
+    for shot in self.shots.iter_mut()
     {
-      /* animate the bullet */
-      self.bullet.as_mut().unwrap().animate();
+      shot.animate();
     }
   }
 
-  /* create debris and animate them when the ship explodes */
-  fn explode(&mut self)
+  /* work the ship's trigger for one tick. the weapon decides what happens, and when it's
+   * ready to fire again. power-ups add to that: rapid fire lifts the limit on shots in
+   * play and only waits a moment between volleys, spread shot fans each shot out into
+   * three, and piercing shot sends shots through the aliens they hit
+   * => held = true if fire is held down
+   *    speed = distance a plain bullet climbs each tick
+   * <= number of shots fired */
+  pub fn trigger(&mut self, held: bool, speed: f32) -> u32
   {
-    let rotate = UnitQuaternion::from_axis_angle(&Vector3::y_axis(), 0.10);
-    for particle in self.debris.iter_mut()
+    let spread = self.has_power(Kind::Spread);
+    let copies = match spread
+    {
+      true => SPREAD_SHOTS,
+      false => 1
+    };
+    let limit = match self.has_power(Kind::RapidFire)
     {
-      particle.node.append_translation(&Translation3::new(particle.x, particle.y, particle.z));
-      particle.node.prepend_to_local_rotation(&rotate);
+      true => MAX_SHOTS,
+      false => (self.weapon.max_live() * copies).min(MAX_SHOTS)
+    };
+    let ready = self.reload_ticks == 0 && self.shots.len() + (self.weapon.volley() * copies) <= limit;
+
+    let volley = self.weapon.trigger(held, ready, self.x, BULLET_Y_START, speed);
+    if volley.is_empty() == true
+    {
+      return 0;
     }
-  }
 
-  /* fire a new bullet if one isn't already in play */
-  pub fn fire(&mut self, mut window: &mut Window)
-  {
-    if self.bullet.is_some() == false
+    let mut fired = 0;
+    for mut shot in volley
     {
-      self.bullet = Some(bullet::Bullet::new(&mut window, self.x, BULLET_Y_START,
-                                             BULLET_RADIUS, BULLET_COLOR_R, BULLET_COLOR_G,
-                                             BULLET_COLOR_B, BULLET_ASCENT));
+      if self.has_power(Kind::Piercing) == true
+      {
+        shot.pierce();
+      }
+      if spread == true
+      {
+        self.shots.push(shot.fanned(0.0 - SPREAD_DRIFT));
+        self.shots.push(shot.fanned(SPREAD_DRIFT));
+        fired = fired + 2;
+      }
+      self.shots.push(shot);
+      fired = fired + 1;
     }
+
+    self.reload_ticks = match self.has_power(Kind::RapidFire)
+    {
+      true => RAPID_RELOAD_TICKS,
+      false => self.weapon.reload()
+    };
+    return fired;
   }
 
-  /* remove bullet from game */
-  pub fn destroy_bullet(&mut self)
+  /* returns the weapon the ship carries */
+  pub fn get_weapon(&self) -> &dyn Weapon
   {
-    if self.bullet.as_mut().is_some() == true
+    self.weapon.as_ref()
+  }
+
+  /* swap the ship's weapon for the next one along, at the same level. any charge that
+   * was building up is lost */
+  pub fn switch_weapon(&mut self)
+  {
+    let level = self.weapon.level();
+    self.weapon = self.weapon.kind().next().create(level);
+  }
+
+  /* returns the ship's bullets in play */
+  pub fn bullets(&self) -> Vec<&bullet::Bullet>
+  {
+    self.shots.iter().map(|s| &s.bullet).collect()
+  }
+
+  /* give the ship a power-up, or top it back up if it already has it. an upgrade
+   * takes effect straight away, and lasts */
+  pub fn power_up(&mut self, kind: Kind)
+  {
+    match kind
     {
-      self.bullet.as_mut().unwrap().destroy();
-      self.bullet = None;
+      Kind::Upgrade => { self.weapon.upgrade(); },
+      kind => self.powers[kind.index()] = kind.duration()
     }
   }
 
+  /* returns true if the ship has the given power-up */
+  pub fn has_power(&self, kind: Kind) -> bool
+  {
+    self.powers[kind.index()] > 0
+  }
+
+  /* returns the ship's power-ups, with the number of ticks each has left */
+  pub fn powers(&self) -> Vec<(Kind, u32)>
+  {
+    powerup::KINDS.iter().map(|k| (*k, self.powers[k.index()])).filter(|(_, ticks)| *ticks > 0).collect()
+  }
+
   /* check to see if the ship has collided with a thing at x,y.
    * note: this check does *NOT* affect the ship */
-  pub fn collision(&mut self, x: f32, y: f32) -> collision::CollisionOutcome
+  pub fn collision(&self, x: f32, y: f32) -> collision::CollisionOutcome
   {
     let scenario = collision::Collision
     {
@@ -215,20 +236,164 @@ impl Hero
     (self.x, self.y, self.z)
   }
 
-  pub fn move_left(&mut self)
+  /* returns the number of ticks the ship has been exploding for, or None if not dying */
+  pub fn ticks_since_death(&self) -> Option<u32>
   {
-    self.move_ship(HERO_MOVE_STEP);
+    self.death_ticks
   }
 
-  pub fn move_right(&mut self)
+  /* returns the distance the ship is moving along the x axis each tick */
+  pub fn get_velocity(&self) -> f32
   {
-    self.move_ship(0.0 - HERO_MOVE_STEP);
+    self.velocity
   }
 
-  fn move_ship(&mut self, distance: f32)
+  /* move the ship for one tick. without inertia, the ship goes at the speed it's steered
+   * at and stops dead when let go. with inertia, it speeds up or slows down towards that
+   * speed a little each tick. either way it stops at the walls the squadron turns at
+   * => steering = how hard the player is steering, from -1.0 for full left to 1.0 for full right
+   *    top_speed = the furthest the ship can move in a tick
+   *    accel = the most the ship's speed can change in a tick, or 0 for no inertia */
+  pub fn steer(&mut self, steering: f32, top_speed: f32, accel: f32)
   {
-    self.x = self.x + distance;
-    self.ship.append_translation(&Translation3::new(distance, 0.0, 0.0));
+    /* screen left is the positive x direction */
+    let wanted = (0.0 - steering) * top_speed;
+    self.velocity = match accel > 0.0
+    {
+      true => self.velocity + (wanted - self.velocity).max(0.0 - accel).min(accel),
+      false => wanted
+    };
+
+    self.x = self.x + self.velocity;
+    if self.x > WALL_LEFT || self.x < WALL_RIGHT
+    {
+      self.x = self.x.max(WALL_RIGHT).min(WALL_LEFT);
+      self.velocity = 0.0;
+    }
+  }
+
+  /* write the ship, its shots, power-ups and weapon to a saved game */
+  pub fn save(&self, writer: &mut save::Writer)
+  {
+    writer.label("hero");
+    writer.value(self.x);
+    writer.value(self.y);
+    writer.value(self.z);
+    writer.option(self.death_ticks);
+    writer.value(match self.state
+    {
+      State::Alive => "alive",
+      State::Dying => "dying",
+      State::Dead => "dead"
+    });
+
+    writer.label("shots");
+    writer.value(self.shots.len());
+    for shot in self.shots.iter()
+    {
+      shot.save(writer);
+    }
+
+    writer.label("powers");
+    writer.value(self.reload_ticks);
+    for ticks in self.powers.iter()
+    {
+      writer.value(ticks);
+    }
+
+    writer.label("weapon");
+    writer.value(self.weapon.kind().name());
+    writer.value(self.weapon.level());
+    writer.value(self.weapon.charge_ticks());
+
+    writer.label("velocity");
+    writer.value(self.velocity);
   }
-}
 
+  /* recreate a ship, its shots, power-ups and weapon from a saved game. files from before
+   * there were power-ups have a single bullet line instead of shots, and no powers line.
+   * files from before there were weapons have no weapon line, and used the classic.
+   * files from before ships had inertia have no velocity line. version 7 files were
+   * saved before the weapon upgrade power-up, so their powers line is one short */
+  pub fn restore(reader: &mut save::Reader) -> Result<Hero, save::SaveError>
+  {
+    reader.label("hero")?;
+    let (x, y, z) = (reader.value()?, reader.value()?, reader.value()?);
+    let death_ticks = reader.option()?;
+    let state = match reader.value::<String>()?.as_str()
+    {
+      "alive" => State::Alive,
+      "dying" => State::Dying,
+      "dead" => State::Dead,
+      other => return Err(reader.unexpected("hero state", other))
+    };
+
+    let mut shots = Vec::new();
+    if reader.next_is("shots") == true
+    {
+      reader.label("shots")?;
+      let count: usize = reader.value()?;
+      for _ in 0..count
+      {
+        shots.push(Shot::restore(reader)?);
+      }
+    }
+    else
+    {
+      reader.label("bullet")?;
+      if reader.none() == false
+      {
+        shots.push(Shot::plain(bullet::Bullet::restore(reader)?));
+      }
+    }
+
+    let mut reload_ticks = 0;
+    let mut powers = [ 0; powerup::KINDS.len() ];
+    if reader.next_is("powers") == true
+    {
+      reader.label("powers")?;
+      reload_ticks = reader.value()?;
+      let count = match reader.version()
+      {
+        7 => powerup::KINDS.len() - 1,
+        _ => powerup::KINDS.len()
+      };
+      for ticks in powers.iter_mut().take(count)
+      {
+        *ticks = reader.value()?;
+      }
+    }
+
+    let mut weapon = weapon::Kind::Classic.create(1);
+    if reader.next_is("weapon") == true
+    {
+      reader.label("weapon")?;
+      let name = reader.value::<String>()?;
+      weapon = match weapon::Kind::from_name(name.as_str())
+      {
+        Some(kind) => kind.create(reader.value()?),
+        None => return Err(reader.unexpected("weapon", name.as_str()))
+      };
+      weapon.set_charge_ticks(reader.value()?);
+    }
+
+    let mut velocity = 0.0;
+    if reader.next_is("velocity") == true
+    {
+      reader.label("velocity")?;
+      velocity = reader.value()?;
+    }
+
+    Ok(Hero
+    {
+      x: x, y: y, z: z,
+      velocity: velocity,
+      death_ticks: death_ticks,
+      state: state,
+      shots: shots,
+      weapon: weapon,
+      reload_ticks: reload_ticks,
+      powers: powers
+    })
+  }
+}
@@ -0,0 +1,209 @@
+/* Space invaders in Rust
+ *
+ * Heads-up display: scores, level and lives drawn over the playfield
+ *
+ * Game concept by Tomohiro Nishikado / Taito
+ * Rust code By Chris Williams <diodesign@tuta.io>
+ *
+ * Written for fun. See LICENSE.
+ *
+ */
+
+/* everything is laid out as a fraction of the window's size, so the HUD keeps its shape
+ * however big the window is. text goes along the top: score on the left, hi-score in the
+ * middle, and level on the right. in a boss fight, the mothership's health is shown as a
+ * bar below the hi-score. along the bottom is a ground line with the lives left
+ * shown as little ships beneath it, like the arcade original. power-ups are listed above
+ * the ground with the seconds they have left, and weapons below it: player one's on the
+ * right, and player two's on the left in co-op mode, after the lives */
+
+extern crate kiss3d;
+extern crate nalgebra as na;
+
+use std::rc::Rc;
+
+use na::{ Point2, Point3 };
+use kiss3d::window::Window;
+use kiss3d::text::Font;
+
+use super::difficulty::{ Difficulty, Profile };
+use super::game::{ Game, GameMode, Playfield, TICKS_PER_SECOND };
+use super::stats;
+use super::weapon;
+
+const TEXT_SIZE: f32 = 0.06;      /* height of the HUD's text, as a fraction of the window's height */
+const TEXT_MIN_SIZE: f32 = 24.0;  /* smallest text size, so it stays readable in small windows */
+const CHAR_WIDTH: f32 = 0.5;      /* rough width of a character, as a fraction of the text size */
+const MARGIN: f32 = 0.02;         /* gap between the HUD and the edges of the window, as a fraction of its width */
+
+const GROUND_Y: f32 = 0.93;       /* height of the ground line, as a fraction of the way down the window */
+const ICON_SIZE: f32 = 0.03;      /* height of a life icon, as a fraction of the window's height */
+const ICON_GAP: f32 = 0.5;        /* gap between life icons, as a fraction of an icon's width */
+const MAX_ICONS: i32 = 9;         /* most lives to draw as icons. any more are shown as a number */
+const CHARGE_WIDTH: usize = 8;    /* characters in a weapon's charge bar */
+
+const BAR_WIDTH: f32 = 0.3;       /* width of the mothership's health bar, as a fraction of the window's width */
+const BAR_HEIGHT: f32 = 0.02;     /* height of the health bar, as a fraction of the window's height */
+
+const TEXT_COLOR: (f32, f32, f32) = (1.0, 1.0, 1.0);
+const HIGH_SCORE_COLOR: (f32, f32, f32) = (1.0, 0.8, 0.2);
+const ALIEN_COLOR: (f32, f32, f32) = (0.2, 1.0, 0.2);
+const GROUND_COLOR: (f32, f32, f32) = (0.2, 1.0, 0.2);
+const ICON_COLOR: (f32, f32, f32) = (0.8, 0.8, 0.8);
+const WEAPON_COLOR: (f32, f32, f32) = (0.8, 0.8, 0.8);
+const BAR_COLOR: (f32, f32, f32) = (0.9, 0.2, 0.2);
+
+pub struct Hud
+{
+  font: Rc<Font>,
+  high_score: i32   /* best score from earlier single-player games at the same difficulty */
+}
+
+impl Hud
+{
+  /* => font = font to draw the HUD's text with
+   *    difficulty = difficulty of the game being played, to show the hi-score of */
+  pub fn new(font: Rc<Font>, difficulty: &Difficulty) -> Hud
+  {
+    Hud { font: font, high_score: stats::high_score(difficulty) }
+  }
+
+  /* draw the HUD over the current frame
+   * => window = graphics context
+   *    state = game to show the scores, level and lives of
+   *    playfield = level being played, to show the mothership's health and the ships' power-ups */
+  pub fn draw(&self, window: &mut Window, state: &Game, playfield: &Playfield)
+  {
+    let (width, height) = (window.width() as f32, window.height() as f32);
+    let size = (height * TEXT_SIZE).max(TEXT_MIN_SIZE);
+    let margin = width * MARGIN;
+
+    /* scores and level along the top */
+    let score = format!("SCORE {:07}", state.score);
+    let high_score = format!("HI-SCORE {:07}", self.high_score.max(state.score));
+    let level = match state.difficulty.profile
+    {
+      Profile::Normal => format!("LEVEL {}", state.level),
+      _ => format!("{} LEVEL {}", state.difficulty.profile.title().to_uppercase(), state.level)
+    };
+
+    self.text(window, score.as_str(), margin, margin, size, TEXT_COLOR);
+    self.text(window, high_score.as_str(), (width - text_width(high_score.as_str(), size)) / 2.0, margin, size, HIGH_SCORE_COLOR);
+    self.text(window, level.as_str(), width - margin - text_width(level.as_str(), size), margin, size, TEXT_COLOR);
+
+    if state.mode == GameMode::Versus
+    {
+      let aliens = format!("ALIENS {:07}", state.alien_score);
+      self.text(window, aliens.as_str(), margin, margin + size, size, ALIEN_COLOR);
+    }
+
+    if let Some(boss) = playfield.boss.as_ref()
+    {
+      let (bar_width, bar_height) = (width * BAR_WIDTH, height * BAR_HEIGHT);
+      let (left, top) = ((width - bar_width) / 2.0, margin + (size * 1.2));
+      health_bar(window, width, height, (left, top), (bar_width, bar_height), boss.health());
+    }
+
+    /* ground line, with the lives left below it and power-ups above */
+    let ground = height * GROUND_Y;
+    line(window, width, height, (margin, ground), (width - margin, ground), GROUND_COLOR);
+
+    for (index, ship) in playfield.heroes.iter().enumerate()
+    {
+      let mut y = ground - size;
+      for (kind, ticks) in ship.powers()
+      {
+        let power = format!("{} {}", kind.title().to_uppercase(), (ticks + TICKS_PER_SECOND - 1) / TICKS_PER_SECOND);
+        let x = match index
+        {
+          0 => width - margin - text_width(power.as_str(), size),
+          _ => margin
+        };
+        self.text(window, power.as_str(), x, y, size, kind.color());
+        y = y - size;
+      }
+    }
+
+    let icon = height * ICON_SIZE;
+    let top = ground + ((height - ground - icon) / 2.0);
+    let lives = state.lives.max(0);
+    let mut x = margin;
+    for _ in 0..lives.min(MAX_ICONS)
+    {
+      ship(window, width, height, x, top, icon);
+      x = x + (icon * (1.0 + ICON_GAP));
+    }
+
+    if lives > MAX_ICONS
+    {
+      self.text(window, format!("x{}", lives).as_str(), x, top - ((size - icon) / 2.0), size, ICON_COLOR);
+      x = x + text_width(format!("x{}", lives).as_str(), size);
+    }
+
+    for (index, hero) in playfield.heroes.iter().enumerate()
+    {
+      let arms = weapon::describe(hero.get_weapon(), CHARGE_WIDTH).to_uppercase();
+      let left = match index
+      {
+        0 => width - margin - text_width(arms.as_str(), size),
+        _ => x + (icon * ICON_GAP)
+      };
+      self.text(window, arms.as_str(), left, top - ((size - icon) / 2.0), size, WEAPON_COLOR);
+    }
+  }
+
+  /* draw a line of text with its top-left corner at x, y in window coordinates */
+  fn text(&self, window: &mut Window, text: &str, x: f32, y: f32, size: f32, color: (f32, f32, f32))
+  {
+    window.draw_text(text, &Point2::new(x, y), size, &self.font, &Point3::new(color.0, color.1, color.2));
+  }
+}
+
+/* returns the rough width of a line of text */
+fn text_width(text: &str, size: f32) -> f32
+{
+  text.len() as f32 * size * CHAR_WIDTH
+}
+
+/* draw a line between two points given in window coordinates: from the top-left corner,
+ * with y going down. planar lines are drawn from the middle of the window, with y going up */
+fn line(window: &mut Window, width: f32, height: f32, from: (f32, f32), to: (f32, f32), color: (f32, f32, f32))
+{
+  let planar = |(x, y): (f32, f32)| Point2::new(x - (width / 2.0), (height / 2.0) - y);
+  window.draw_planar_line(&planar(from), &planar(to), &Point3::new(color.0, color.1, color.2));
+}
+
+/* draw a health bar: an outline filled in from the left with what's left
+ * => corner = top-left corner of the bar, in window coordinates
+ *    size = width and height of the bar
+ *    health = fraction of the bar to fill in */
+fn health_bar(window: &mut Window, width: f32, height: f32, corner: (f32, f32), size: (f32, f32), health: f32)
+{
+  let (left, top) = corner;
+  let (right, bottom) = (left + size.0, top + size.1);
+  line(window, width, height, (left, top), (right, top), BAR_COLOR);
+  line(window, width, height, (right, top), (right, bottom), BAR_COLOR);
+  line(window, width, height, (right, bottom), (left, bottom), BAR_COLOR);
+  line(window, width, height, (left, bottom), (left, top), BAR_COLOR);
+
+  let filled = left + (size.0 * health.max(0.0).min(1.0));
+  let mut across = top + 1.0;
+  while across < bottom
+  {
+    line(window, width, height, (left, across), (filled, across), BAR_COLOR);
+    across = across + 1.0;
+  }
+}
+
+/* draw a little ship icon, a filled triangle, with its top-left corner at x, y in window coordinates */
+fn ship(window: &mut Window, width: f32, height: f32, x: f32, y: f32, size: f32)
+{
+  let rows = size.max(1.0) as i32;
+  for row in 0..rows
+  {
+    let half = (size / 2.0) * (row as f32 + 1.0) / rows as f32;
+    let middle = x + (size / 2.0);
+    let across = y + row as f32;
+    line(window, width, height, (middle - half, across), (middle + half, across), ICON_COLOR);
+  }
+}
@@ -0,0 +1,273 @@
+/* Space invaders in Rust
+ *
+ * Player controls
+ *
+ * Game concept by Tomohiro Nishikado / Taito
+ * Rust code By Chris Williams <diodesign@tuta.io>
+ *
+ * Written for fun. See LICENSE.
+ *
+ */
+
+extern crate glfw;
+extern crate kiss3d;
+
+use kiss3d::event::{ WindowEvent, Key, Action };
+
+const STICKS: [glfw::JoystickId; 2] = [ glfw::JoystickId::Joystick1, glfw::JoystickId::Joystick2 ];
+const STICK_DEAD_ZONE: f32 = 0.2; /* stick pushes smaller than this count as centered */
+const STICK_PRESS: f32 = 0.5;     /* stick pushes bigger than this also count as holding left or right */
+const STICK_FIRE: usize = 0;      /* stick button that fires */
+const STICK_SPECIAL: usize = 1;   /* stick button for special */
+
+/* buttons a player can hold down, sampled once per simulation tick. the hero uses
+ * left, right and fire, and special to switch weapons. an alien player uses left and
+ * right to pick a column, fire to drop bombs, and special to trigger a speed burst. a
+ * player with a joystick also has an analog stick, which steers the hero's ship at
+ * anything up to its top speed */
+#[derive(Clone, Copy, Default, PartialEq)]
+pub struct Controls
+{
+  pub left: bool,
+  pub right: bool,
+  pub fire: bool,
+  pub special: bool,
+  pub stick: i8 /* how far the stick is pushed, from -127 for full left to 127 for full right. 0 if centered, or no stick */
+}
+
+impl Controls
+{
+  /* pack the controls into a number, eg: for sending over the network. the buttons
+   * are in the low four bits, and the stick in the high byte */
+  pub fn to_bits(self) -> u16
+  {
+    (self.left as u16) | (self.right as u16) << 1 | (self.fire as u16) << 2 | (self.special as u16) << 3 |
+    (self.stick as u8 as u16) << 8
+  }
+
+  /* unpack controls from a number created by to_bits() */
+  pub fn from_bits(bits: u16) -> Controls
+  {
+    Controls
+    {
+      left:    bits & 1 != 0,
+      right:   bits & 2 != 0,
+      fire:    bits & 4 != 0,
+      special: bits & 8 != 0,
+      stick:   (bits >> 8) as u8 as i8
+    }
+  }
+
+  /* combine two sets of controls: a button is held if it's held in either. the first's
+   * stick wins if both are pushed */
+  pub fn merge(&self, other: &Controls) -> Controls
+  {
+    Controls
+    {
+      left:    self.left    || other.left,
+      right:   self.right   || other.right,
+      fire:    self.fire    || other.fire,
+      special: self.special || other.special,
+      stick:   match self.stick
+      {
+        0 => other.stick,
+        stick => stick
+      }
+    }
+  }
+
+  /* returns how hard the player is steering, from -1.0 for full left to 1.0 for full
+   * right. a pushed stick gives anything in between, the buttons give all or nothing */
+  pub fn steering(&self) -> f32
+  {
+    if self.stick != 0
+    {
+      return (self.stick as f32 / 127.0).max(-1.0);
+    }
+
+    match (self.left, self.right)
+    {
+      (true, false) => -1.0,
+      (false, true) => 1.0,
+      _ => 0.0
+    }
+  }
+}
+
+/* two players can share the keyboard, each with their own set of keys */
+#[derive(Clone, Copy)]
+pub enum Layout
+{
+  Primary,   /* z = left, x = right, return = fire, c = special, or the first joystick */
+  Secondary  /* cursor keys: left, right, down = fire, up = special, or the second joystick */
+}
+
+/* track which keys are held down on the keyboard, and what's pushed on any joysticks */
+pub struct Keyboard
+{
+  primary: Controls,
+  secondary: Controls,
+  sticks: [Controls; 2],   /* the first two joysticks, as controls */
+  glfw: Option<glfw::Glfw> /* reads the joysticks, or None if it can't be started */
+}
+
+impl Keyboard
+{
+  pub fn new() -> Keyboard
+  {
+    Keyboard
+    {
+      primary: Controls::default(),
+      secondary: Controls::default(),
+      sticks: [ Controls::default(); 2 ],
+      glfw: glfw::init(glfw::LOG_ERRORS).ok()
+    }
+  }
+
+  /* forget which keys are held down, eg: when keys may have been let go while the game
+   * wasn't watching */
+  pub fn clear(&mut self)
+  {
+    self.primary = Controls::default();
+    self.secondary = Controls::default();
+  }
+
+  /* read the joysticks. call this once a frame, before get() or either() */
+  pub fn poll_sticks(&mut self)
+  {
+    let glfw = match self.glfw.as_ref()
+    {
+      Some(glfw) => glfw,
+      None => return
+    };
+
+    for (controls, id) in self.sticks.iter_mut().zip(STICKS.iter())
+    {
+      let stick = glfw.get_joystick(*id);
+      if stick.is_present() == false
+      {
+        *controls = Controls::default();
+        continue;
+      }
+
+      let push = stick.get_axes().first().cloned().unwrap_or(0.0).max(-1.0).min(1.0);
+      let buttons = stick.get_buttons();
+      let pressed = |button: usize| buttons.get(button).map_or(false, |b| *b != 0);
+      *controls = Controls
+      {
+        left: push < 0.0 - STICK_PRESS,
+        right: push > STICK_PRESS,
+        fire: pressed(STICK_FIRE),
+        special: pressed(STICK_SPECIAL),
+        stick: match push.abs() < STICK_DEAD_ZONE
+        {
+          true => 0,
+          false => (push * 127.0) as i8
+        }
+      };
+    }
+  }
+
+  /* update the held keys from a window event
+   * <= true if the event was a keypress or release, false if not */
+  pub fn handle(&mut self, event: &WindowEvent) -> bool
+  {
+    match *event
+    {
+      WindowEvent::Key(code, action, _) =>
+      {
+        let held = match action
+        {
+          Action::Press => true,
+          Action::Release => false
+        };
+
+        match code
+        {
+          Key::Z      => self.primary.left      = held,
+          Key::X      => self.primary.right     = held,
+          Key::Return => self.primary.fire      = held,
+          Key::C      => self.primary.special   = held,
+          Key::Left   => self.secondary.left    = held,
+          Key::Right  => self.secondary.right   = held,
+          Key::Down   => self.secondary.fire    = held,
+          Key::Up     => self.secondary.special = held,
+          _ => {}
+        }
+
+        return true;
+      },
+
+      _ => return false
+    }
+  }
+
+  /* return the controls held down in the given layout */
+  pub fn get(&self, layout: Layout) -> Controls
+  {
+    match layout
+    {
+      Layout::Primary => self.sticks[0].merge(&self.primary),
+      Layout::Secondary => self.sticks[1].merge(&self.secondary)
+    }
+  }
+
+  /* return the controls held down in either layout, for when there's only
+   * one player at this keyboard */
+  pub fn either(&self) -> Controls
+  {
+    self.get(Layout::Primary).merge(&self.get(Layout::Secondary))
+  }
+}
+
+/* ------------------------------------------------------------------------------ */
+
+#[cfg(test)]
+mod tests
+{
+  use super::*;
+
+  #[test]
+  fn controls_survive_packing()
+  {
+    for buttons in 0..16u16
+    {
+      for stick in [ -127i8, -128, -1, 0, 1, 64, 127 ].iter()
+      {
+        let controls = Controls
+        {
+          left: buttons & 1 != 0,
+          right: buttons & 2 != 0,
+          fire: buttons & 4 != 0,
+          special: buttons & 8 != 0,
+          stick: *stick
+        };
+        assert!(Controls::from_bits(controls.to_bits()) == controls, "buttons {} stick {} changed", buttons, stick);
+      }
+    }
+  }
+
+  #[test]
+  fn buttons_keep_their_bits()
+  {
+    /* the bits are written to recordings and sent over the network, so they mustn't move */
+    assert_eq!(Controls { left: true, ..Controls::default() }.to_bits(), 1);
+    assert_eq!(Controls { right: true, ..Controls::default() }.to_bits(), 2);
+    assert_eq!(Controls { fire: true, ..Controls::default() }.to_bits(), 4);
+    assert_eq!(Controls { special: true, ..Controls::default() }.to_bits(), 8);
+    assert_eq!(Controls { stick: -1, ..Controls::default() }.to_bits(), 0xff00);
+  }
+
+  #[test]
+  fn stick_steers_before_buttons()
+  {
+    assert_eq!(Controls { left: true, ..Controls::default() }.steering(), -1.0);
+    assert_eq!(Controls { left: true, right: true, ..Controls::default() }.steering(), 0.0);
+    assert_eq!(Controls { right: true, stick: -127, ..Controls::default() }.steering(), -1.0);
+    assert_eq!(Controls { stick: -128, ..Controls::default() }.steering(), -1.0);
+
+    let keys = Controls { fire: true, stick: 0, ..Controls::default() };
+    let pad = Controls { stick: 64, ..Controls::default() };
+    assert!(keys.merge(&pad) == Controls { fire: true, stick: 64, ..Controls::default() });
+  }
+}
@@ -0,0 +1,573 @@
+/* Space invaders in Rust
+ *
+ * Networked two-player games: lockstep simulation over TCP
+ *
+ * Game concept by Tomohiro Nishikado / Taito
+ * Rust code By Chris Williams <diodesign@tuta.io>
+ *
+ * Written for fun. See LICENSE.
+ *
+ */
+
+/* both peers run exactly the same deterministic simulation. the only thing sent
+ * over the wire is each player's controls for each tick. controls sampled locally
+ * are scheduled INPUT_DELAY ticks into the future, which hides the network latency:
+ * by the time a peer needs the other player's controls for a tick, they've usually
+ * already arrived. if not, the peer stalls until they do.
+ *
+ * the protocol is line-based text:
+ *   HELLO <version> <seed> <mode> <difficulty>   host to guest when the guest connects
+ *   READY <version>                 guest to host in reply
+ * where <difficulty> is laid out as in a saved game, on one line, so the guest plays by the
+ * host's settings
+ *   I <tick> <controls>             a player's controls for a tick
+ *   H <tick> <hash>                 hash of the playfield after a tick, to detect desyncs
+ *   BYE                             peer is leaving
+ */
+
+use std::fmt;
+use std::io::{ BufRead, BufReader, Write };
+use std::net::{ TcpListener, TcpStream, ToSocketAddrs };
+use std::sync::mpsc::{ channel, Receiver, TryRecvError };
+use std::collections::HashMap;
+use std::thread;
+use std::time::Duration;
+
+use super::difficulty::Difficulty;
+use super::game::GameMode;
+use super::input::Controls;
+use super::save;
+
+pub const DEFAULT_PORT: u16 = 7777;
+const PROTOCOL_VERSION: u32 = 4; /* version 2 added the difficulty, version 3 the march, and version 4 sticks and inertia */
+const INPUT_DELAY: u32 = 4;      /* ticks between sampling controls and them taking effect */
+const HASH_INTERVAL: u32 = 60;   /* ticks between comparing playfield hashes */
+const CONNECT_TIMEOUT_SECS: u64 = 10;
+const HANDSHAKE_TIMEOUT_SECS: u64 = 5; /* longest to wait for the other peer's HELLO or READY */
+
+/* ways a network game can fail */
+pub enum NetError
+{
+  Io(String),       /* couldn't talk to the other peer */
+  Protocol(String), /* other peer said something unexpected */
+  Desync(u32),      /* simulations no longer agree as of the given tick */
+  Timeout,          /* other peer connected but didn't finish the handshake in time */
+  Disconnected      /* other peer left the game */
+}
+
+impl fmt::Display for NetError
+{
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
+  {
+    match self
+    {
+      NetError::Io(reason) => write!(f, "Network error: {}", reason),
+      NetError::Protocol(reason) => write!(f, "Protocol error: {}", reason),
+      NetError::Desync(tick) => write!(f, "Games out of sync at tick {}", tick),
+      NetError::Timeout => write!(f, "Other player didn't answer in time"),
+      NetError::Disconnected => write!(f, "Other player left the game")
+    }
+  }
+}
+
+impl From<std::io::Error> for NetError
+{
+  fn from(error: std::io::Error) -> NetError
+  {
+    NetError::Io(error.to_string())
+  }
+}
+
+/* messages received from the other peer */
+enum Message
+{
+  Input(u32, Controls),
+  Hash(u32, u64),
+  Bye,
+  Closed(String) /* connection dropped, with the reason why */
+}
+
+/* a host waiting for a guest to connect */
+pub struct Listener
+{
+  listener: TcpListener,
+  mode: GameMode,
+  seed: u64,
+  difficulty: Difficulty
+}
+
+/* host a game
+ * => port = TCP port to listen on
+ *    mode = game mode to play, either co-op or versus
+ *    seed = seed for the game's random decisions
+ *    difficulty = how hard the game is, for both players
+ * <= listener to poll for the guest */
+pub fn host(port: u16, mode: GameMode, seed: u64, difficulty: Difficulty) -> Result<Listener, NetError>
+{
+  let listener = TcpListener::bind(("0.0.0.0", port))?;
+  listener.set_nonblocking(true)?;
+  Ok(Listener { listener: listener, mode: mode, seed: seed, difficulty: difficulty })
+}
+
+impl Listener
+{
+  /* check to see if a guest has connected, without blocking
+   * <= Some(session) if so, or None to keep waiting */
+  pub fn poll(&self) -> Result<Option<Session>, NetError>
+  {
+    let stream = match self.listener.accept()
+    {
+      Ok((stream, _)) => stream,
+      Err(ref e) if e.kind() == std::io::ErrorKind::WouldBlock => return Ok(None),
+      Err(e) => return Err(NetError::from(e))
+    };
+
+    stream.set_nonblocking(false)?;
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut writer = stream;
+
+    let mut difficulty = save::Writer::new();
+    self.difficulty.save(&mut difficulty);
+    let difficulty: Vec<&str> = difficulty.text().split_whitespace().collect();
+    writeln!(writer, "HELLO {} {} {} {}", PROTOCOL_VERSION, self.seed, mode_name(self.mode), difficulty.join(" "))?;
+
+    let line = read_handshake(&mut reader)?;
+    let words: Vec<&str> = line.split_whitespace().collect();
+    match words.as_slice()
+    {
+      [ "READY", version ] if *version == PROTOCOL_VERSION.to_string() => {},
+      _ => return Err(NetError::Protocol(format!("unexpected reply from guest: {}", line.trim())))
+    }
+
+    return Ok(Some(Session::new(reader, writer, 0, self.mode, self.seed, self.difficulty)));
+  }
+}
+
+/* join a game hosted elsewhere
+ * => address = host's address, eg: 192.168.1.10:7777
+ * <= connected session */
+pub fn join(address: &str) -> Result<Session, NetError>
+{
+  let target = match address.to_socket_addrs()?.next()
+  {
+    Some(target) => target,
+    None => return Err(NetError::Io(format!("can't find host {}", address)))
+  };
+
+  let stream = TcpStream::connect_timeout(&target, Duration::from_secs(CONNECT_TIMEOUT_SECS))?;
+  let mut reader = BufReader::new(stream.try_clone()?);
+  let mut writer = stream;
+
+  let line = read_handshake(&mut reader)?;
+  let words: Vec<&str> = line.split_whitespace().collect();
+  let (seed, mode) = match &words[..words.len().min(4)]
+  {
+    [ "HELLO", version, seed, mode ] if *version == PROTOCOL_VERSION.to_string() =>
+    {
+      match (seed.parse::<u64>(), mode_from_name(mode))
+      {
+        (Ok(seed), Some(mode)) => (seed, mode),
+        _ => return Err(NetError::Protocol(format!("bad greeting from host: {}", line.trim())))
+      }
+    },
+    [ "HELLO", version, _, _ ] =>
+      return Err(NetError::Protocol(format!("host uses protocol version {}, we use {}", version, PROTOCOL_VERSION))),
+    _ => return Err(NetError::Protocol(format!("unexpected greeting from host: {}", line.trim())))
+  };
+
+  /* the rest of the greeting is the difficulty */
+  let rest = words[4..].join(" ");
+  let difficulty = match Difficulty::restore(&mut save::Reader::new(rest.as_str()))
+  {
+    Ok(difficulty) => difficulty,
+    Err(_) => return Err(NetError::Protocol(format!("bad difficulty from host: {}", line.trim())))
+  };
+
+  writeln!(writer, "READY {}", PROTOCOL_VERSION)?;
+
+  return Ok(Session::new(reader, writer, 1, mode, seed, difficulty));
+}
+
+/* read the other peer's side of the handshake, giving up if it doesn't arrive in time
+ * so a peer that connects and says nothing can't hang the game
+ * => reader = connection to the other peer
+ * <= the line the other peer sent */
+fn read_handshake(reader: &mut BufReader<TcpStream>) -> Result<String, NetError>
+{
+  reader.get_ref().set_read_timeout(Some(Duration::from_secs(HANDSHAKE_TIMEOUT_SECS)))?;
+
+  let mut line = String::new();
+  match reader.read_line(&mut line)
+  {
+    Ok(_) => {},
+    Err(ref e) if e.kind() == std::io::ErrorKind::WouldBlock || e.kind() == std::io::ErrorKind::TimedOut =>
+      return Err(NetError::Timeout),
+    Err(e) => return Err(NetError::from(e))
+  }
+
+  /* once the game is under way, the reader thread waits as long as it takes */
+  reader.get_ref().set_read_timeout(None)?;
+  return Ok(line);
+}
+
+fn mode_name(mode: GameMode) -> &'static str
+{
+  match mode
+  {
+    GameMode::Single => "single",
+    GameMode::Versus => "versus",
+    GameMode::Coop => "coop"
+  }
+}
+
+fn mode_from_name(name: &str) -> Option<GameMode>
+{
+  match name
+  {
+    "versus" => Some(GameMode::Versus),
+    "coop" => Some(GameMode::Coop),
+    _ => None
+  }
+}
+
+/* ------------------------------------------------------------------------------ */
+
+/* a connection to the other peer, keeping the two simulations in lockstep */
+pub struct Session
+{
+  writer: TcpStream,
+  incoming: Receiver<Message>,     /* messages from the reader thread */
+  pub player: usize,               /* which player this peer controls: host is 0, guest is 1 */
+  pub mode: GameMode,              /* game mode agreed with the host */
+  pub seed: u64,                   /* game seed agreed with the host */
+  pub difficulty: Difficulty,      /* difficulty agreed with the host */
+  tick: u32,                       /* next tick to simulate */
+  local: HashMap<u32, Controls>,   /* our controls, by tick */
+  remote: HashMap<u32, Controls>,  /* their controls, by tick */
+  local_hashes: HashMap<u32, u64>, /* our playfield hashes, by tick */
+  remote_hashes: HashMap<u32, u64>, /* their playfield hashes, by tick */
+  closed: Option<NetError>         /* why the other peer stopped talking, if it has */
+}
+
+impl Session
+{
+  fn new(reader: BufReader<TcpStream>, writer: TcpStream, player: usize, mode: GameMode, seed: u64,
+         difficulty: Difficulty) -> Session
+  {
+    let _ = writer.set_nodelay(true);
+
+    /* read messages in the background so the game never blocks on the network */
+    let (sender, incoming) = channel();
+    thread::spawn(move ||
+    {
+      for line in reader.lines()
+      {
+        let line = match line
+        {
+          Ok(line) => line,
+          Err(e) =>
+          {
+            let _ = sender.send(Message::Closed(e.to_string()));
+            return;
+          }
+        };
+
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let message = match words.as_slice()
+        {
+          [ "I", tick, bits ] => match (tick.parse::<u32>(), bits.parse::<u16>())
+          {
+            (Ok(tick), Ok(bits)) => Message::Input(tick, Controls::from_bits(bits)),
+            _ => Message::Closed(format!("bad input message: {}", line))
+          },
+          [ "H", tick, hash ] => match (tick.parse::<u32>(), u64::from_str_radix(hash, 16))
+          {
+            (Ok(tick), Ok(hash)) => Message::Hash(tick, hash),
+            _ => Message::Closed(format!("bad hash message: {}", line))
+          },
+          [ "BYE" ] => Message::Bye,
+          _ => Message::Closed(format!("unknown message: {}", line))
+        };
+
+        if sender.send(message).is_err() == true
+        {
+          return; /* session has gone away */
+        }
+      }
+
+      let _ = sender.send(Message::Bye); /* end of stream */
+    });
+
+    let mut session = Session
+    {
+      writer: writer,
+      incoming: incoming,
+      player: player,
+      mode: mode,
+      seed: seed,
+      difficulty: difficulty,
+      tick: 0,
+      local: HashMap::new(),
+      remote: HashMap::new(),
+      local_hashes: HashMap::new(),
+      remote_hashes: HashMap::new(),
+      closed: None
+    };
+
+    /* nobody has pressed anything during the first few ticks */
+    for tick in 0..INPUT_DELAY
+    {
+      session.local.insert(tick, Controls::default());
+      session.remote.insert(tick, Controls::default());
+    }
+
+    return session;
+  }
+
+  /* swap controls with the other peer for the next tick
+   * => controls = what the local player is holding down right now
+   * <= Some(controls for each player, in player order) if the next tick can be simulated,
+   *    or None if we're still waiting for the other player */
+  pub fn exchange(&mut self, controls: Controls) -> Result<Option<Vec<Controls>>, NetError>
+  {
+    /* schedule the local controls for the future, unless we're stalled and already have */
+    let future = self.tick + INPUT_DELAY;
+    if self.local.contains_key(&future) == false
+    {
+      self.local.insert(future, controls);
+      self.send(format!("I {} {}", future, controls.to_bits()));
+    }
+
+    self.receive();
+
+    /* the other peer may have finished the game a few ticks ahead of us and left, so
+     * only give up once we've run out of controls they sent */
+    if self.remote.contains_key(&self.tick) == false
+    {
+      return match self.closed.take()
+      {
+        Some(reason) => Err(reason),
+        None => Ok(None) /* stall until the other player's controls arrive */
+      };
+    }
+
+    let mine = self.local.remove(&self.tick).unwrap_or_default();
+    let theirs = self.remote.remove(&self.tick).unwrap_or_default();
+    self.tick = self.tick + 1;
+
+    return Ok(Some(match self.player
+    {
+      0 => vec![ mine, theirs ],
+      _ => vec![ theirs, mine ]
+    }));
+  }
+
+  /* tell the other peer what our playfield looks like after the tick just simulated,
+   * every so often, and check theirs agrees
+   * => hash = hash of the playfield */
+  pub fn verify(&mut self, hash: u64) -> Result<(), NetError>
+  {
+    let tick = self.tick - 1;
+    if tick % HASH_INTERVAL == 0
+    {
+      self.local_hashes.insert(tick, hash);
+      self.send(format!("H {} {:x}", tick, hash));
+    }
+
+    return self.compare_hashes();
+  }
+
+  /* politely tell the other peer we're leaving */
+  pub fn close(&mut self)
+  {
+    let _ = writeln!(self.writer, "BYE");
+    let _ = self.writer.shutdown(std::net::Shutdown::Both);
+  }
+
+  /* send a message to the other peer. if that fails, they've gone away, but we
+   * may still have controls from them to use up, so just make a note of it */
+  fn send(&mut self, message: String)
+  {
+    if let Err(e) = writeln!(self.writer, "{}", message)
+    {
+      if self.closed.is_none() == true
+      {
+        self.closed = Some(NetError::from(e));
+      }
+    }
+  }
+
+  /* collect up all the messages that have arrived from the other peer */
+  fn receive(&mut self)
+  {
+    while self.closed.is_none() == true
+    {
+      match self.incoming.try_recv()
+      {
+        Ok(Message::Input(tick, controls)) => { self.remote.insert(tick, controls); },
+        Ok(Message::Hash(tick, hash)) => { self.remote_hashes.insert(tick, hash); },
+        Ok(Message::Bye) => self.closed = Some(NetError::Disconnected),
+        Ok(Message::Closed(reason)) => self.closed = Some(NetError::Io(reason)),
+        Err(TryRecvError::Empty) => return,
+        Err(TryRecvError::Disconnected) => self.closed = Some(NetError::Disconnected)
+      }
+    }
+  }
+
+  /* check the hashes we have from both sides for the same ticks */
+  fn compare_hashes(&mut self) -> Result<(), NetError>
+  {
+    let ticks: Vec<u32> = self.local_hashes.keys().filter(|t| self.remote_hashes.contains_key(t)).cloned().collect();
+    for tick in ticks
+    {
+      let ours = self.local_hashes.remove(&tick);
+      let theirs = self.remote_hashes.remove(&tick);
+      if ours != theirs
+      {
+        return Err(NetError::Desync(tick));
+      }
+    }
+
+    return Ok(());
+  }
+}
+
+/* ------------------------------------------------------------------------------ */
+
+#[cfg(test)]
+mod tests
+{
+  use super::*;
+  use std::time::Instant;
+  use super::super::difficulty::Profile;
+  use super::super::game::{ Game, Playfield };
+
+  const TICKS: usize = 600; /* ten seconds of play */
+
+  /* what each player holds down on each tick, different for the two players */
+  fn scripted(player: usize, tick: u32) -> Controls
+  {
+    let phase = (tick / 40) + player as u32;
+    Controls
+    {
+      left: phase % 3 == 0,
+      right: phase % 3 == 1,
+      fire: tick % 7 == player as u32,
+      special: false,
+      stick: 0
+    }
+  }
+
+  /* host a game on a free port, and return the listener and the port */
+  fn listen() -> (Listener, u16)
+  {
+    let listener = match host(0, GameMode::Coop, 1234, Difficulty::new(Profile::Arcade))
+    {
+      Ok(listener) => listener,
+      Err(e) => panic!("can't host: {}", e)
+    };
+    let port = listener.listener.local_addr().unwrap().port();
+    (listener, port)
+  }
+
+  /* have a guest join a hosted game over loopback
+   * <= the host's session and the guest's session */
+  fn connect() -> (Session, Session)
+  {
+    let (listener, port) = listen();
+    let guest = thread::spawn(move || join(format!("127.0.0.1:{}", port).as_str()));
+
+    let host = loop
+    {
+      match listener.poll()
+      {
+        Ok(Some(session)) => break session,
+        Ok(None) => thread::sleep(Duration::from_millis(1)),
+        Err(e) => panic!("host failed: {}", e)
+      }
+    };
+
+    match guest.join().unwrap()
+    {
+      Ok(guest) => (host, guest),
+      Err(e) => panic!("guest failed: {}", e)
+    }
+  }
+
+  #[test]
+  fn peers_agree_on_settings()
+  {
+    let (host, guest) = connect();
+    assert_eq!(host.player, 0);
+    assert_eq!(guest.player, 1);
+    assert_eq!(host.seed, guest.seed);
+    assert!(host.mode == guest.mode);
+    assert!(host.difficulty == guest.difficulty);
+    assert!(guest.difficulty.profile == Profile::Arcade);
+  }
+
+  #[test]
+  fn peers_stay_in_lockstep()
+  {
+    let (host, guest) = connect();
+    let mut peers: Vec<(Session, Game, Playfield)> = vec![ host, guest ].into_iter().map(|session|
+    {
+      let mut state = Game::new(session.mode, session.seed, session.difficulty);
+      let playfield = Playfield::new(&mut state);
+      (session, state, playfield)
+    }).collect();
+
+    let mut hashes = [ Vec::new(), Vec::new() ];
+    let deadline = Instant::now() + Duration::from_secs(20);
+    while hashes.iter().any(|h| h.len() < TICKS) == true
+    {
+      assert!(Instant::now() < deadline, "peers stalled");
+
+      for (peer, (session, state, playfield)) in peers.iter_mut().enumerate()
+      {
+        if hashes[peer].len() >= TICKS
+        {
+          continue;
+        }
+
+        let controls = match session.exchange(scripted(session.player, session.tick))
+        {
+          Ok(Some(controls)) => controls,
+          Ok(None) => continue, /* waiting on the other peer */
+          Err(e) => panic!("peer {} failed: {}", peer, e)
+        };
+
+        if playfield.step(state, &controls).is_some() == true
+        {
+          *playfield = Playfield::new(state);
+        }
+
+        if let Err(e) = session.verify(playfield.state_hash())
+        {
+          panic!("peer {} failed: {}", peer, e);
+        }
+        hashes[peer].push(playfield.state_hash());
+      }
+    }
+
+    assert!(hashes[0] == hashes[1], "the two simulations drifted apart");
+  }
+
+  #[test]
+  fn silent_guest_times_out()
+  {
+    let (listener, port) = listen();
+    let _silent = TcpStream::connect(("127.0.0.1", port)).unwrap();
+
+    loop
+    {
+      match listener.poll()
+      {
+        Ok(None) => thread::sleep(Duration::from_millis(1)),
+        Ok(Some(_)) => panic!("a guest that never said READY was let in"),
+        Err(NetError::Timeout) => break,
+        Err(e) => panic!("expected a timeout, got: {}", e)
+      }
+    }
+  }
+}
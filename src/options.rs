@@ -0,0 +1,595 @@
+/* Space invaders in Rust
+ *
+ * Command-line options
+ *
+ * Game concept by Tomohiro Nishikado / Taito
+ * Rust code By Chris Williams <diodesign@tuta.io>
+ *
+ * Written for fun. See LICENSE.
+ *
+ */
+
+/* options can also be kept in a file named with --config. each line of the file holds
+ * one option without its leading dashes, followed by its value if it takes one, eg:
+ *
+ *   # demo setup
+ *   start-level 3
+ *   lives 9
+ *   fullscreen
+ *
+ * blank lines and lines starting with # are skipped. options on the command line are
+ * read after the file's, so they win where the two disagree */
+
+extern crate rand;
+
+use std::fs;
+
+use super::difficulty::{ Difficulty, March, Profile };
+use super::env;
+use super::game::{ self, Game, GameMode };
+use super::net;
+
+pub const USAGE: &str = "\
+usage: rustinvaders [OPTIONS]
+
+Every game:
+  --seed SEED          seed for the game's random decisions, instead of a random one
+  --start-level LEVEL  level to start on, from 1 (default 1)
+  --difficulty NAME    easy, normal, arcade, insane or custom (default normal)
+
+Tweaks to the difficulty. any of these makes it a custom difficulty, starting from
+the settings of the one picked with --difficulty:
+  --lives LIVES        lives to start with, from 1 to 99 (normal is 3)
+  --march STYLE        smooth, where the whole squadron moves together, or classic,
+                       where it ripples one alien at a time (arcade is classic, the
+                       others smooth)
+  --march-speed SPEED  how fast a full squadron moves (normal is 0.1)
+  --march-speedup SPEED
+                       extra squadron speed for each alien shot down (normal is 0.008).
+                       a classic march speeds up by itself, and ignores this
+  --bomb-speed SPEED   how fast alien bombs fall (normal is 1)
+  --bullet-speed SPEED how fast the ship's bullets climb (normal is 2)
+  --ship-speed SPEED   top speed of the ship (normal is 1)
+  --ship-accel ACCEL   give the ship inertia: how much it speeds up or slows down each
+                       tick (normal is 0, where it goes straight to top speed and stops
+                       dead)
+
+Games in a window:
+  --fullscreen         open a window the size of the whole screen. it's still a normal
+                       window with a title bar, as kiss3d can't switch the screen over
+  --windowed           open a normal window (the default)
+  --size WxH           size of the window, eg: 1280x720
+  --fps FPS            frames drawn per second, from 10 to 240 (default 60). the game
+                       runs at the same speed whatever this is set to
+  --record FILE        record the keypresses of each game to FILE, replacing the last game's
+  --replay FILE        play back a game recorded with --record, then quit
+
+Games without a window:
+  --headless           play one game with the CPU player, or the game given by --replay,
+                       and print how it went. --record records the game
+  --autoplay GAMES     let the CPU player play GAMES games and print the results
+  --terminal           play in the text terminal
+  --gym [vector|grid]  serve a training environment over stdin and stdout
+
+Network games:
+  --host [PORT] [--coop | --versus]   host a network game (default port 7777, co-op). the
+                                      guest plays at the host's difficulty. --coop and
+                                      --versus only go with --host
+  --join HOST[:PORT]                  join a network game
+
+Other:
+  --config FILE        read options from FILE, one per line, before the command line's
+  --help               show this help
+";
+
+/* network game requested on the command line */
+pub enum Network
+{
+  Host(u16, GameMode), /* listen on the given port for a guest to play the given mode */
+  Join(String)         /* connect to the host at the given address */
+}
+
+/* what to do when the program starts */
+pub enum Launch
+{
+  Local,            /* open the title screen as normal, or play back a replay */
+  Headless,         /* play one game without a window */
+  Network(Network), /* host or join a network game */
+  Autoplay(u32),    /* let the bot play the given number of games, without a window */
+  Gym(env::ObservationKind), /* serve a learning environment over stdin and stdout, without a window */
+  Terminal,         /* play in the text terminal, without a window */
+  Help              /* print the usage and stop */
+}
+
+/* settings from the command line that apply whichever way the game is launched */
+pub struct Options
+{
+  pub seed: Option<u64>,            /* seed for each game, or None for a random one */
+  pub start_level: i32,             /* level each game starts on */
+  pub difficulty: Difficulty,       /* how hard each game is, and the lives it starts with */
+  pub fullscreen: bool,             /* true to size the window to cover the screen */
+  pub size: Option<(u32, u32)>,     /* width and height of the window, or None for kiss3d's default */
+  pub fps: u64,                     /* frames drawn each second */
+  pub record: Option<String>,       /* file to record games to */
+  pub replay: Option<String>        /* file to play a recorded game back from */
+}
+
+const DEFAULT_FPS: u64 = 60;
+const MIN_FPS: u64 = 10;
+const MAX_FPS: u64 = 240;
+const MAX_START_LEVEL: i32 = 999;
+const MIN_WINDOW_SIZE: u32 = 200;
+const MAX_WINDOW_SIZE: u32 = 16384;
+const MAX_SPEED: f32 = 10.0;       /* fastest anything can be made to move with the difficulty tweaks */
+
+impl Options
+{
+  fn new() -> Options
+  {
+    Options
+    {
+      seed: None, start_level: 1, difficulty: Difficulty::new(Profile::Normal),
+      fullscreen: false, size: None, fps: DEFAULT_FPS,
+      record: None, replay: None
+    }
+  }
+
+  /* start a new game with the chosen difficulty and starting level
+   * => mode = who is playing
+   *    seed = seed for the game's random decisions */
+  pub fn new_game(&self, mode: GameMode, seed: u64) -> Game
+  {
+    let mut state = Game::new(mode, seed, self.difficulty);
+
+    /* levels skipped over count as played, with no time spent on them */
+    state.level = self.start_level - 1;
+    state.stats.level_ticks = vec![ 0; state.level as usize ];
+    return state;
+  }
+
+  /* returns the seed to use for a game: the chosen one, or a random one */
+  pub fn pick_seed(&self) -> u64
+  {
+    self.seed.unwrap_or_else(rand::random::<u64>)
+  }
+}
+
+/* a change to one setting of the picked difficulty */
+enum Tweak
+{
+  Lives(i32),         /* lives to start with */
+  March(March),       /* how the squadron moves */
+  Speed(String, f32)  /* option naming a speed, and the speed it gives */
+}
+
+/* returns the value following an option, parsed and checked
+ * => args, index = the arguments, and where the option is
+ *    option = the option's name, for error messages
+ *    wanted = what the value should be, for error messages
+ *    check = returns true if the value is acceptable */
+fn value<T: std::str::FromStr, F: Fn(&T) -> bool>(args: &[String], index: usize, option: &str, wanted: &str, check: F) -> Result<T, String>
+{
+  match args.get(index + 1).and_then(|a| a.parse::<T>().ok())
+  {
+    Some(value) if check(&value) == true => Ok(value),
+    _ => Err(format!("{} needs {}", option, wanted))
+  }
+}
+
+/* turn WIDTHxHEIGHT into a width and height */
+fn parse_size(text: &str) -> Option<(u32, u32)>
+{
+  let mut parts = text.splitn(2, |c| c == 'x' || c == 'X');
+  let width = parts.next()?.parse::<u32>().ok()?;
+  let height = parts.next()?.parse::<u32>().ok()?;
+  Some((width, height))
+}
+
+/* read a config file into a list of arguments, as if they'd been typed on the command line */
+fn read_config(path: &str) -> Result<Vec<String>, String>
+{
+  let text = fs::read_to_string(path).map_err(|e| format!("can't read config file {}: {}", path, e))?;
+  let mut args = Vec::new();
+
+  for line in text.lines().map(|l| l.trim()).filter(|l| l.is_empty() == false && l.starts_with('#') == false)
+  {
+    let mut words = line.split_whitespace();
+    if let Some(option) = words.next()
+    {
+      if option == "config"
+      {
+        return Err(format!("config file {} can't name another config file", path));
+      }
+      args.push(format!("--{}", option));
+      args.extend(words.map(String::from));
+    }
+  }
+
+  return Ok(args);
+}
+
+/* parse the command line arguments
+ * => args = arguments after the program name
+ * <= what to do at launch and the options to do it with, or an error message */
+pub fn parse_args(args: Vec<String>) -> Result<(Launch, Options), String>
+{
+  /* pull in config files first, so the command line can override them */
+  let mut config = Vec::new();
+  let mut rest = Vec::new();
+  let mut index = 0;
+  while index < args.len()
+  {
+    match args[index].as_str()
+    {
+      "--config" =>
+      {
+        match args.get(index + 1)
+        {
+          Some(path) => config.extend(read_config(path)?),
+          None => return Err(String::from("--config needs a file name"))
+        }
+        index = index + 1;
+      },
+      other => rest.push(String::from(other))
+    }
+    index = index + 1;
+  }
+  config.extend(rest);
+  let args = config;
+
+  let mut options = Options::new();
+  let mut host = None;
+  let mut join = None;
+  let mut mode = None; /* mode of a hosted game, if picked */
+  let mut autoplay = None;
+  let mut gym = None;
+  let mut text = false;
+  let mut headless = false;
+  let mut profile = Profile::Normal;
+  let mut tweaks = Vec::new(); /* changes to the picked difficulty, in the order given */
+  let mut index = 0;
+
+  while index < args.len()
+  {
+    match args[index].as_str()
+    {
+      "--help" | "-h" => return Ok((Launch::Help, options)),
+      "--host" =>
+      {
+        /* port number is optional */
+        let mut port = net::DEFAULT_PORT;
+        if let Some(number) = args.get(index + 1).and_then(|a| a.parse::<u16>().ok())
+        {
+          port = number;
+          index = index + 1;
+        }
+        host = Some(port);
+      },
+      "--join" =>
+      {
+        match args.get(index + 1)
+        {
+          Some(address) => join = Some(address.clone()),
+          None => return Err(String::from("--join needs the host's address"))
+        }
+        index = index + 1;
+      },
+      "--coop" => mode = Some(GameMode::Coop),
+      "--versus" => mode = Some(GameMode::Versus),
+      "--autoplay" =>
+      {
+        autoplay = Some(value(&args, index, "--autoplay", "the number of games to play", |_: &u32| true)?);
+        index = index + 1;
+      },
+      "--seed" =>
+      {
+        options.seed = Some(value(&args, index, "--seed", "a number", |_: &u64| true)?);
+        index = index + 1;
+      },
+      "--start-level" =>
+      {
+        options.start_level = value(&args, index, "--start-level", &format!("a level from 1 to {}", MAX_START_LEVEL),
+                                    |l| *l >= 1 && *l <= MAX_START_LEVEL)?;
+        index = index + 1;
+      },
+      "--difficulty" =>
+      {
+        profile = match args.get(index + 1).and_then(|a| Profile::from_name(a))
+        {
+          Some(profile) => profile,
+          None => return Err(String::from("--difficulty needs one of easy, normal, arcade, insane or custom"))
+        };
+        index = index + 1;
+      },
+      "--lives" =>
+      {
+        let lives = value(&args, index, "--lives", &format!("a number of lives from 1 to {}", game::MAX_LIVES),
+                          |l| *l >= 1 && *l <= game::MAX_LIVES)?;
+        tweaks.push(Tweak::Lives(lives));
+        index = index + 1;
+      },
+      "--march" =>
+      {
+        match args.get(index + 1).and_then(|a| March::from_name(a))
+        {
+          Some(march) => tweaks.push(Tweak::March(march)),
+          None => return Err(String::from("--march needs smooth or classic"))
+        }
+        index = index + 1;
+      },
+      "--march-speed" | "--march-speedup" | "--bomb-speed" | "--bullet-speed" | "--ship-speed" | "--ship-accel" =>
+      {
+        /* everything has to move, except the squadron needn't speed up and the ship
+         * needn't have inertia */
+        let option = args[index].as_str();
+        let (slowest, wanted) = match option
+        {
+          "--march-speedup" | "--ship-accel" => (0.0, format!("a speed from 0 to {}", MAX_SPEED)),
+          _ => (f32::MIN_POSITIVE, format!("a speed above 0 and up to {}", MAX_SPEED))
+        };
+        let speed = value(&args, index, option, &wanted, |s: &f32| *s >= slowest && *s <= MAX_SPEED)?;
+        tweaks.push(Tweak::Speed(String::from(option), speed));
+        index = index + 1;
+      },
+      "--fullscreen" => options.fullscreen = true,
+      "--windowed" => options.fullscreen = false,
+      "--size" =>
+      {
+        let size = args.get(index + 1).and_then(|a| parse_size(a));
+        match size
+        {
+          Some((width, height)) if [ width, height ].iter().all(|s| *s >= MIN_WINDOW_SIZE && *s <= MAX_WINDOW_SIZE) =>
+          {
+            options.size = Some((width, height));
+            options.fullscreen = false;
+          },
+          _ => return Err(format!("--size needs a width and height from {} to {}, eg: 1280x720",
+                                  MIN_WINDOW_SIZE, MAX_WINDOW_SIZE))
+        }
+        index = index + 1;
+      },
+      "--fps" =>
+      {
+        options.fps = value(&args, index, "--fps", &format!("a frame rate from {} to {}", MIN_FPS, MAX_FPS),
+                            |f| *f >= MIN_FPS && *f <= MAX_FPS)?;
+        index = index + 1;
+      },
+      "--record" | "--replay" =>
+      {
+        let file = match args.get(index + 1)
+        {
+          Some(file) => Some(file.clone()),
+          None => return Err(format!("{} needs a file name", args[index]))
+        };
+        match args[index].as_str()
+        {
+          "--record" => options.record = file,
+          _ => options.replay = file
+        }
+        index = index + 1;
+      },
+      "--headless" => headless = true,
+      "--gym" =>
+      {
+        /* observation kind is optional */
+        let mut kind = env::ObservationKind::Vector;
+        if let Some(named) = args.get(index + 1).and_then(|a| env::ObservationKind::from_name(a))
+        {
+          kind = named;
+          index = index + 1;
+        }
+        gym = Some(kind);
+      },
+      "--terminal" => text = true,
+      other => return Err(format!("unknown option {}", other))
+    }
+    index = index + 1;
+  }
+
+  /* apply any tweaks to the picked difficulty, making it a custom one */
+  options.difficulty = Difficulty::new(profile);
+  for tweak in tweaks.iter()
+  {
+    let difficulty = &mut options.difficulty;
+    match tweak
+    {
+      Tweak::Lives(lives) => difficulty.lives = *lives,
+      Tweak::March(march) => difficulty.march = *march,
+      Tweak::Speed(option, speed) => match option.as_str()
+      {
+        "--march-speed" => difficulty.march_step = *speed,
+        "--march-speedup" => difficulty.march_speedup = *speed,
+        "--bomb-speed" => difficulty.bomb_speed = *speed,
+        "--bullet-speed" => difficulty.bullet_speed = *speed,
+        "--ship-accel" => difficulty.ship_accel = *speed,
+        _ => difficulty.ship_speed = *speed
+      }
+    }
+    difficulty.profile = Profile::Custom;
+  }
+
+  /* only one way of running the game can be picked */
+  let picked = [ host.is_some(), join.is_some(), autoplay.is_some(), gym.is_some(), text, headless ];
+  if picked.iter().filter(|p| **p == true).count() > 1
+  {
+    return Err(String::from("pick only one of --host, --join, --autoplay, --gym, --terminal and --headless"));
+  }
+
+  /* local games pick their mode on the title screen */
+  if mode.is_some() == true && host.is_none() == true
+  {
+    return Err(String::from("--coop and --versus pick the mode of a hosted game, so they need --host"));
+  }
+
+  /* recordings are only made of games played on this machine */
+  let recorded = options.record.is_some() == true || options.replay.is_some() == true;
+  if recorded == true && [ host.is_some(), join.is_some(), autoplay.is_some(), gym.is_some(), text ].iter().any(|p| *p == true)
+  {
+    return Err(String::from("--record and --replay only work with games in a window or with --headless"));
+  }
+  if options.record.is_some() == true && options.record == options.replay
+  {
+    return Err(String::from("--record and --replay can't use the same file"));
+  }
+
+  if let Some(kind) = gym
+  {
+    return Ok((Launch::Gym(kind), options));
+  }
+
+  if text == true
+  {
+    return Ok((Launch::Terminal, options));
+  }
+
+  if headless == true
+  {
+    return Ok((Launch::Headless, options));
+  }
+
+  let launch = match (host, join, autoplay)
+  {
+    (None, None, Some(games)) => Launch::Autoplay(games),
+    (Some(port), None, None) => Launch::Network(Network::Host(port, mode.unwrap_or(GameMode::Coop))),
+    (None, Some(address), None) =>
+    {
+      /* use the default port if one isn't given */
+      match address.contains(':')
+      {
+        true => Launch::Network(Network::Join(address)),
+        false => Launch::Network(Network::Join(format!("{}:{}", address, net::DEFAULT_PORT)))
+      }
+    },
+    _ => Launch::Local
+  };
+
+  return Ok((launch, options));
+}
+
+/* ------------------------------------------------------------------------------ */
+
+#[cfg(test)]
+mod tests
+{
+  use super::*;
+
+  /* parse a command line given as one string */
+  fn parse(line: &str) -> Result<(Launch, Options), String>
+  {
+    parse_args(line.split_whitespace().map(String::from).collect())
+  }
+
+  /* returns a short name for the way of playing a command line picks, to compare against */
+  fn launch(line: &str) -> String
+  {
+    let launch = match parse(line)
+    {
+      Ok((launch, _)) => launch,
+      Err(reason) => panic!("rejected {}: {}", line, reason)
+    };
+
+    match launch
+    {
+      Launch::Local => String::from("local"),
+      Launch::Headless => String::from("headless"),
+      Launch::Network(Network::Host(port, mode)) => format!("host {} {}", port, game_mode_name(mode)),
+      Launch::Network(Network::Join(address)) => format!("join {}", address),
+      Launch::Autoplay(games) => format!("autoplay {}", games),
+      Launch::Gym(env::ObservationKind::Vector) => String::from("gym vector"),
+      Launch::Gym(env::ObservationKind::Grid) => String::from("gym grid"),
+      Launch::Terminal => String::from("terminal"),
+      Launch::Help => String::from("help")
+    }
+  }
+
+  fn game_mode_name(mode: GameMode) -> &'static str
+  {
+    match mode
+    {
+      GameMode::Single => "single",
+      GameMode::Coop => "coop",
+      GameMode::Versus => "versus"
+    }
+  }
+
+  #[test]
+  fn defaults_to_a_local_game()
+  {
+    assert_eq!(launch(""), "local");
+
+    let (_, options) = parse("").unwrap();
+    assert_eq!(options.seed, None);
+    assert_eq!(options.start_level, 1);
+    assert!(options.difficulty == Difficulty::new(Profile::Normal));
+    assert_eq!(options.fps, DEFAULT_FPS);
+  }
+
+  #[test]
+  fn reads_game_settings()
+  {
+    let (_, options) = parse("--seed 42 --start-level 3 --difficulty arcade --fps 30 --size 800x600").unwrap();
+    assert_eq!(options.seed, Some(42));
+    assert_eq!(options.start_level, 3);
+    assert!(options.difficulty == Difficulty::new(Profile::Arcade));
+    assert_eq!(options.fps, 30);
+    assert_eq!(options.size, Some((800, 600)));
+  }
+
+  #[test]
+  fn tweaks_make_a_custom_difficulty()
+  {
+    let (_, options) = parse("--difficulty easy --lives 7 --march classic --bomb-speed 1.5 --ship-accel 0.1").unwrap();
+    let easy = Difficulty::new(Profile::Easy);
+    assert!(options.difficulty.profile == Profile::Custom);
+    assert_eq!(options.difficulty.lives, 7);
+    assert!(options.difficulty.march == March::Classic);
+    assert_eq!(options.difficulty.bomb_speed, 1.5);
+    assert_eq!(options.difficulty.ship_accel, 0.1);
+    assert_eq!(options.difficulty.ship_speed, easy.ship_speed);
+  }
+
+  #[test]
+  fn picks_the_launch()
+  {
+    assert_eq!(launch("--help"), "help");
+    assert_eq!(launch("--headless"), "headless");
+    assert_eq!(launch("--terminal"), "terminal");
+    assert_eq!(launch("--autoplay 5"), "autoplay 5");
+    assert_eq!(launch("--gym grid"), "gym grid");
+    assert_eq!(launch("--host"), format!("host {} coop", net::DEFAULT_PORT));
+    assert_eq!(launch("--host 9000 --versus"), "host 9000 versus");
+    assert_eq!(launch("--join example.com"), format!("join example.com:{}", net::DEFAULT_PORT));
+  }
+
+  #[test]
+  fn rejects_bad_command_lines()
+  {
+    let bad =
+    [
+      "--bogus",
+      "--seed",
+      "--seed minus-one",
+      "--start-level 0",
+      "--lives 0",
+      "--difficulty impossible",
+      "--march sideways",
+      "--bomb-speed 0",
+      "--ship-accel -1",
+      "--fps 1000",
+      "--size 10x10",
+      "--record",
+      "--join",
+      "--host --autoplay 3",
+      "--terminal --headless",
+      "--coop",
+      "--versus --join example.com",
+      "--record a.rec --autoplay 3",
+      "--record same.rec --replay same.rec"
+    ];
+
+    for line in bad.iter()
+    {
+      assert!(parse(line).is_err() == true, "accepted {}", line);
+    }
+  }
+}
@@ -0,0 +1,231 @@
+/* Space invaders in Rust
+ *
+ * Debug overlay: frame timings and object counts, for finding slowdowns
+ *
+ * Game concept by Tomohiro Nishikado / Taito
+ * Rust code By Chris Williams <diodesign@tuta.io>
+ *
+ * Written for fun. See LICENSE.
+ *
+ */
+
+/* F3 shows or hides the overlay during a game, and F4 starts or stops logging the
+ * counters to PROFILE_FILE once a second, ready to load into a spreadsheet. the overlay
+ * and log both carry on from level to level until switched off */
+
+extern crate kiss3d;
+extern crate nalgebra as na;
+
+use std::fs::{ File, OpenOptions };
+use std::io::{ self, Write };
+use std::rc::Rc;
+use std::time::{ Duration, Instant };
+
+use na::{ Point2, Point3 };
+use kiss3d::window::Window;
+use kiss3d::text::Font;
+
+use super::aliens;
+use super::game::Playfield;
+use super::render::Scene;
+
+const PROFILE_FILE: &str = "rustinvaders-profile.csv";
+const PROFILE_HEADER: &str = "seconds,fps,frame_ms,tick_ms,scene_nodes,aliens_alive,aliens_dying,aliens_dead,projectiles,particles";
+
+const TEXT_SIZE: f32 = 40.0;
+const TEXT_TOP: f32 = 120.0;      /* y position of the overlay's first line, below the HUD */
+const TEXT_SPACING: f32 = 22.0;   /* distance between the overlay's lines */
+
+/* a snapshot of what's going on in the game */
+struct Counters
+{
+  nodes: usize,        /* scene nodes on show, leaving out hidden ones such as those waiting in pools */
+  alive: usize,        /* aliens still flying in the squadron */
+  dying: usize,        /* aliens blowing up */
+  dead: usize,         /* aliens gone for good */
+  projectiles: usize,  /* bullets, bombs and capsules in play */
+  particles: usize     /* particles in flight */
+}
+
+impl Counters
+{
+  fn new(scene: &Scene, playfield: &Playfield) -> Counters
+  {
+    let squadron = playfield.aliens.get_squadron();
+    let count = |state| squadron.iter().filter(|a| a.get_state() == state).count();
+
+    Counters
+    {
+      nodes: scene.node_count(),
+      alive: count(aliens::State::Alive),
+      dying: count(aliens::State::Dying),
+      dead: count(aliens::State::Dead),
+      projectiles: playfield.bullets().len() + playfield.bombs().len() + playfield.powerups.capsules.len(),
+      particles: scene.particle_count()
+    }
+  }
+}
+
+/* times the game and, when switched on, shows what it finds and logs it */
+pub struct Overlay
+{
+  visible: bool,          /* true to draw the overlay */
+  log: Option<File>,      /* where to write the counters each second, if logging */
+  started: Instant,       /* when the overlay was created, for the log's timestamps */
+  last_frame: Instant,    /* when the previous frame was drawn */
+  frame_time: Duration,   /* time taken by the previous frame */
+  tick_time: Duration,    /* time taken by the previous simulation tick */
+
+  /* totals over the current second, averaged when the second is up */
+  second: Instant,
+  frames: u32,
+  ticks: u32,
+  frame_total: Duration,
+  tick_total: Duration,
+  fps: u32,
+  frame_average: Duration,
+  tick_average: Duration
+}
+
+impl Overlay
+{
+  pub fn new() -> Overlay
+  {
+    let now = Instant::now();
+    Overlay
+    {
+      visible: false,
+      log: None,
+      started: now,
+      last_frame: now,
+      frame_time: Duration::from_secs(0),
+      tick_time: Duration::from_secs(0),
+      second: now,
+      frames: 0,
+      ticks: 0,
+      frame_total: Duration::from_secs(0),
+      tick_total: Duration::from_secs(0),
+      fps: 0,
+      frame_average: Duration::from_secs(0),
+      tick_average: Duration::from_secs(0)
+    }
+  }
+
+  /* show the overlay if it's hidden, and hide it if it's showing */
+  pub fn toggle(&mut self)
+  {
+    self.visible = self.visible == false;
+  }
+
+  /* start logging to the profile file if we're not, and stop if we are
+   * <= true if now logging */
+  pub fn toggle_log(&mut self) -> io::Result<bool>
+  {
+    if self.log.take().is_some() == true
+    {
+      return Ok(false);
+    }
+
+    let mut file = OpenOptions::new().create(true).append(true).open(PROFILE_FILE)?;
+    if file.metadata()?.len() == 0
+    {
+      writeln!(file, "{}", PROFILE_HEADER)?;
+    }
+    self.log = Some(file);
+    return Ok(true);
+  }
+
+  /* run a simulation tick, timing how long it takes
+   * => tick = function that runs the tick
+   * <= whatever the tick returns */
+  pub fn time_tick<T, F: FnOnce() -> T>(&mut self, tick: F) -> T
+  {
+    let start = Instant::now();
+    let result = tick();
+    self.tick_time = start.elapsed();
+    self.tick_total = self.tick_total + self.tick_time;
+    self.ticks = self.ticks + 1;
+    return result;
+  }
+
+  /* call once per frame to time the frame, draw the overlay if it's showing, and log
+   * the counters if a second has passed
+   * => window = graphics context
+   *    font = font to draw the overlay with
+   *    scene, playfield = the level being played */
+  pub fn frame(&mut self, window: &mut Window, font: &Rc<Font>, scene: &Scene, playfield: &Playfield)
+  {
+    let now = Instant::now();
+    self.frame_time = now - self.last_frame;
+    self.last_frame = now;
+    self.frames = self.frames + 1;
+    self.frame_total = self.frame_total + self.frame_time;
+
+    /* work out the averages once a second */
+    let mut second_up = false;
+    if now - self.second >= Duration::from_secs(1)
+    {
+      self.fps = self.frames;
+      self.frame_average = self.frame_total / self.frames.max(1);
+      self.tick_average = self.tick_total / self.ticks.max(1);
+      self.second = now;
+      self.frames = 0;
+      self.ticks = 0;
+      self.frame_total = Duration::from_secs(0);
+      self.tick_total = Duration::from_secs(0);
+      second_up = true;
+    }
+
+    if self.visible == false && (second_up == false || self.log.is_none() == true)
+    {
+      return;
+    }
+
+    let counters = Counters::new(scene, playfield);
+
+    if self.visible == true
+    {
+      let lines =
+      [
+        format!("FPS {}  frame {:.2} ms  tick {:.3} ms", self.fps, milliseconds(self.frame_time), milliseconds(self.tick_time)),
+        format!("Scene nodes on show {}", counters.nodes),
+        format!("Aliens alive {}  dying {}  dead {}", counters.alive, counters.dying, counters.dead),
+        format!("Projectiles {}  particles {}", counters.projectiles, counters.particles),
+        format!("Profile log {}", match self.log.is_some() { true => "on", false => "off" })
+      ];
+
+      for (index, line) in lines.iter().enumerate()
+      {
+        window.draw_text(line.as_str(), &Point2::new(10.0, TEXT_TOP + (index as f32 * TEXT_SPACING)), TEXT_SIZE,
+                         font, &Point3::new(1.0, 0.6, 0.2));
+      }
+    }
+
+    if second_up == true
+    {
+      let row = format!("{:.1},{},{:.3},{:.3},{},{},{},{},{},{}",
+                        (now - self.started).as_secs_f32(), self.fps,
+                        milliseconds(self.frame_average), milliseconds(self.tick_average),
+                        counters.nodes, counters.alive, counters.dying, counters.dead,
+                        counters.projectiles, counters.particles);
+
+      /* give up logging if the file can't be written to, rather than complain every second */
+      let failed = match self.log.as_mut()
+      {
+        Some(file) => writeln!(file, "{}", row).is_err(),
+        None => false
+      };
+      if failed == true
+      {
+        eprintln!("rustinvaders: could not write to {}, stopped logging", PROFILE_FILE);
+        self.log = None;
+      }
+    }
+  }
+}
+
+/* returns a duration in milliseconds */
+fn milliseconds(duration: Duration) -> f32
+{
+  duration.as_secs_f32() * 1000.0
+}
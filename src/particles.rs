@@ -0,0 +1,281 @@
+/* Space invaders in Rust
+ *
+ * Particle effects: explosions, debris, impacts and splashes
+ *
+ * Game concept by Tomohiro Nishikado / Taito
+ * Rust code By Chris Williams <diodesign@tuta.io>
+ *
+ * Written for fun. See LICENSE.
+ *
+ */
+
+extern crate kiss3d;
+extern crate nalgebra as na;
+extern crate rand;
+
+use rand::Rng;
+use na::{ Vector3, Translation3, UnitQuaternion };
+use kiss3d::scene::SceneNode;
+
+use super::game::TICKS_PER_SECOND;
+use super::pool::{ Pool, Shape };
+
+/* describes how a burst of particles looks and moves. effects are plain data, so a new
+ * kind of explosion is a new entry below rather than more animation code */
+pub struct Effect
+{
+  pub count: usize,                       /* particles created per burst, unless the caller supplies its own */
+  pub size: f32,                          /* width of each particle's cube */
+  pub lifetime: u32,                      /* ticks each particle lives for */
+  pub speed: (f32, f32),                  /* slowest and fastest movement along each axis per tick */
+  pub upwards: bool,                      /* true to only fly upwards */
+  pub gravity: f32,                       /* change in y velocity each tick */
+  pub spin: f32,                          /* y-axis rotation of each particle per tick */
+  pub colors: &'static [(f32, f32, f32)], /* colors the particles fade through over their lifetime */
+  pub scale: (f32, f32)                   /* size of the particles at the start and end of their lifetime */
+}
+
+/* an alien's pixels flying apart, glowing orange then yellow before burning out */
+pub const ALIEN_EXPLOSION: Effect = Effect
+{
+  count: 0, size: 0.0, /* a cube per pixel of the alien, in the pixel's size */
+  lifetime: 11 * TICKS_PER_SECOND,
+  speed: (0.1, 0.5),
+  upwards: false,
+  gravity: 0.0,
+  spin: 0.05,
+  colors: &[ (1.0, 0.4, 0.0), (1.0, 0.4, 0.0), (1.0, 0.6, 0.0), (1.0, 0.8, 0.0), (1.0, 1.0, 0.0),
+             (0.8, 0.8, 0.0), (0.6, 0.6, 0.0), (0.4, 0.4, 0.0), (0.2, 0.2, 0.0), (0.2, 0.2, 0.0) ],
+  scale: (1.0, 1.0)
+};
+
+/* the hero's ship blowing up into a shower of red debris */
+pub const HERO_EXPLOSION: Effect = Effect
+{
+  count: 20, size: 2.0,
+  lifetime: 5 * TICKS_PER_SECOND,
+  speed: (0.1, 0.5),
+  upwards: true,
+  gravity: 0.0,
+  spin: 0.10,
+  colors: &[ (1.0, 0.2, 0.2) ],
+  scale: (1.0, 1.0)
+};
+
+/* sparks from a bullet striking an alien */
+pub const BULLET_IMPACT: Effect = Effect
+{
+  count: 8, size: 1.0,
+  lifetime: TICKS_PER_SECOND / 3,
+  speed: (0.3, 1.0),
+  upwards: false,
+  gravity: -0.05,
+  spin: 0.2,
+  colors: &[ (1.0, 1.0, 0.6), (1.0, 0.5, 0.0), (0.3, 0.1, 0.0) ],
+  scale: (1.0, 0.2)
+};
+
+/* a bomb bursting as it hits the ground or a ship */
+pub const BOMB_SPLASH: Effect = Effect
+{
+  count: 12, size: 1.5,
+  lifetime: TICKS_PER_SECOND * 2 / 3,
+  speed: (0.2, 0.8),
+  upwards: true,
+  gravity: -0.04,
+  spin: 0.1,
+  colors: &[ (0.6, 1.0, 0.6), (0.0, 1.0, 0.0), (0.0, 0.3, 0.0) ],
+  scale: (1.0, 0.3)
+};
+
+/* a single particle flying through the game world */
+struct Particle
+{
+  node: SceneNode,           /* the cube drawn for this particle, from the particle pool */
+  size: Vector3<f32>,        /* width, height and depth of the cube at full scale */
+  start: Vector3<f32>,       /* where the particle was created */
+  velocity: Vector3<f32>,    /* movement per tick when created */
+  age: u32,                  /* ticks since the particle was created */
+  effect: &'static Effect    /* how the particle behaves */
+}
+
+impl Particle
+{
+  /* move, spin, color and size the particle for its age */
+  fn place(&mut self)
+  {
+    let ticks = self.age as f32;
+    let effect = self.effect;
+    let life = (ticks / effect.lifetime.max(1) as f32).min(1.0);
+
+    let mut position = self.start + (self.velocity * ticks);
+    position.y = position.y + (0.5 * effect.gravity * ticks * ticks);
+    self.node.set_local_translation(Translation3::from(position));
+    self.node.set_local_rotation(UnitQuaternion::from_axis_angle(&Vector3::y_axis(), effect.spin * ticks));
+
+    let scale = self.size * (effect.scale.0 + ((effect.scale.1 - effect.scale.0) * life));
+    self.node.set_local_scale(scale.x, scale.y, scale.z);
+
+    let (r, g, b) = blend(effect.colors, life);
+    self.node.set_color(r, g, b);
+  }
+}
+
+/* returns the color a fraction of the way through a list of evenly spaced colors
+ * => colors = colors to blend between
+ *    position = how far through the list, from 0.0 to 1.0 */
+fn blend(colors: &[(f32, f32, f32)], position: f32) -> (f32, f32, f32)
+{
+  if colors.len() < 2
+  {
+    return colors.first().cloned().unwrap_or((1.0, 1.0, 1.0));
+  }
+
+  let place = position * (colors.len() - 1) as f32;
+  let index = (place as usize).min(colors.len() - 2);
+  let fraction = place - index as f32;
+  let (from, to) = (colors[index], colors[index + 1]);
+
+  return (from.0 + ((to.0 - from.0) * fraction),
+          from.1 + ((to.1 - from.1) * fraction),
+          from.2 + ((to.2 - from.2) * fraction));
+}
+
+/* every particle in flight. the particles move on one tick each time update() is called */
+pub struct Particles
+{
+  particles: Vec<Particle>,
+  pool: Pool,                   /* cubes to use for particles */
+  rng: rand::rngs::ThreadRng    /* access to the thread's RNG, for particle trajectories */
+}
+
+impl Particles
+{
+  /* create a particle system
+   * => parent = scene node to add the particles to
+   *    capacity = most particles that can be bursting at once */
+  pub fn new(parent: &mut SceneNode, capacity: usize) -> Particles
+  {
+    Particles
+    {
+      particles: Vec::new(),
+      pool: Pool::new(parent, "particles", Shape::Cube, capacity),
+      rng: rand::thread_rng()
+    }
+  }
+
+  /* create a burst of the effect's particles. if the pool runs dry, the burst is smaller
+   * => effect = what the particles look like and how they move
+   *    x, y, z = where to start the particles */
+  pub fn burst(&mut self, effect: &'static Effect, x: f32, y: f32, z: f32)
+  {
+    for _ in 0..effect.count
+    {
+      match self.pool.take()
+      {
+        Some(node) => self.launch(node, Vector3::repeat(effect.size), effect, Vector3::new(x, y, z)),
+        None => break
+      }
+    }
+  }
+
+  /* break something into pieces and send them flying as the effect's particles, eg: an
+   * alien's pixels. if the pool runs dry, some of the pieces are left out
+   * => effect = how the particles look and move
+   *    pieces = where each piece starts, and its width, height and depth */
+  pub fn shatter(&mut self, effect: &'static Effect, pieces: &[(Vector3<f32>, Vector3<f32>)])
+  {
+    for (start, size) in pieces.iter()
+    {
+      match self.pool.take()
+      {
+        Some(node) => self.launch(node, *size, effect, *start),
+        None => break
+      }
+    }
+  }
+
+  /* set a particle off from the given start position in a random direction */
+  fn launch(&mut self, node: SceneNode, size: Vector3<f32>, effect: &'static Effect, start: Vector3<f32>)
+  {
+    let mut velocity = Vector3::new(self.random_speed(effect), self.random_speed(effect), self.random_speed(effect));
+    if effect.upwards == true
+    {
+      velocity.y = velocity.y.abs();
+    }
+
+    let mut particle = Particle
+    {
+      node: node,
+      size: size,
+      start: start,
+      velocity: velocity,
+      age: 0,
+      effect: effect
+    };
+    particle.place();
+    self.particles.push(particle);
+  }
+
+  /* generate a random speed along one axis for the given effect, in either direction */
+  fn random_speed(&mut self, effect: &Effect) -> f32
+  {
+    let (slowest, fastest) = effect.speed;
+    let speed = match fastest > slowest
+    {
+      true => self.rng.gen_range(slowest, fastest),
+      false => slowest
+    };
+
+    if self.rng.gen()
+    {
+      return 0.0 - speed;
+    }
+    return speed;
+  }
+
+  /* returns the number of particles in flight */
+  pub fn count(&self) -> usize
+  {
+    self.particles.len()
+  }
+
+  /* returns the number of scene nodes taken from the particle pool */
+  pub fn nodes_in_use(&self) -> usize
+  {
+    self.pool.in_use()
+  }
+
+  /* move every particle on by a tick, and remove those that have burnt out */
+  pub fn update(&mut self)
+  {
+    for particle in self.particles.iter_mut()
+    {
+      particle.age = particle.age + 1;
+      particle.place();
+    }
+
+    let (alive, expired): (Vec<Particle>, Vec<Particle>) = self.particles.drain(..).partition(|p| p.age < p.effect.lifetime);
+    self.particles = alive;
+    for particle in expired
+    {
+      self.remove(particle);
+    }
+  }
+
+  /* take a particle out of the game world, putting its cube back in the pool */
+  fn remove(&mut self, particle: Particle)
+  {
+    self.pool.give(particle.node);
+  }
+
+  /* remove every particle, and the pool of cubes, from the game world */
+  pub fn delete(&mut self)
+  {
+    while let Some(particle) = self.particles.pop()
+    {
+      self.remove(particle);
+    }
+    self.pool.delete();
+  }
+}
@@ -0,0 +1,109 @@
+/* Space invaders in Rust
+ *
+ * Recycle scene nodes rather than adding and removing them all the time
+ *
+ * Game concept by Tomohiro Nishikado / Taito
+ * Rust code By Chris Williams <diodesign@tuta.io>
+ *
+ * Written for fun. See LICENSE.
+ *
+ */
+
+/* projectiles and particles come and go many times a second. rather than adding a node
+ * to the kiss3d scene graph for each one and unlinking it afterwards, a pool creates a
+ * fixed number of hidden nodes up front and hands them out and back again. when a pool
+ * runs dry, it says so and the caller goes without, rather than the scene growing without
+ * limit */
+
+extern crate kiss3d;
+
+use kiss3d::scene::SceneNode;
+
+/* the shape of the nodes in a pool. they're created at unit size: scale them as needed */
+pub enum Shape
+{
+  Cube,
+  Sphere
+}
+
+/* a set of identical scene nodes waiting to be used */
+pub struct Pool
+{
+  name: &'static str,      /* what the nodes are for, when reporting the pool has run dry */
+  free: Vec<SceneNode>,    /* hidden nodes ready to hand out */
+  size: usize,             /* number of nodes created for the pool */
+  misses: u32              /* number of times a node was wanted but none were free */
+}
+
+impl Pool
+{
+  /* create a pool of hidden nodes
+   * => parent = scene node to add the pool's nodes to
+   *    name = what the nodes are for
+   *    shape = shape of the nodes
+   *    capacity = number of nodes to create */
+  pub fn new(parent: &mut SceneNode, name: &'static str, shape: Shape, capacity: usize) -> Pool
+  {
+    let mut free = Vec::with_capacity(capacity);
+    for _ in 0..capacity
+    {
+      let mut node = match shape
+      {
+        Shape::Cube => parent.add_cube(1.0, 1.0, 1.0),
+        Shape::Sphere => parent.add_sphere(0.5)
+      };
+      node.set_visible(false);
+      free.push(node);
+    }
+
+    Pool { name: name, free: free, size: capacity, misses: 0 }
+  }
+
+  /* take a node out of the pool and make it visible
+   * <= the node, or None if the pool has run dry */
+  pub fn take(&mut self) -> Option<SceneNode>
+  {
+    match self.free.pop()
+    {
+      Some(mut node) =>
+      {
+        node.set_visible(true);
+        Some(node)
+      },
+
+      None =>
+      {
+        /* mention it the first time only, otherwise it'll flood the terminal */
+        if self.misses == 0
+        {
+          eprintln!("rustinvaders: ran out of {} to draw, some won't be shown", self.name);
+        }
+        self.misses = self.misses + 1;
+        None
+      }
+    }
+  }
+
+  /* returns the number of nodes taken out of the pool and not yet given back */
+  pub fn in_use(&self) -> usize
+  {
+    self.size - self.free.len()
+  }
+
+  /* hide a node and put it back in the pool, ready to be used again */
+  pub fn give(&mut self, mut node: SceneNode)
+  {
+    node.set_visible(false);
+    self.free.push(node);
+  }
+
+  /* remove the pool's nodes from the game world. give back any nodes taken out first */
+  pub fn delete(&mut self)
+  {
+    while let Some(mut node) = self.free.pop()
+    {
+      node.unlink();
+    }
+    self.size = 0;
+  }
+}
@@ -0,0 +1,250 @@
+/* Space invaders in Rust
+ *
+ * Power-ups: capsules dropped by aliens that give the hero's ship a boost for a while
+ *
+ * Game concept by Tomohiro Nishikado / Taito
+ * Rust code By Chris Williams <diodesign@tuta.io>
+ *
+ * Written for fun. See LICENSE.
+ *
+ */
+
+/* every so often, an alien shot down lets go of a capsule that drifts down the screen.
+ * a ship that flies into it picks up the power-up inside, which lasts for a few seconds.
+ * whether an alien drops a capsule, and what's in it, is decided by the level's seed so
+ * recordings and network games play out the same every time */
+
+extern crate rand;
+
+use rand::Rng;
+
+use super::collision;
+use super::game::TICKS_PER_SECOND;
+use super::hero::{ self, Hero };
+use super::prng::Prng;
+use super::save;
+
+const DROP_CHANCE: u32 = 10;        /* one in this many aliens shot down drops a capsule */
+pub const MAX_CAPSULES: usize = 4;  /* most capsules falling at once */
+const CAPSULE_SPEED: f32 = 0.5;     /* distance a capsule falls each tick */
+pub const CAPSULE_SIZE: f32 = 4.0;  /* width, height and depth of a capsule */
+
+/* what's in a capsule */
+#[derive(Clone, Copy, PartialEq)]
+pub enum Kind
+{
+  RapidFire, /* fire again without waiting for the last bullet to land */
+  Spread,    /* fire three bullets at once, fanning out */
+  Piercing,  /* bullets fly on through the aliens they hit */
+  Shield,    /* bombs and aliens bounce off the ship */
+  SlowTime,  /* the aliens, the mothership and their bombs move at half speed */
+  Upgrade    /* the ship's weapon is upgraded for good */
+}
+
+pub const KINDS: [Kind; 6] = [ Kind::RapidFire, Kind::Spread, Kind::Piercing, Kind::Shield, Kind::SlowTime, Kind::Upgrade ];
+
+impl Kind
+{
+  /* returns the power-up's name, as used in files */
+  pub fn name(&self) -> &'static str
+  {
+    match self
+    {
+      Kind::RapidFire => "rapid",
+      Kind::Spread => "spread",
+      Kind::Piercing => "piercing",
+      Kind::Shield => "shield",
+      Kind::SlowTime => "slow",
+      Kind::Upgrade => "upgrade"
+    }
+  }
+
+  /* returns the power-up with the given name, or None if there isn't one */
+  pub fn from_name(name: &str) -> Option<Kind>
+  {
+    KINDS.iter().find(|k| k.name() == name).cloned()
+  }
+
+  /* returns the power-up's name as shown on screen */
+  pub fn title(&self) -> &'static str
+  {
+    match self
+    {
+      Kind::RapidFire => "Rapid fire",
+      Kind::Spread => "Spread shot",
+      Kind::Piercing => "Piercing shot",
+      Kind::Shield => "Shield",
+      Kind::SlowTime => "Slow time",
+      Kind::Upgrade => "Weapon upgrade"
+    }
+  }
+
+  /* returns the (r, g, b) color of the power-up's capsule */
+  pub fn color(&self) -> (f32, f32, f32)
+  {
+    match self
+    {
+      Kind::RapidFire => (1.0, 0.5, 0.1),
+      Kind::Spread => (1.0, 1.0, 0.2),
+      Kind::Piercing => (0.2, 1.0, 1.0),
+      Kind::Shield => (0.3, 0.5, 1.0),
+      Kind::SlowTime => (0.9, 0.3, 1.0),
+      Kind::Upgrade => (1.0, 1.0, 1.0)
+    }
+  }
+
+  /* returns how many ticks the power-up lasts once picked up. upgrades last for good */
+  pub fn duration(&self) -> u32
+  {
+    match self
+    {
+      Kind::RapidFire => 10 * TICKS_PER_SECOND,
+      Kind::Spread => 10 * TICKS_PER_SECOND,
+      Kind::Piercing => 8 * TICKS_PER_SECOND,
+      Kind::Shield => 8 * TICKS_PER_SECOND,
+      Kind::SlowTime => 6 * TICKS_PER_SECOND,
+      Kind::Upgrade => 0
+    }
+  }
+
+  /* returns where the power-up sits in a list of every kind */
+  pub fn index(&self) -> usize
+  {
+    KINDS.iter().position(|k| k == self).unwrap_or(0)
+  }
+}
+
+/* a capsule on its way down */
+pub struct Capsule
+{
+  kind: Kind,
+  x: f32, y: f32
+}
+
+impl Capsule
+{
+  /* returns what's in the capsule */
+  pub fn get_kind(&self) -> Kind
+  {
+    self.kind
+  }
+
+  /* returns (x, y, z) coords of the capsule */
+  pub fn get_coords(&self) -> (f32, f32, f32)
+  {
+    (self.x, self.y, 0.0)
+  }
+}
+
+/* the capsules falling through a level, and the dice that decide when they drop */
+pub struct Powerups
+{
+  rng: Prng,                 /* decides which aliens drop capsules, and what's in them */
+  ticks: u32,                /* ticks the level has run for, to slow time by skipping every other one */
+  pub capsules: Vec<Capsule>
+}
+
+impl Powerups
+{
+  /* => seed = seed for the level's drops */
+  pub fn new(seed: u64) -> Powerups
+  {
+    Powerups
+    {
+      rng: Prng::new(seed),
+      ticks: 0,
+      capsules: Vec::new()
+    }
+  }
+
+  /* move the capsules along one tick, and throw away any the ships missed
+   * => slowed = true if a ship has slowed time down
+   * <= true if the aliens get to move this tick, or false if they sit it out */
+  pub fn animate(&mut self, slowed: bool) -> bool
+  {
+    self.ticks = self.ticks.wrapping_add(1);
+    for capsule in self.capsules.iter_mut()
+    {
+      capsule.y = capsule.y - CAPSULE_SPEED;
+    }
+    self.capsules.retain(|c| c.y >= hero::HERO_Y_FLOOR);
+
+    return slowed == false || self.ticks % 2 == 0;
+  }
+
+  /* maybe drop a capsule where an alien was shot down
+   * => x, y = where the alien was hit */
+  pub fn alien_down(&mut self, x: f32, y: f32)
+  {
+    /* roll the dice every time, so whether a capsule drops doesn't depend on how many
+     * are already falling */
+    let roll = self.rng.gen::<u32>();
+    if roll % DROP_CHANCE != 0 || self.capsules.len() >= MAX_CAPSULES
+    {
+      return;
+    }
+
+    let kind = KINDS[(roll / DROP_CHANCE) as usize % KINDS.len()];
+    self.capsules.push(Capsule { kind: kind, x: x, y: y });
+  }
+
+  /* give the ship any capsules it has flown into */
+  pub fn collect(&mut self, ship: &mut Hero)
+  {
+    let mut index = 0;
+    while index < self.capsules.len()
+    {
+      let (x, y, _) = self.capsules[index].get_coords();
+      match ship.collision(x, y)
+      {
+        collision::CollisionOutcome::Hit => ship.power_up(self.capsules.remove(index).kind),
+        collision::CollisionOutcome::Miss => index = index + 1
+      }
+    }
+  }
+
+  /* write the capsules to a saved game */
+  pub fn save(&self, writer: &mut save::Writer)
+  {
+    writer.label("powerups");
+    writer.value(self.rng.get_state());
+    writer.value(self.ticks);
+    writer.value(self.capsules.len());
+    for capsule in self.capsules.iter()
+    {
+      writer.label("capsule");
+      writer.value(capsule.kind.name());
+      writer.value(capsule.x);
+      writer.value(capsule.y);
+    }
+  }
+
+  /* recreate the capsules from a saved game */
+  pub fn restore(reader: &mut save::Reader) -> Result<Powerups, save::SaveError>
+  {
+    reader.label("powerups")?;
+    let rng = Prng::new(reader.value()?);
+    let ticks = reader.value()?;
+    let count: usize = reader.value()?;
+
+    let mut capsules = Vec::with_capacity(count.min(MAX_CAPSULES));
+    for _ in 0..count
+    {
+      reader.label("capsule")?;
+      let name = reader.value::<String>()?;
+      let kind = match Kind::from_name(name.as_str())
+      {
+        Some(kind) => kind,
+        None => return Err(reader.unexpected("power-up", name.as_str()))
+      };
+      capsules.push(Capsule { kind: kind, x: reader.value()?, y: reader.value()? });
+    }
+
+    Ok(Powerups
+    {
+      rng: rng,
+      ticks: ticks,
+      capsules: capsules
+    })
+  }
+}
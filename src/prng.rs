@@ -0,0 +1,73 @@
+/* Space invaders in Rust
+ *
+ * Seedable random number generator for the simulation
+ *
+ * Game concept by Tomohiro Nishikado / Taito
+ * Rust code By Chris Williams <diodesign@tuta.io>
+ *
+ * Written for fun. See LICENSE.
+ *
+ */
+
+extern crate rand;
+
+use rand::{ RngCore, Error };
+
+/* the game simulation must produce exactly the same results on every machine given the
+ * same seed and inputs, so that networked peers stay in lockstep. this is a splitmix64
+ * generator: its entire state is a single u64 so it's trivial to seed, copy and store,
+ * and its output doesn't depend on the version of the rand crate. it plugs into rand's
+ * RngCore so the usual gen() and gen_range() helpers work on it */
+#[derive(Clone)]
+pub struct Prng
+{
+  state: u64
+}
+
+impl Prng
+{
+  /* create a generator from the given seed */
+  pub fn new(seed: u64) -> Prng
+  {
+    Prng { state: seed }
+  }
+
+  /* return the generator's state. passing this to new() creates a generator that
+   * carries on from where this one is */
+  pub fn get_state(&self) -> u64
+  {
+    self.state
+  }
+}
+
+impl RngCore for Prng
+{
+  fn next_u64(&mut self) -> u64
+  {
+    self.state = self.state.wrapping_add(0x9e3779b97f4a7c15);
+    let mut z = self.state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    return z ^ (z >> 31);
+  }
+
+  fn next_u32(&mut self) -> u32
+  {
+    (self.next_u64() >> 32) as u32
+  }
+
+  fn fill_bytes(&mut self, dest: &mut [u8])
+  {
+    for chunk in dest.chunks_mut(8)
+    {
+      let bytes = self.next_u64().to_le_bytes();
+      chunk.copy_from_slice(&bytes[..chunk.len()]);
+    }
+  }
+
+  fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error>
+  {
+    self.fill_bytes(dest);
+    Ok(())
+  }
+}
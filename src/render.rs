@@ -0,0 +1,823 @@
+/* Space invaders in Rust
+ *
+ * Draw the playfield using kiss3d scene nodes
+ *
+ * Game concept by Tomohiro Nishikado / Taito
+ * Rust code By Chris Williams <diodesign@tuta.io>
+ *
+ * Written for fun. See LICENSE.
+ *
+ */
+
+extern crate glfw;
+extern crate kiss3d;
+extern crate nalgebra as na;
+extern crate rand;
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use na::{ Isometry3, Point3, Vector3, Translation3, UnitQuaternion };
+use kiss3d::window::Window;
+use kiss3d::scene::SceneNode;
+use kiss3d::resource::Mesh;
+
+use super::aliens;
+use super::boss;
+use super::hero;
+use super::bullet;
+use super::game;
+use super::particles::{ self, Particles };
+use super::pool::{ Pool, Shape };
+use super::powerup;
+use super::weapon;
+
+const HERO_GRAY: f32 = 0.8;
+const PARTNER_COLOR_R: f32 = 0.6; /* second ship in co-op mode is tinted blue */
+const PARTNER_COLOR_G: f32 = 0.7;
+const PARTNER_COLOR_B: f32 = 1.0;
+
+/* scene nodes created up front for each level and recycled, rather than added and removed as needed */
+const BULLET_POOL:   usize = 2 * hero::MAX_SHOTS; /* enough for both ships in co-op mode */
+const BEAM_POOL:     usize = 2 * hero::MAX_SHOTS;
+const BOMB_POOL:     usize = 1 + boss::MAX_BOMBS; /* the squadron's bomb and the mothership's */
+const CAPSULE_POOL:  usize = powerup::MAX_CAPSULES;
+
+const SHIELD_COLOR_R: f32 = 0.3;  /* a shielded ship flashes this color */
+const SHIELD_COLOR_G: f32 = 0.5;
+const SHIELD_COLOR_B: f32 = 1.0;
+const SHIELD_FLASH_TICKS: u32 = 8; /* ticks between flashes */
+
+const SHOT_TRACKING: f32 = 1.0;   /* how far a bullet can stray from where it was heading and still be the same bullet */
+const PARTICLE_POOL: usize = 800; /* enough for forty or so aliens blowing up at once, along with sparks and splashes */
+
+const MARKER_SIZE:    f32 = 3.0;
+const MARKER_COLOR_R: f32 = 1.0;
+const MARKER_COLOR_G: f32 = 1.0;
+const MARKER_COLOR_B: f32 = 0.0;
+const MARKER_GAP:     f32 = 8.0; /* distance below the column's lowest alien to draw the marker */
+
+/* ------------------------------------------------------------------------------ */
+
+/* add a box to a mesh being built. each face gets its own corners so the box is lit flat
+ * => coords, faces, normals = the mesh's vertices, triangles and vertex normals so far
+ *    center = middle of the box
+ *    size = width, height and depth of the box */
+fn add_box(coords: &mut Vec<Point3<f32>>, faces: &mut Vec<Point3<u16>>, normals: &mut Vec<Vector3<f32>>,
+           center: Vector3<f32>, size: Vector3<f32>)
+{
+  let half = size * 0.5;
+  for axis in 0..3
+  {
+    for &side in [ 1.0f32, -1.0f32 ].iter()
+    {
+      /* pick two edges across the face that wind anticlockwise when viewed from outside */
+      let mut normal = Vector3::zeros();
+      normal[axis] = side;
+      let (mut across, mut up) = (Vector3::zeros(), Vector3::zeros());
+      across[(axis + 1) % 3] = half[(axis + 1) % 3];
+      up[(axis + 2) % 3] = half[(axis + 2) % 3];
+      if side < 0.0
+      {
+        std::mem::swap(&mut across, &mut up);
+      }
+
+      let middle = center + normal.component_mul(&half);
+      let first = coords.len() as u16;
+      for corner in [ (-1.0, -1.0), (1.0, -1.0), (1.0, 1.0), (-1.0, 1.0) ].iter()
+      {
+        coords.push(Point3::from(middle + (across * corner.0) + (up * corner.1)));
+        normals.push(normal);
+      }
+      faces.push(Point3::new(first, first + 1, first + 2));
+      faces.push(Point3::new(first, first + 2, first + 3));
+    }
+  }
+}
+
+/* the parts of a pixel design drawn in one color, baked into a mesh for each animation frame */
+struct MeshPart
+{
+  color: (f32, f32, f32),
+  base: Rc<RefCell<Mesh>>,        /* the part with its pixels in their base positions */
+  translated: Rc<RefCell<Mesh>>   /* the part with its pixels in their translated positions */
+}
+
+/* bake a pixel design into meshes, one per color per animation frame */
+fn bake(design: &[aliens::Pixel]) -> Vec<MeshPart>
+{
+  let mut colors: Vec<(f32, f32, f32)> = Vec::new();
+  for pixel in design.iter()
+  {
+    if colors.contains(&(pixel.r, pixel.g, pixel.b)) == false
+    {
+      colors.push((pixel.r, pixel.g, pixel.b));
+    }
+  }
+
+  let bake_frame = |color: (f32, f32, f32), frame: aliens::Frame|
+  {
+    let (mut coords, mut faces, mut normals) = (Vec::new(), Vec::new(), Vec::new());
+    for pixel in design.iter().filter(|p| (p.r, p.g, p.b) == color)
+    {
+      add_box(&mut coords, &mut faces, &mut normals, pixel_position(pixel, frame),
+              Vector3::new(pixel.width, pixel.height, pixel.depth));
+    }
+    Rc::new(RefCell::new(Mesh::new(coords, faces, Some(normals), None, false)))
+  };
+
+  colors.iter().map(|color| MeshPart
+  {
+    color: *color,
+    base: bake_frame(*color, aliens::Frame::Base),
+    translated: bake_frame(*color, aliens::Frame::Translated)
+  }).collect()
+}
+
+/* each species' design baked into meshes. these are built once and shared by every alien,
+ * so drawing a living alien takes a couple of scene nodes rather than one per pixel */
+pub struct AlienMeshes
+{
+  parts: Vec<Vec<MeshPart>> /* the parts of each species, in the order of aliens::SPECIES */
+}
+
+impl AlienMeshes
+{
+  pub fn new() -> AlienMeshes
+  {
+    AlienMeshes
+    {
+      parts: aliens::SPECIES.iter().map(|species| bake(&aliens::design(*species))).collect()
+    }
+  }
+
+  /* returns the baked parts of the given species */
+  fn species(&self, species: aliens::Species) -> &Vec<MeshPart>
+  {
+    let index = aliens::SPECIES.iter().position(|s| *s == species).unwrap_or(0);
+    &self.parts[index]
+  }
+}
+
+/* returns where a pixel sits within its model in the given animation frame */
+fn pixel_position(pixel: &aliens::Pixel, frame: aliens::Frame) -> Vector3<f32>
+{
+  let base = Vector3::new(pixel.x, pixel.y, pixel.z);
+  match frame
+  {
+    aliens::Frame::Base => base,
+    aliens::Frame::Translated => base + Vector3::new(pixel.tx, pixel.ty, pixel.tz)
+  }
+}
+
+/* draw something built from pixels, such as an alien or a section of the mothership, using
+ * its baked meshes, switching to particle cubes for its pixels when it blows up */
+struct PixelModel
+{
+  model: SceneNode,               /* the scene node holding the meshes */
+  base: Vec<SceneNode>,           /* the meshes in their base frame */
+  translated: Vec<SceneNode>,     /* the meshes in their translated frame */
+  design: Vec<aliens::Pixel>,     /* the pixels to send flying when it blows up */
+  frame: aliens::Frame,           /* the animation frame on show */
+  placement: Isometry3<f32>,      /* where the model was last placed and how it was turned */
+  exploding: bool                 /* true once the pixels have been sent flying */
+}
+
+impl PixelModel
+{
+  /* => parent = scene node to attach the model to
+   *    parts = the model's design, baked into meshes
+   *    design = the model's design, pixel by pixel */
+  fn new(parent: &mut SceneNode, parts: &[MeshPart], design: Vec<aliens::Pixel>) -> PixelModel
+  {
+    let mut model = PixelModel
+    {
+      /* attach the meshes together as a group */
+      model: parent.add_group(),
+      base: Vec::new(),
+      translated: Vec::new(),
+      design: design,
+      frame: aliens::Frame::Base,
+      placement: Isometry3::identity(),
+      exploding: false
+    };
+
+    let scale = Vector3::new(1.0, 1.0, 1.0);
+    for part in parts.iter()
+    {
+      let (r, g, b) = part.color;
+      let mut base = model.model.add_mesh(part.base.clone(), scale);
+      let mut translated = model.model.add_mesh(part.translated.clone(), scale);
+      base.set_color(r, g, b);
+      translated.set_color(r, g, b);
+      translated.set_visible(false);
+      model.base.push(base);
+      model.translated.push(translated);
+    }
+
+    return model;
+  }
+
+  /* move the whole model into position, rotated about the y-axis by the given angle */
+  fn place(&mut self, x: f32, y: f32, z: f32, angle: f32)
+  {
+    self.placement = Isometry3::from_parts(Translation3::new(x, y, z), UnitQuaternion::from_axis_angle(&Vector3::y_axis(), angle));
+    self.model.set_local_translation(self.placement.translation);
+    self.model.set_local_rotation(self.placement.rotation);
+  }
+
+  /* swap meshes to show the given animation frame */
+  fn show_frame(&mut self, frame: aliens::Frame)
+  {
+    if frame != self.frame
+    {
+      self.frame = frame;
+      let translated = self.frame == aliens::Frame::Translated;
+      for node in self.base.iter_mut()
+      {
+        node.set_visible(translated == false);
+      }
+      for node in self.translated.iter_mut()
+      {
+        node.set_visible(translated);
+      }
+    }
+  }
+
+  /* blow up the model by hiding its meshes and sending a particle cube flying from where
+   * each of its pixels was. the model's parent mustn't be moved, as the particles are
+   * placed in the game world. does nothing if it's already exploding */
+  fn explode(&mut self, particles: &mut Particles)
+  {
+    if self.exploding == true
+    {
+      return;
+    }
+    self.exploding = true;
+
+    for node in self.base.iter_mut().chain(self.translated.iter_mut())
+    {
+      node.set_visible(false);
+    }
+
+    let pieces: Vec<(Vector3<f32>, Vector3<f32>)> = self.design.iter().map(|pixel|
+    {
+      let start = self.placement * Point3::from(pixel_position(pixel, self.frame));
+      (start.coords, Vector3::new(pixel.width, pixel.height, pixel.depth))
+    }).collect();
+    particles.shatter(&particles::ALIEN_EXPLOSION, &pieces);
+  }
+
+  /* hide the whole model, once it's gone for good */
+  fn hide(&mut self)
+  {
+    self.model.set_visible(false);
+  }
+
+  /* returns the number of the model's scene nodes on show: its group and the meshes of
+   * the frame being shown, or just the group once it's blown up, or none once it's hidden */
+  fn node_count(&self) -> usize
+  {
+    match self.model.is_visible()
+    {
+      true => 1 + self.base.iter().chain(self.translated.iter()).filter(|node| node.is_visible() == true).count(),
+      false => 0
+    }
+  }
+
+  /* set the whole model to a particular color */
+  fn override_color(&mut self, r: f32, g: f32, b: f32)
+  {
+    for node in self.base.iter_mut().chain(self.translated.iter_mut())
+    {
+      node.set_color(r, g, b);
+    }
+  }
+
+  /* remove all objects (pixels) from the game world */
+  fn delete(&mut self)
+  {
+    self.model.unlink();
+  }
+}
+
+/* draw an alien using its species' baked meshes, blowing it up when it dies */
+pub struct AlienModel
+{
+  pixels: PixelModel
+}
+
+impl AlienModel
+{
+  /* create the alien in the game world
+   * => window = graphics context
+   *    meshes = the aliens' designs, baked into meshes
+   *    species = which of the designs to draw the alien with */
+  pub fn new(window: &mut Window, meshes: &AlienMeshes, species: aliens::Species) -> AlienModel
+  {
+    AlienModel::attach(window.scene_mut(), meshes, species)
+  }
+
+  /* like new(), but attach the alien to the given scene node */
+  fn attach(parent: &mut SceneNode, meshes: &AlienMeshes, species: aliens::Species) -> AlienModel
+  {
+    AlienModel { pixels: PixelModel::new(parent, meshes.species(species), aliens::design(species)) }
+  }
+
+  /* bring the model in line with the alien's game state
+   * => alien = the alien to draw
+   *    particles = particle system to blow the alien up with */
+  pub fn update(&mut self, alien: &aliens::Alien, particles: &mut Particles)
+  {
+    match alien.get_state()
+    {
+      aliens::State::Alive =>
+      {
+        /* move the whole model into position and rotate it as required */
+        let (x, y, z) = alien.get_coords();
+        self.pixels.place(x, y, z, alien.get_angle());
+        self.pixels.show_frame(alien.get_frame());
+      },
+
+      aliens::State::Dying => self.pixels.explode(particles),
+
+      aliens::State::Dead => self.pixels.hide()
+    }
+  }
+
+  /* returns the number of the alien's scene nodes on show */
+  pub fn node_count(&self) -> usize
+  {
+    self.pixels.node_count()
+  }
+
+  /* override_color
+     Set the whole alien to a particular color
+     => r, g, b = new RGB color of the alien
+  */
+  pub fn override_color(&mut self, r: f32, g: f32, b: f32)
+  {
+    self.pixels.override_color(r, g, b);
+  }
+
+  /* remove all objects (pixels) from the game world */
+  pub fn delete(&mut self)
+  {
+    self.pixels.delete();
+  }
+}
+
+/* ------------------------------------------------------------------------------ */
+
+/* draw the mothership a section at a time, each blowing up when it's shot away, along
+ * with the escorts it launches */
+pub struct BossModel
+{
+  group: SceneNode,              /* holds the sections and escorts */
+  sections: Vec<PixelModel>,     /* in the same order as the mothership's sections */
+  escorts: Vec<AlienModel>,      /* one for each escort launched so far */
+  escort_meshes: AlienMeshes     /* the escorts' designs, baked into meshes */
+}
+
+impl BossModel
+{
+  /* create the mothership in the game world
+   * => window = graphics context
+   *    boss = the mothership to draw */
+  pub fn new(window: &mut Window, boss: &boss::Mothership) -> BossModel
+  {
+    let mut group = window.add_group();
+    let sections = boss.get_sections().iter().map(|section|
+    {
+      let design = section.get_part().design();
+      PixelModel::new(&mut group, &bake(&design), design)
+    }).collect();
+
+    BossModel
+    {
+      group: group,
+      sections: sections,
+      escorts: Vec::new(),
+      escort_meshes: AlienMeshes::new()
+    }
+  }
+
+  /* bring the model in line with the mothership's game state
+   * => boss = the mothership to draw
+   *    particles = particle system to blow its sections and escorts up with */
+  pub fn update(&mut self, boss: &boss::Mothership, particles: &mut Particles)
+  {
+    for (model, section) in self.sections.iter_mut().zip(boss.get_sections().iter())
+    {
+      match section.get_state()
+      {
+        aliens::State::Alive =>
+        {
+          let (x, y, z) = boss.section_coords(section);
+          model.place(x, y, z, 0.0);
+          model.show_frame(boss.get_frame());
+        },
+
+        aliens::State::Dying => model.explode(particles),
+
+        aliens::State::Dead => model.hide()
+      }
+    }
+
+    /* escorts are never removed, so there's a model for each one launched so far */
+    while self.escorts.len() < boss.escorts.len()
+    {
+      let species = boss.escorts[self.escorts.len()].get_species();
+      self.escorts.push(AlienModel::attach(&mut self.group, &self.escort_meshes, species));
+    }
+    for (model, escort) in self.escorts.iter_mut().zip(boss.escorts.iter())
+    {
+      model.update(escort, particles);
+    }
+  }
+
+  /* returns the number of scene nodes on show for the mothership and its escorts */
+  pub fn node_count(&self) -> usize
+  {
+    1 + self.sections.iter().map(|s| s.node_count()).sum::<usize>() +
+    self.escorts.iter().map(|e| e.node_count()).sum::<usize>()
+  }
+
+  /* remove the mothership and its escorts from the game world */
+  pub fn delete(&mut self)
+  {
+    self.group.unlink();
+  }
+}
+
+/* ------------------------------------------------------------------------------ */
+
+/* draw the hero's ship as a cone, and a shower of debris when it blows up */
+pub struct HeroModel
+{
+  ship: SceneNode,                   /* the ship in the graphics context */
+  color: (f32, f32, f32),            /* the ship's own color, when it's not flashing with a shield */
+  exploding: bool                    /* true once the debris has been sent flying */
+}
+
+impl HeroModel
+{
+  /* create the ship model with the given color */
+  pub fn new(window: &mut Window, r: f32, g: f32, b: f32) -> HeroModel
+  {
+    let mut model = HeroModel
+    {
+      ship: window.add_cone(hero::HERO_RADIUS, hero::HERO_HEIGHT),
+      color: (r, g, b),
+      exploding: false
+    };
+
+    model.ship.set_color(r, g, b);
+    return model;
+  }
+
+  /* bring the model in line with the hero's game state
+   * => player = the ship to draw
+   *    particles = particle system to blow the ship up with */
+  pub fn update(&mut self, player: &hero::Hero, particles: &mut Particles)
+  {
+    let (x, y, z) = player.get_coords();
+    self.ship.set_local_translation(Translation3::new(x, y, z));
+
+    /* if the ship is blowing up then keep it hidden, otherwise visible */
+    match player.state
+    {
+      hero::State::Dying =>
+      {
+        self.ship.set_visible(false);
+        if self.exploding == false
+        {
+          particles.burst(&particles::HERO_EXPLOSION, x, y, z);
+          self.exploding = true;
+        }
+      },
+
+      _ =>
+      {
+        self.ship.set_visible(true);
+        self.exploding = false;
+      }
+    }
+
+    /* flash while the ship has a shield */
+    let shield = player.powers().iter().find(|(kind, _)| *kind == powerup::Kind::Shield).map(|(_, ticks)| *ticks);
+    let (r, g, b) = match shield
+    {
+      Some(ticks) if (ticks / SHIELD_FLASH_TICKS) % 2 == 0 => (SHIELD_COLOR_R, SHIELD_COLOR_G, SHIELD_COLOR_B),
+      _ => self.color
+    };
+    self.ship.set_color(r, g, b);
+  }
+
+  /* make sure everything is removed from the game world */
+  pub fn delete(&mut self)
+  {
+    self.ship.unlink();
+  }
+}
+
+/* ------------------------------------------------------------------------------ */
+
+/* where to draw a projectile: its (x, y, z) coords, (width, height, depth) size and (r, g, b) color */
+type Placement = ((f32, f32, f32), (f32, f32, f32), (f32, f32, f32));
+
+/* draw a set of bullets, bombs or capsules, taken from a pool */
+struct Projectiles
+{
+  nodes: Vec<SceneNode>,
+  pool: Pool
+}
+
+impl Projectiles
+{
+  /* => window = graphics context
+   *    name = what the projectiles are, for reporting if the pool runs dry
+   *    shape = shape to draw each projectile
+   *    capacity = most projectiles that can be drawn at once */
+  fn new(window: &mut Window, name: &'static str, shape: Shape, capacity: usize) -> Projectiles
+  {
+    Projectiles { nodes: Vec::new(), pool: Pool::new(window.scene_mut(), name, shape, capacity) }
+  }
+
+  /* take, move, and give back spheres so there's one per bullet or bomb in play */
+  fn update(&mut self, shells: &[&bullet::Bullet])
+  {
+    let places: Vec<Placement> = shells.iter()
+                 .map(|s| { let size = s.get_radius() * 2.0; (s.get_coords(), (size, size, size), s.get_color()) })
+                 .collect();
+    self.place(&places);
+  }
+
+  /* take, move, and give back nodes so there's one per projectile in play
+   * => places = where to draw each projectile */
+  fn place(&mut self, places: &[Placement])
+  {
+    for (index, place) in places.iter().enumerate()
+    {
+      if index == self.nodes.len()
+      {
+        match self.pool.take()
+        {
+          Some(node) => self.nodes.push(node),
+          None => break /* pool has run dry, so the rest go undrawn */
+        }
+      }
+
+      /* nodes can move from one projectile to another, eg: from a bomb to one dropped
+       * by the mothership, so set them up afresh each time */
+      let ((x, y, z), (width, height, depth), (r, g, b)) = *place;
+      let node = &mut self.nodes[index];
+      node.set_color(r, g, b);
+      node.set_local_scale(width, height, depth);
+      node.set_local_translation(Translation3::new(x, y, z));
+    }
+
+    while self.nodes.len() > places.len()
+    {
+      self.pool.give(self.nodes.pop().unwrap());
+    }
+  }
+
+  /* returns the number of scene nodes taken from the pool to draw projectiles */
+  fn node_count(&self) -> usize
+  {
+    self.pool.in_use()
+  }
+
+  /* remove all the projectiles from the game world */
+  fn delete(&mut self)
+  {
+    while let Some(node) = self.nodes.pop()
+    {
+      self.pool.give(node);
+    }
+    self.pool.delete();
+  }
+}
+
+/* ------------------------------------------------------------------------------ */
+
+/* everything drawn for a playfield */
+pub struct Scene
+{
+  aliens: Vec<AlienModel>,
+  boss: Option<BossModel>,   /* the mothership, on levels with a boss fight */
+  heroes: Vec<HeroModel>,
+  bullets: Projectiles,
+  beams: Projectiles,        /* laser beams fired by the ships */
+  bombs: Projectiles,
+  capsules: Projectiles,     /* power-ups dropped by aliens */
+  marker: Option<SceneNode>, /* shows the column selected by the alien player in versus mode */
+  particles: Particles,      /* explosions, debris, impacts and splashes */
+  shots: Vec<(f32, f32, f32)>,         /* where the ships' bullets will be next tick */
+  bomb: Option<(f32, f32, f32)>        /* where the squadron's bomb will be next tick, if it has one */
+}
+
+/* check which of the ships' bullets have vanished since the last tick. bullets come and
+ * go in any order, so each is matched up with one that was heading to where it is now
+ * => next = where the bullets were heading, updated to where they're heading now
+ *    shells = the bullets now
+ * <= where each of the bullets that vanished was */
+fn vanished_shots(next: &mut Vec<(f32, f32, f32)>, shells: &[&bullet::Bullet]) -> Vec<(f32, f32, f32)>
+{
+  let mut gone = std::mem::take(next);
+  for shell in shells.iter()
+  {
+    let (x, y, z) = shell.get_coords();
+    if let Some(index) = gone.iter().position(|g| (g.0 - x).abs() < SHOT_TRACKING && (g.1 - y).abs() < SHOT_TRACKING)
+    {
+      gone.remove(index);
+    }
+    next.push((x, y + shell.get_speed(), z));
+  }
+  return gone;
+}
+
+/* check whether a projectile has vanished since the last tick
+ * => next = where the projectile was heading, updated to where it's heading now
+ *    shell = the projectile now, if there is one
+ * <= where the projectile vanished, or None if it's still in play or there wasn't one */
+fn vanished(next: &mut Option<(f32, f32, f32)>, shell: Option<&bullet::Bullet>) -> Option<(f32, f32, f32)>
+{
+  let previous = *next;
+  *next = shell.map(|s|
+  {
+    let (x, y, z) = s.get_coords();
+    (x, y + s.get_speed(), z)
+  });
+
+  match shell
+  {
+    None => previous,
+    Some(_) => None
+  }
+}
+
+impl Scene
+{
+  /* create scene nodes for everything in the playfield */
+  pub fn new(window: &mut Window, playfield: &game::Playfield) -> Scene
+  {
+    let mut scene = Scene
+    {
+      aliens: Vec::new(),
+      boss: None,
+      heroes: Vec::new(),
+      bullets: Projectiles::new(window, "bullets", Shape::Sphere, BULLET_POOL),
+      beams: Projectiles::new(window, "beams", Shape::Cube, BEAM_POOL),
+      bombs: Projectiles::new(window, "bombs", Shape::Sphere, BOMB_POOL),
+      capsules: Projectiles::new(window, "capsules", Shape::Cube, CAPSULE_POOL),
+      marker: None,
+      particles: Particles::new(window.scene_mut(), PARTICLE_POOL),
+      shots: Vec::new(),
+      bomb: None
+    };
+
+    let meshes = AlienMeshes::new();
+    for baddie in playfield.aliens.get_squadron().iter()
+    {
+      scene.aliens.push(AlienModel::new(window, &meshes, baddie.get_species()));
+    }
+    scene.boss = playfield.boss.as_ref().map(|boss| BossModel::new(window, boss));
+
+    for index in 0..playfield.heroes.len()
+    {
+      scene.heroes.push(match index
+      {
+        0 => HeroModel::new(window, HERO_GRAY, HERO_GRAY, HERO_GRAY),
+        _ => HeroModel::new(window, PARTNER_COLOR_R, PARTNER_COLOR_G, PARTNER_COLOR_B)
+      });
+    }
+
+    if playfield.commander.is_some() == true
+    {
+      let mut marker = window.add_cube(MARKER_SIZE, MARKER_SIZE, MARKER_SIZE);
+      marker.set_color(MARKER_COLOR_R, MARKER_COLOR_G, MARKER_COLOR_B);
+      scene.marker = Some(marker);
+    }
+
+    scene.update(playfield);
+    return scene;
+  }
+
+  /* bring the scene in line with the playfield */
+  pub fn update(&mut self, playfield: &game::Playfield)
+  {
+    self.particles.update();
+
+    for (model, baddie) in self.aliens.iter_mut().zip(playfield.aliens.get_squadron().iter())
+    {
+      model.update(baddie, &mut self.particles);
+    }
+    if let (Some(model), Some(boss)) = (self.boss.as_mut(), playfield.boss.as_ref())
+    {
+      model.update(boss, &mut self.particles);
+    }
+
+    for (model, player) in self.heroes.iter_mut().zip(playfield.heroes.iter())
+    {
+      model.update(player, &mut self.particles);
+    }
+
+    /* throw up sparks where a bullet hit an alien, rather than flew off the top of the
+     * screen, and a splash wherever a bomb burst */
+    let bullets = playfield.bullets();
+    for (x, y, z) in vanished_shots(&mut self.shots, &bullets)
+    {
+      if y <= aliens::ALIEN_Y_CEILING
+      {
+        self.particles.burst(&particles::BULLET_IMPACT, x, y, z);
+      }
+    }
+
+    if let Some((x, y, z)) = vanished(&mut self.bomb, playfield.aliens.bomb.as_ref())
+    {
+      self.particles.burst(&particles::BOMB_SPLASH, x, y, z);
+    }
+
+    /* beams trail down from the tip of the shot */
+    let shots: Vec<&weapon::Shot> = playfield.heroes.iter().flat_map(|p| p.shots.iter()).collect();
+    let balls: Vec<&bullet::Bullet> = shots.iter().filter(|s| s.look == weapon::Look::Ball).map(|s| &s.bullet).collect();
+    let beams: Vec<Placement> = shots.iter()
+                .filter(|s| s.look == weapon::Look::Beam)
+                .map(|s|
+                {
+                  let (x, y, z) = s.bullet.get_coords();
+                  let width = s.bullet.get_radius() * 2.0;
+                  ((x, y - (s.get_length() / 2.0), z), (width, s.get_length(), width), s.bullet.get_color())
+                })
+                .collect();
+    self.bullets.update(&balls);
+    self.beams.place(&beams);
+    self.bombs.update(&playfield.bombs());
+
+    let size = powerup::CAPSULE_SIZE;
+    let capsules: Vec<Placement> = playfield.powerups.capsules.iter()
+                   .map(|c| (c.get_coords(), (size, size, size), c.get_kind().color()))
+                   .collect();
+    self.capsules.place(&capsules);
+
+    /* keep the marker under the selected column */
+    if let (Some(marker), Some(commander)) = (self.marker.as_mut(), playfield.commander.as_ref())
+    {
+      match playfield.aliens.column_coords(commander.get_column())
+      {
+        Some((x, y)) =>
+        {
+          marker.set_visible(true);
+          marker.set_local_translation(Translation3::new(x, y - MARKER_GAP, 0.0));
+        },
+        None => marker.set_visible(false)
+      }
+    }
+  }
+
+  /* returns the number of scene nodes on show for the playfield. hidden nodes, such as
+   * those waiting in pools or making up aliens that are gone, aren't counted */
+  pub fn node_count(&self) -> usize
+  {
+    self.aliens.iter().map(|a| a.node_count()).sum::<usize>() +
+    self.boss.as_ref().map_or(0, |b| b.node_count()) +
+    self.heroes.iter().filter(|h| h.ship.is_visible() == true).count() +
+    self.bullets.node_count() + self.beams.node_count() + self.bombs.node_count() + self.capsules.node_count() +
+    self.particles.nodes_in_use() +
+    self.marker.iter().filter(|m| m.is_visible() == true).count()
+  }
+
+  /* returns the number of particles in flight */
+  pub fn particle_count(&self) -> usize
+  {
+    self.particles.count()
+  }
+
+  /* remove all objects from the game world */
+  pub fn delete(&mut self)
+  {
+    for model in self.aliens.iter_mut()
+    {
+      model.delete();
+    }
+    if let Some(model) = self.boss.as_mut()
+    {
+      model.delete();
+    }
+    for model in self.heroes.iter_mut()
+    {
+      model.delete();
+    }
+    self.bullets.delete();
+    self.beams.delete();
+    self.capsules.delete();
+    self.bombs.delete();
+    self.particles.delete();
+    if let Some(marker) = self.marker.as_mut()
+    {
+      marker.unlink();
+    }
+  }
+}
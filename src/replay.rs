@@ -0,0 +1,125 @@
+/* Space invaders in Rust
+ *
+ * Record the controls of a game and play them back
+ *
+ * Game concept by Tomohiro Nishikado / Taito
+ * Rust code By Chris Williams <diodesign@tuta.io>
+ *
+ * Written for fun. See LICENSE.
+ *
+ */
+
+/* the simulation is deterministic, so a game can be replayed exactly from how it started
+ * and what the players held down on each tick. a replay file is laid out like a saved
+ * game: a version line, the game as it was at the start, and then the controls for each
+ * tick, packed into one number per tick: player one's buttons in the low four bits and
+ * player two's in the next four, then player one's stick in the next byte and player
+ * two's in the byte after that */
+
+use std::fs;
+
+use super::difficulty::Difficulty;
+use super::game::Game;
+use super::input::Controls;
+use super::save::{ self, SaveError };
+use super::stats::Stats;
+
+const REPLAY_VERSION: u32 = 5; /* version 2 added the difficulty, version 3 the march, version 4 the weapon upgrade
+                               * power-up, and version 5 sticks and inertia */
+const OLDEST_REPLAY: u32 = 4;  /* older recordings roll different power-ups, so they'd play out a different game */
+const REPLAY_MAGIC: &str = "rustinvaders-replay"; /* label of the first line of the file */
+
+/* the controls of a game, being recorded or played back */
+pub struct Tape
+{
+  start: String,   /* the game as it was at the start, written out as in a saved game */
+  ticks: Vec<u32>, /* each tick's controls */
+  position: usize  /* next tick to play back */
+}
+
+impl Tape
+{
+  /* start recording a game
+   * => state = the game, before its first tick */
+  pub fn new(state: &Game) -> Tape
+  {
+    let mut writer = save::Writer::new();
+    state.save(&mut writer);
+    state.stats.save(&mut writer);
+    state.difficulty.save(&mut writer);
+    Tape { start: String::from(writer.text()), ticks: Vec::new(), position: 0 }
+  }
+
+  /* add a tick's controls to the recording */
+  pub fn record(&mut self, controls: &[Controls])
+  {
+    let bits = controls.iter().take(2).enumerate().fold(0, |bits, (player, c)|
+    {
+      let packed = c.to_bits() as u32;
+      bits | ((packed & 0xf) << (player * 4)) | ((packed >> 8) << (8 + (player * 8)))
+    });
+    self.ticks.push(bits);
+  }
+
+  /* returns the next tick's controls, or None if the recording has run out
+   * => players = number of players to return controls for */
+  pub fn play(&mut self, players: usize) -> Option<Vec<Controls>>
+  {
+    let bits = *self.ticks.get(self.position)?;
+    self.position = self.position + 1;
+    Some((0..players.min(2)).map(|player|
+    {
+      let buttons = (bits >> (player * 4)) & 0xf;
+      let stick = (bits >> (8 + (player * 8))) & 0xff;
+      Controls::from_bits((buttons | (stick << 8)) as u16)
+    }).collect())
+  }
+
+  /* write the recording to the given file, replacing whatever was there */
+  pub fn save(&self, path: &str) -> Result<(), SaveError>
+  {
+    let mut writer = save::Writer::new();
+    writer.label(REPLAY_MAGIC);
+    writer.value(REPLAY_VERSION);
+    let mut text = format!("{}\n{}\nticks {}\n", writer.text(), self.start, self.ticks.len());
+    for line in self.ticks.chunks(32)
+    {
+      let words: Vec<String> = line.iter().map(|bits| bits.to_string()).collect();
+      text.push_str(words.join(" ").as_str());
+      text.push('\n');
+    }
+
+    fs::write(path, text.as_bytes())?;
+    return Ok(());
+  }
+
+  /* read a recording from the given file
+   * <= the game as it was at the start, and the recording ready to play back */
+  pub fn load(path: &str) -> Result<(Game, Tape), SaveError>
+  {
+    let text = fs::read_to_string(path)?;
+    let mut reader = save::Reader::new(text.as_str());
+
+    reader.label(REPLAY_MAGIC)?;
+    match reader.value::<u32>()?
+    {
+      OLDEST_REPLAY ..= REPLAY_VERSION => {},
+      version => return Err(SaveError::Version(version))
+    }
+
+    let mut state = Game::restore(&mut reader)?;
+    state.stats = Stats::restore(&mut reader)?;
+    state.difficulty = Difficulty::restore(&mut reader)?;
+    let tape = Tape::new(&state);
+
+    reader.label("ticks")?;
+    let count: usize = reader.value()?;
+    let mut ticks = Vec::with_capacity(count);
+    for _ in 0..count
+    {
+      ticks.push(reader.value()?);
+    }
+
+    Ok((state, Tape { ticks: ticks, ..tape }))
+  }
+}
@@ -0,0 +1,409 @@
+/* Space invaders in Rust
+ *
+ * Save a game in progress and continue it later
+ *
+ * Game concept by Tomohiro Nishikado / Taito
+ * Rust code By Chris Williams <diodesign@tuta.io>
+ *
+ * Written for fun. See LICENSE.
+ *
+ */
+
+/* a saved game is a small text file, one object per line: a label saying what the line
+ * describes, followed by its values separated by spaces. missing things, such as a bullet
+ * that hasn't been fired, are written as a dash. numbers are written so they read back
+ * exactly, so a continued game carries on precisely where it left off. the first line
+ * holds the file's version number. bump SAVE_VERSION whenever the layout changes, and
+ * teach load() how to read the old layout if it's worth keeping */
+
+use std::fmt;
+use std::fs;
+use std::io;
+use std::iter::Peekable;
+use std::str::{ FromStr, SplitWhitespace };
+
+use super::difficulty::Difficulty;
+use super::game::{ Game, Playfield };
+use super::stats::Stats;
+
+pub const SAVE_VERSION: u32 = 10;     /* version 2 added game stats, version 3 the difficulty, version 4 the march,
+                                      * version 5 the species of each alien, version 6 the mothership,
+                                      * version 7 power-ups, version 8 weapons and the weapon upgrade power-up,
+                                      * version 9 the ship's inertia and velocity, and version 10 kills by species */
+const SAVE_FILE: &str  = "rustinvaders.sav";
+const SAVE_MAGIC: &str = "rustinvaders-save"; /* label of the first line of the file */
+
+/* things that can go wrong saving or loading */
+pub enum SaveError
+{
+  Io(io::Error),   /* couldn't read or write the file */
+  Version(u32),    /* saved by a version of the game we can't read */
+  Corrupt(String)  /* file isn't laid out as expected */
+}
+
+impl fmt::Display for SaveError
+{
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
+  {
+    match self
+    {
+      SaveError::Io(e) => write!(f, "{}", e),
+      SaveError::Version(version) => match *version > SAVE_VERSION
+      {
+        true => write!(f, "saved by a newer version of the game"),
+        false => write!(f, "saved by an old version of the game that's no longer supported")
+      },
+      SaveError::Corrupt(reason) => write!(f, "save file is damaged: {}", reason)
+    }
+  }
+}
+
+impl From<io::Error> for SaveError
+{
+  fn from(e: io::Error) -> SaveError
+  {
+    SaveError::Io(e)
+  }
+}
+
+/* builds up the text of a save file */
+pub struct Writer
+{
+  text: String
+}
+
+impl Writer
+{
+  pub fn new() -> Writer
+  {
+    Writer { text: String::new() }
+  }
+
+  /* returns everything written so far */
+  pub fn text(&self) -> &str
+  {
+    self.text.as_str()
+  }
+
+  /* start a new line describing the given thing */
+  pub fn label(&mut self, label: &str)
+  {
+    if self.text.is_empty() == false
+    {
+      self.text.push('\n');
+    }
+    self.text.push_str(label);
+  }
+
+  /* add a value to the current line */
+  pub fn value<T: fmt::Display>(&mut self, value: T)
+  {
+    self.text.push(' ');
+    self.text.push_str(&value.to_string());
+  }
+
+  /* add a value that might be missing to the current line */
+  pub fn option<T: fmt::Display>(&mut self, value: Option<T>)
+  {
+    match value
+    {
+      Some(value) => self.value(value),
+      None => self.none()
+    }
+  }
+
+  /* mark something on the current line as missing */
+  pub fn none(&mut self)
+  {
+    self.value("-");
+  }
+}
+
+/* picks apart the text of a save file */
+pub struct Reader<'a>
+{
+  words: Peekable<SplitWhitespace<'a>>,
+  version: u32  /* version of the file being read, for layouts that can't be told apart by their labels */
+}
+
+impl<'a> Reader<'a>
+{
+  pub fn new(text: &'a str) -> Reader<'a>
+  {
+    Reader { words: text.split_whitespace().peekable(), version: SAVE_VERSION }
+  }
+
+  /* returns the version of the save file being read */
+  pub fn version(&self) -> u32
+  {
+    self.version
+  }
+
+  /* check the next thing in the file is the given label */
+  pub fn label(&mut self, label: &str) -> Result<(), SaveError>
+  {
+    match self.words.next()
+    {
+      Some(word) if word == label => Ok(()),
+      Some(word) => Err(SaveError::Corrupt(format!("expected {} but found {}", label, word))),
+      None => Err(SaveError::Corrupt(format!("expected {} but the file ended", label)))
+    }
+  }
+
+  /* read the next value */
+  pub fn value<T: FromStr>(&mut self) -> Result<T, SaveError>
+  {
+    match self.words.next()
+    {
+      Some(word) => word.parse::<T>().map_err(|_| SaveError::Corrupt(format!("bad value {}", word))),
+      None => Err(SaveError::Corrupt(String::from("file ended too soon")))
+    }
+  }
+
+  /* read the next value, which might be missing */
+  pub fn option<T: FromStr>(&mut self) -> Result<Option<T>, SaveError>
+  {
+    match self.none()
+    {
+      true => Ok(None),
+      false => self.value().map(Some)
+    }
+  }
+
+  /* check whether the next thing is the given label, without reading past it. this lets
+   * lines added to the layout later be left out of older files */
+  pub fn next_is(&mut self, label: &str) -> bool
+  {
+    self.words.peek() == Some(&label)
+  }
+
+  /* check whether the next thing is marked as missing, and if so, skip over it */
+  pub fn none(&mut self) -> bool
+  {
+    if self.words.peek() == Some(&"-")
+    {
+      self.words.next();
+      return true;
+    }
+    return false;
+  }
+
+  /* return a corruption error for the given unexpected value */
+  pub fn unexpected(&self, what: &str, value: &str) -> SaveError
+  {
+    SaveError::Corrupt(format!("unknown {} {}", what, value))
+  }
+}
+
+/* ------------------------------------------------------------------------------ */
+
+/* save the game and its playfield, replacing any earlier save */
+pub fn save(state: &Game, playfield: &Playfield) -> Result<(), SaveError>
+{
+  /* write to a temporary file first so a failed save doesn't wreck the last good one */
+  let temporary = format!("{}.new", SAVE_FILE);
+  fs::write(&temporary, encode(state, playfield).as_bytes())?;
+  fs::rename(&temporary, SAVE_FILE)?;
+  return Ok(());
+}
+
+/* load the saved game and its playfield */
+pub fn load() -> Result<(Game, Playfield), SaveError>
+{
+  let text = fs::read_to_string(SAVE_FILE)?;
+  return decode(text.as_str());
+}
+
+/* returns the text of a save file holding the game and its playfield */
+fn encode(state: &Game, playfield: &Playfield) -> String
+{
+  let mut writer = Writer::new();
+  writer.label(SAVE_MAGIC);
+  writer.value(SAVE_VERSION);
+  state.save(&mut writer);
+  state.stats.save(&mut writer);
+  state.difficulty.save(&mut writer);
+  playfield.save(&mut writer);
+  writer.text.push('\n');
+  return writer.text;
+}
+
+/* recreate a game and its playfield from the text of a save file, of this version or an older one */
+fn decode(text: &str) -> Result<(Game, Playfield), SaveError>
+{
+  let mut reader = Reader::new(text);
+
+  reader.label(SAVE_MAGIC)?;
+  match reader.value::<u32>()?
+  {
+    version @ 1 ..= SAVE_VERSION =>
+    {
+      reader.version = version;
+      let mut state = Game::restore(&mut reader)?;
+
+      /* version 1 saves have no stats: count from the saved level onwards, with
+       * no time recorded for the levels before it */
+      state.stats = match version
+      {
+        1 =>
+        {
+          let mut stats = Stats::new();
+          stats.level_ticks = vec![ 0; state.level.max(0) as usize ];
+          stats
+        },
+        _ => Stats::restore(&mut reader)?
+      };
+
+      /* games saved before there was a choice of difficulty were played on normal */
+      if version >= 3
+      {
+        state.difficulty = Difficulty::restore(&mut reader)?;
+      }
+
+      let playfield = Playfield::restore(&mut reader, &state)?;
+      Ok((state, playfield))
+    },
+
+    version => Err(SaveError::Version(version))
+  }
+}
+
+/* return true if there's a saved game to continue */
+pub fn exists() -> bool
+{
+  fs::metadata(SAVE_FILE).is_ok()
+}
+
+/* throw away the saved game, eg: once it's been continued */
+pub fn discard()
+{
+  let _ = fs::remove_file(SAVE_FILE);
+}
+
+/* ------------------------------------------------------------------------------ */
+
+#[cfg(test)]
+mod tests
+{
+  use super::*;
+  use super::super::difficulty::Profile;
+  use super::super::game::GameMode;
+  use super::super::input::Controls;
+  use super::super::powerup::Kind;
+
+  /* play a game for a while, moving about and firing, so there's plenty in flight to save */
+  fn played(ticks: u32) -> (Game, Playfield)
+  {
+    let mut state = Game::new(GameMode::Single, 99, Difficulty::new(Profile::Arcade));
+    let mut playfield = Playfield::new(&mut state);
+    for tick in 0..ticks
+    {
+      step(&mut state, &mut playfield, tick);
+    }
+    (state, playfield)
+  }
+
+  /* run a tick with the hero sweeping from side to side, firing */
+  fn step(state: &mut Game, playfield: &mut Playfield, tick: u32)
+  {
+    let controls = Controls { left: (tick / 90) % 2 == 0, right: (tick / 90) % 2 == 1, fire: true, ..Controls::default() };
+    if playfield.step(state, &[ controls ]).is_some() == true
+    {
+      *playfield = Playfield::new(state);
+    }
+  }
+
+  /* decode a game, failing the test if it can't be read back */
+  fn reload(text: &str) -> (Game, Playfield)
+  {
+    match decode(text)
+    {
+      Ok(loaded) => loaded,
+      Err(e) => panic!("couldn't read the save back: {}", e)
+    }
+  }
+
+  #[test]
+  fn continued_game_carries_on_exactly()
+  {
+    let (mut state, mut playfield) = played(400);
+    playfield.heroes[0].power_up(Kind::Shield);
+    let (mut loaded_state, mut loaded_playfield) = reload(encode(&state, &playfield).as_str());
+
+    assert_eq!(loaded_state.score, state.score);
+    assert_eq!(loaded_state.lives, state.lives);
+    assert_eq!(loaded_state.level, state.level);
+    assert!(loaded_state.difficulty == state.difficulty);
+    assert!(loaded_playfield.state_hash() == playfield.state_hash());
+    assert!(loaded_playfield.heroes[0].powers() == playfield.heroes[0].powers());
+    assert!(state.stats.species_kills.iter().sum::<u32>() > 0);
+    assert!(loaded_state.stats.species_kills == state.stats.species_kills);
+
+    /* saving again gives the same file */
+    assert_eq!(encode(&loaded_state, &loaded_playfield), encode(&state, &playfield));
+
+    /* and the two games play on the same */
+    for tick in 400..800
+    {
+      step(&mut state, &mut playfield, tick);
+      step(&mut loaded_state, &mut loaded_playfield, tick);
+      assert!(loaded_playfield.state_hash() == playfield.state_hash(), "games drifted apart at tick {}", tick);
+    }
+    assert_eq!(loaded_state.score, state.score);
+  }
+
+  #[test]
+  fn reads_version_7_saves()
+  {
+    let (state, mut playfield) = played(200);
+    playfield.heroes[0].power_up(Kind::Shield);
+    let text = encode(&state, &playfield);
+
+    /* version 7 came before weapons, the weapon upgrade power-up at the end of the powers
+     * line, ship inertia and kills by species */
+    let mut old = Vec::new();
+    for line in text.lines()
+    {
+      let words: Vec<&str> = line.split_whitespace().collect();
+      match words.first().cloned()
+      {
+        Some(SAVE_MAGIC) => old.push(format!("{} 7", SAVE_MAGIC)),
+        Some("powers") => old.push(words[..words.len() - 1].join(" ")),
+        Some("weapon") | Some("weapons") | Some("payload") | Some("velocity") | Some("inertia") |
+        Some("species_kills") => {},
+        _ => old.push(String::from(line))
+      }
+    }
+
+    let (loaded_state, loaded_playfield) = reload(old.join("\n").as_str());
+    assert_eq!(loaded_state.score, state.score);
+    assert!(loaded_playfield.heroes[0].powers() == playfield.heroes[0].powers());
+    assert_eq!(loaded_playfield.heroes[0].get_weapon().level(), 1);
+    assert!(loaded_state.stats.kills == state.stats.kills);
+    assert!(loaded_state.stats.species_kills.iter().all(|kills| *kills == 0));
+  }
+
+  #[test]
+  fn rejects_newer_and_damaged_saves()
+  {
+    let (state, playfield) = played(10);
+    let text = encode(&state, &playfield);
+
+    let newer = text.replacen(format!("{} {}", SAVE_MAGIC, SAVE_VERSION).as_str(),
+                              format!("{} {}", SAVE_MAGIC, SAVE_VERSION + 1).as_str(), 1);
+    match decode(newer.as_str())
+    {
+      Err(SaveError::Version(version)) => assert_eq!(version, SAVE_VERSION + 1),
+      _ => panic!("a save from a newer version was read")
+    }
+
+    for broken in [ &text[..text.len() / 2], "something else entirely" ].iter()
+    {
+      match decode(broken)
+      {
+        Err(SaveError::Corrupt(_)) => {},
+        _ => panic!("a damaged save wasn't reported as corrupt")
+      }
+    }
+  }
+}
@@ -0,0 +1,353 @@
+/* Space invaders in Rust
+ *
+ * Statistics on how each game was played
+ *
+ * Game concept by Tomohiro Nishikado / Taito
+ * Rust code By Chris Williams <diodesign@tuta.io>
+ *
+ * Written for fun. See LICENSE.
+ *
+ */
+
+/* the simulation fills these in as the game is played. they're shown on the level complete
+ * and game over screens, and every finished game is added to STATS_FILE as one line of
+ * JSON so that games can be compared and analysed over time. the file doubles as the
+ * high-score table: games that make it into the table carry the player's initials */
+
+use std::fs::{ self, OpenOptions };
+use std::io::{ self, Write };
+use std::time::{ SystemTime, UNIX_EPOCH };
+
+use super::aliens;
+use super::difficulty::{ Difficulty, Profile };
+use super::game::{ Game, GameMode, TICKS_PER_SECOND };
+use super::save;
+
+const STATS_FILE: &str = "rustinvaders-stats.jsonl";
+pub const HIGH_SCORES: usize = 10;   /* games in the high-score table at each difficulty */
+pub const MAX_INITIALS: usize = 3;  /* letters a player can sign a high score with */
+const NO_INITIALS: &str = "???";     /* shown in the table for games that weren't signed */
+
+/* what happened during a game. in co-op mode, these cover both ships */
+pub struct Stats
+{
+  pub shots: u32,              /* bullets fired */
+  pub hits: u32,               /* aliens shot down, and shots that damaged the mothership */
+  pub kills: Vec<u32>,         /* aliens shot down in each row of the formation, from the bottom */
+  pub species_kills: Vec<u32>, /* aliens shot down of each species, escorts included, in the order of aliens::SPECIES */
+  pub bombs_dodged: u32,       /* bombs that fell past the ships without hitting them */
+  pub lost_to_bombs: u32,      /* lives lost to alien bombs */
+  pub lost_to_aliens: u32,     /* lives lost colliding with aliens */
+  pub lost_to_invasion: u32,   /* lives lost when the aliens got below a ship */
+  pub level_ticks: Vec<u32>    /* ticks spent playing each level */
+}
+
+impl Stats
+{
+  pub fn new() -> Stats
+  {
+    Stats
+    {
+      shots: 0, hits: 0,
+      kills: vec![ 0; aliens::ALIEN_ROWS as usize ],
+      species_kills: vec![ 0; aliens::SPECIES.len() ],
+      bombs_dodged: 0,
+      lost_to_bombs: 0, lost_to_aliens: 0, lost_to_invasion: 0,
+      level_ticks: Vec::new()
+    }
+  }
+
+  /* count an alien of the given species shot down in the given formation row */
+  pub fn kill(&mut self, row: i32, species: aliens::Species)
+  {
+    if let Some(kills) = self.kills.get_mut(row as usize)
+    {
+      *kills = *kills + 1;
+    }
+    self.escort_kill(species);
+  }
+
+  /* count an escort of the given species shot down. escorts aren't part of the formation,
+   * so they only count towards their species */
+  pub fn escort_kill(&mut self, species: aliens::Species)
+  {
+    self.hits = self.hits + 1;
+    let index = aliens::SPECIES.iter().position(|s| *s == species).unwrap_or(0);
+    if let Some(kills) = self.species_kills.get_mut(index)
+    {
+      *kills = *kills + 1;
+    }
+  }
+
+  /* count a shot that hit something outside the formation, such as the mothership */
+  pub fn hit(&mut self)
+  {
+    self.hits = self.hits + 1;
+  }
+
+  /* count another tick of the current level */
+  pub fn tick(&mut self)
+  {
+    if let Some(ticks) = self.level_ticks.last_mut()
+    {
+      *ticks = *ticks + 1;
+    }
+  }
+
+  /* returns the percentage of shots that hit an alien */
+  pub fn accuracy(&self) -> f32
+  {
+    match self.shots
+    {
+      0 => 0.0,
+      shots => self.hits as f32 * 100.0 / shots as f32
+    }
+  }
+
+  /* returns the total number of lives lost */
+  pub fn lives_lost(&self) -> u32
+  {
+    self.lost_to_bombs + self.lost_to_aliens + self.lost_to_invasion
+  }
+
+  /* describe how the most recent level went, for the level complete screen */
+  pub fn level_summary(&self) -> Vec<String>
+  {
+    vec!
+    [
+      format!("Level {} cleared in {}", self.level_ticks.len(), time(self.level_ticks.last().cloned().unwrap_or(0))),
+      format!("Shots {}  Hits {}  Accuracy {:.0}%", self.shots, self.hits, self.accuracy())
+    ]
+  }
+
+  /* describe how the whole game went, for the game over screen */
+  pub fn game_summary(&self) -> Vec<String>
+  {
+    let kills: Vec<String> = self.kills.iter().rev().map(|k| k.to_string()).collect();
+    let species: Vec<String> = aliens::SPECIES.iter().zip(self.species_kills.iter())
+      .map(|(species, kills)| format!("{} {}", species.title(), kills)).collect();
+    vec!
+    [
+      format!("Shots {}  Hits {}  Accuracy {:.0}%", self.shots, self.hits, self.accuracy()),
+      format!("Kills by row, top down: {}", kills.join(" ")),
+      format!("Kills by species: {}", species.join("  ")),
+      format!("Bombs dodged {}", self.bombs_dodged),
+      format!("Lives lost: bombs {}  aliens {}  invasion {}", self.lost_to_bombs, self.lost_to_aliens, self.lost_to_invasion),
+      format!("Played for {}", time(self.level_ticks.iter().sum()))
+    ]
+  }
+
+  /* write the stats to a saved game */
+  pub fn save(&self, writer: &mut save::Writer)
+  {
+    writer.label("stats");
+    for value in [ self.shots, self.hits, self.bombs_dodged, self.lost_to_bombs,
+                   self.lost_to_aliens, self.lost_to_invasion ].iter()
+    {
+      writer.value(value);
+    }
+
+    for list in [ &self.kills, &self.level_ticks ].iter()
+    {
+      writer.value(list.len());
+      for value in list.iter()
+      {
+        writer.value(value);
+      }
+    }
+
+    writer.label("species_kills");
+    for value in self.species_kills.iter()
+    {
+      writer.value(value);
+    }
+  }
+
+  /* recreate the stats from a saved game */
+  pub fn restore(reader: &mut save::Reader) -> Result<Stats, save::SaveError>
+  {
+    reader.label("stats")?;
+    let mut stats = Stats
+    {
+      shots: reader.value()?, hits: reader.value()?, bombs_dodged: reader.value()?,
+      lost_to_bombs: reader.value()?, lost_to_aliens: reader.value()?, lost_to_invasion: reader.value()?,
+      kills: Vec::new(), species_kills: vec![ 0; aliens::SPECIES.len() ], level_ticks: Vec::new()
+    };
+
+    for list in [ &mut stats.kills, &mut stats.level_ticks ].iter_mut()
+    {
+      let count: usize = reader.value()?;
+      for _ in 0..count
+      {
+        list.push(reader.value()?);
+      }
+    }
+
+    /* files from before the species were counted leave them out */
+    if reader.next_is("species_kills") == true
+    {
+      reader.label("species_kills")?;
+      for kills in stats.species_kills.iter_mut()
+      {
+        *kills = reader.value()?;
+      }
+    }
+
+    return Ok(stats);
+  }
+}
+
+/* turn a number of ticks into minutes and seconds */
+fn time(ticks: u32) -> String
+{
+  let seconds = ticks / TICKS_PER_SECOND;
+  format!("{}:{:02}", seconds / 60, seconds % 60)
+}
+
+/* describe a finished game as a single line of JSON
+ * => state = the finished game
+ *    initials = the player's initials if the game made the high-score table, or None */
+fn to_json(state: &Game, initials: Option<&str>) -> String
+{
+  let stats = &state.stats;
+  let list = |values: &Vec<u32>| values.iter().map(|v| v.to_string()).collect::<Vec<String>>().join(",");
+  let seconds: Vec<String> = stats.level_ticks.iter().map(|t| format!("{:.2}", *t as f32 / TICKS_PER_SECOND as f32)).collect();
+  let species: Vec<String> = aliens::SPECIES.iter().zip(stats.species_kills.iter())
+    .map(|(species, kills)| format!("\"{}\":{}", species.title().to_lowercase(), kills)).collect();
+  let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+  let name = match initials
+  {
+    Some(initials) => format!("\"name\":\"{}\",", initials),
+    None => String::new()
+  };
+
+  format!("{{\"timestamp\":{},{}{},{},\"seed\":{},\"final_score\":{},\"alien_score\":{},\"levels\":{},\
+           \"shots\":{},\"hits\":{},\"accuracy\":{:.2},\"kills_per_row\":[{}],\"kills_per_species\":{{{}}},\"bombs_dodged\":{},\
+           \"lives_lost\":{{\"total\":{},\"bomb\":{},\"collision\":{},\"invasion\":{}}},\"level_seconds\":[{}]}}",
+          timestamp, name, mode_json(state.mode), difficulty_json(&state.difficulty),
+          state.seed, state.score, state.alien_score, state.level,
+          stats.shots, stats.hits, stats.accuracy(), list(&stats.kills), species.join(","), stats.bombs_dodged,
+          stats.lives_lost(), stats.lost_to_bombs, stats.lost_to_aliens, stats.lost_to_invasion, seconds.join(","))
+}
+
+/* describe a game's mode as a JSON field */
+fn mode_json(mode: GameMode) -> String
+{
+  format!("\"mode\":\"{}\"", match mode
+  {
+    GameMode::Single => "single",
+    GameMode::Versus => "versus",
+    GameMode::Coop => "coop"
+  })
+}
+
+/* describe a game's difficulty as a JSON field */
+fn difficulty_json(difficulty: &Difficulty) -> String
+{
+  format!("\"difficulty\":{}", difficulty.to_json())
+}
+
+/* returns the final score and initials, if any, of each single-player game in the stats
+ * file made at the given difficulty, or nothing if there's no file yet */
+fn scores(difficulty: &Difficulty) -> Vec<(i32, Option<String>)>
+{
+  match fs::read_to_string(STATS_FILE)
+  {
+    Ok(text) => scores_in(text.as_str(), difficulty),
+    Err(_) => Vec::new()
+  }
+}
+
+/* returns the final score and initials, if any, of each single-player game in the text of
+ * a stats file made at the given difficulty. two players share a score in co-op, and in
+ * versus the score is the hero's alone, so neither is ranked with one player's games.
+ * games recorded before there was a choice of difficulty were played on normal */
+fn scores_in(text: &str, difficulty: &Difficulty) -> Vec<(i32, Option<String>)>
+{
+  /* returns the text of a field's value, up to the end of the value */
+  let field = |line: &str, label: &str, end: &dyn Fn(char) -> bool| -> Option<String>
+  {
+    let start = line.find(label)? + label.len();
+    line[start..].split(end).next().map(|value| String::from(value.trim()))
+  };
+
+  let mode = mode_json(GameMode::Single);
+  let wanted = difficulty_json(difficulty);
+  let legacy = difficulty.profile == Profile::Normal;
+  text.lines()
+      .filter(|line| line.contains(mode.as_str()) == true)
+      .filter(|line| line.contains(wanted.as_str()) == true || (legacy == true && line.contains("\"difficulty\":") == false))
+      .filter_map(|line|
+      {
+        let score = field(line, "\"final_score\":", &|c| c == ',' || c == '}')?.parse::<i32>().ok()?;
+
+        /* the initials come before the mode. the difficulty, after it, has a name too */
+        let head = &line[..line.find(mode.as_str()).unwrap_or(0)];
+        Some((score, field(head, "\"name\":\"", &|c| c == '"')))
+      })
+      .collect()
+}
+
+/* returns the best final score in the stats file made at the given difficulty, or zero
+ * if there's no file yet */
+pub fn high_score(difficulty: &Difficulty) -> i32
+{
+  scores(difficulty).iter().map(|(score, _)| *score).max().unwrap_or(0)
+}
+
+/* returns the high-score table at the given difficulty: the initials and final score of
+ * the best games, best first */
+pub fn high_scores(difficulty: &Difficulty) -> Vec<(String, i32)>
+{
+  let mut table = scores(difficulty);
+  table.sort_by(|a, b| b.0.cmp(&a.0));
+  table.truncate(HIGH_SCORES);
+  table.into_iter().map(|(score, name)| (name.unwrap_or(String::from(NO_INITIALS)), score)).collect()
+}
+
+/* returns true if a finished game's score earns it a place in the high-score table. only
+ * single-player games are ranked */
+pub fn makes_table(state: &Game) -> bool
+{
+  if state.mode != GameMode::Single
+  {
+    return false;
+  }
+
+  let table = high_scores(&state.difficulty);
+  state.score > 0 && (table.len() < HIGH_SCORES || table.iter().any(|(_, score)| state.score > *score))
+}
+
+/* add a finished game's stats to the end of the stats file
+ * => state = the finished game
+ *    initials = the player's initials if the game made the high-score table, or None */
+pub fn record(state: &Game, initials: Option<&str>) -> io::Result<()>
+{
+  let mut file = OpenOptions::new().create(true).append(true).open(STATS_FILE)?;
+  writeln!(file, "{}", to_json(state, initials))
+}
+
+/* ------------------------------------------------------------------------------ */
+
+#[cfg(test)]
+mod tests
+{
+  use super::*;
+
+  #[test]
+  fn ranks_single_player_games_only()
+  {
+    let normal = Difficulty::new(Profile::Normal);
+    let arcade = Difficulty::new(Profile::Arcade);
+    let text = [
+      format!("{{\"name\":\"ONE\",{},{},\"final_score\":300}}", mode_json(GameMode::Single), difficulty_json(&normal)),
+      format!("{{\"name\":\"TWO\",{},{},\"final_score\":900}}", mode_json(GameMode::Coop), difficulty_json(&normal)),
+      format!("{{{},{},\"final_score\":800}}", mode_json(GameMode::Versus), difficulty_json(&normal)),
+      format!("{{{},{},\"final_score\":700}}", mode_json(GameMode::Single), difficulty_json(&arcade)),
+      format!("{{{},\"final_score\":200}}", mode_json(GameMode::Single))
+    ].join("\n");
+
+    assert!(scores_in(text.as_str(), &normal) == vec![ (300, Some(String::from("ONE"))), (200, None) ]);
+    assert!(scores_in(text.as_str(), &arcade) == vec![ (700, None) ]);
+  }
+}
@@ -0,0 +1,729 @@
+/* Space invaders in Rust
+ *
+ * Text terminal front end: play the game in a terminal, without OpenGL
+ *
+ * Game concept by Tomohiro Nishikado / Taito
+ * Rust code By Chris Williams <diodesign@tuta.io>
+ *
+ * Written for fun. See LICENSE.
+ *
+ */
+
+/* this is the second way of looking at the game state, alongside the kiss3d renderer.
+ * the playfield is drawn with braille characters, each of which holds a 2x4 grid of
+ * dots. that's fine enough to draw the aliens pixel for pixel in a big terminal, and
+ * the picture is scaled down to fit smaller terminals. keypresses are read straight from
+ * the terminal, so this works over ssh and on machines with no graphics at all.
+ *
+ * terminals don't tell us when a key is released, they only repeat the keypress while
+ * the key is held down. so a keypress holds its control down for a short while, long
+ * enough to bridge the gap before the terminal starts repeating the key */
+
+use std::io::{ self, Read, Write };
+use std::process::{ Command, Stdio };
+use std::sync::mpsc::{ self, Receiver };
+use std::thread;
+use std::time::{ Duration, Instant };
+
+use super::aliens;
+use super::boss;
+use super::hero;
+use super::game::{ Game, GameMode, Playfield, LevelOutcome, TICKS_PER_SECOND };
+use super::input::Controls;
+use super::options::Options;
+use super::powerup;
+use super::weapon;
+use super::stats;
+
+const FRAMES_PER_SECOND: u32 = 30; /* terminals can't keep up with 60 full redraws a second */
+const TICKS_PER_FRAME: u32 = TICKS_PER_SECOND / FRAMES_PER_SECOND;
+
+const MOVE_HOLD_TICKS: u32 = TICKS_PER_SECOND / 2; /* how long a movement keypress is held down */
+const FIRE_HOLD_TICKS: u32 = 2;                    /* how long a fire keypress is held down */
+const SPECIAL_HOLD_TICKS: u32 = 1;                 /* how long a special keypress is held down */
+const MESSAGE_TICKS: u32 = 2 * TICKS_PER_SECOND;   /* how long to show the level complete message */
+
+/* area of the game world that is drawn, in game world units */
+const FIELD_LEFT: f32   = 112.0;  /* remember, screen left is the positive x direction */
+const FIELD_RIGHT: f32  = -112.0;
+const FIELD_TOP: f32    = 96.0;
+const FIELD_BOTTOM: f32 = -98.0;
+
+const TEXT_ROWS: usize = 2;         /* terminal rows used by the score and message lines */
+const HEALTH_BAR_WIDTH: usize = 20; /* characters in the mothership's health bar */
+const CHARGE_BAR_WIDTH: usize = 6;  /* characters in a weapon's charge bar */
+const DEFAULT_SIZE: (usize, usize) = (24, 80); /* rows and columns if the terminal won't say */
+
+const DEBRIS_SPEED: f32 = 0.4;                      /* world units per tick that explosion dots fly */
+const ALIEN_DEBRIS_TICKS: u32 = 3 * TICKS_PER_SECOND; /* how long to draw an alien's explosion */
+const HERO_DEBRIS_TICKS: u32 = 2 * TICKS_PER_SECOND;  /* how long to draw the ship's explosion */
+
+/* terminal colors, as ANSI foreground color codes */
+const COLOR_DEBRIS: u8 = 33; /* yellow */
+const COLOR_HERO: u8   = 37; /* white */
+const COLOR_SHIELD: u8 = 34; /* blue, flashing on and off while the ship has a shield */
+const SHIELD_FLASH_TICKS: u32 = 8;
+
+/* the hero's ship, drawn dot for dot, row by row from the top */
+const HERO_SHAPE: [&str; 10] =
+[
+  "     #     ",
+  "    ###    ",
+  "    ###    ",
+  "   #####   ",
+  "   #####   ",
+  "  #######  ",
+  " ######### ",
+  "###########",
+  "###########",
+  "##  ###  ##"
+];
+
+/* braille dot bit for each dot within a character, by row and then column */
+const BRAILLE_DOTS: [[u8; 2]; 4] = [ [ 0x01, 0x08 ], [ 0x02, 0x10 ], [ 0x04, 0x20 ], [ 0x40, 0x80 ] ];
+const BRAILLE_BASE: u32 = 0x2800;
+
+/* keypresses we care about */
+#[derive(Clone, Copy, PartialEq)]
+enum Key
+{
+  Left,
+  Right,
+  Fire,
+  Special,
+  Quit
+}
+
+/* a text terminal switched into a mode where we can read single keypresses and
+ * redraw the whole screen. it's put back the way we found it when dropped */
+struct Terminal
+{
+  saved: String,       /* the terminal's settings before we started */
+  keys: Receiver<Key>, /* keypresses read from the terminal */
+  rows: usize,
+  columns: usize
+}
+
+impl Terminal
+{
+  /* take over the terminal attached to stdin and stdout */
+  fn open() -> io::Result<Terminal>
+  {
+    /* stop the terminal echoing keypresses, waiting for return, or handling ctrl-c itself */
+    let saved = stty(&[ "-g" ])?;
+    stty(&[ "-icanon", "-echo", "-isig" ])?;
+
+    let (rows, columns) = match stty(&[ "size" ])
+    {
+      Ok(size) =>
+      {
+        let numbers: Vec<usize> = size.split_whitespace().filter_map(|n| n.parse().ok()).collect();
+        match numbers.as_slice()
+        {
+          [ rows, columns ] if *rows > 0 && *columns > 0 => (*rows, *columns),
+          _ => DEFAULT_SIZE
+        }
+      },
+      Err(_) => DEFAULT_SIZE
+    };
+
+    /* read the keyboard on its own thread, so the game doesn't wait for keypresses */
+    let (sender, keys) = mpsc::channel();
+    thread::spawn(move ||
+    {
+      let mut bytes = io::stdin().bytes().filter_map(|b| b.ok());
+      while let Some(byte) = bytes.next()
+      {
+        let key = match byte
+        {
+          b'z' | b'a' => Some(Key::Left),
+          b'x' | b'd' => Some(Key::Right),
+          b' ' | b'\n' | b'\r' => Some(Key::Fire),
+          b'c' | b's' => Some(Key::Special),
+          b'q' | 0x03 => Some(Key::Quit),
+
+          /* arrow keys arrive as escape sequences */
+          0x1b => match (bytes.next(), bytes.next())
+          {
+            (Some(b'['), Some(b'D')) => Some(Key::Left),
+            (Some(b'['), Some(b'C')) => Some(Key::Right),
+            (Some(b'['), Some(b'A')) => Some(Key::Fire),
+            _ => None
+          },
+          _ => None
+        };
+
+        if let Some(key) = key
+        {
+          if sender.send(key).is_err() == true
+          {
+            break; /* nobody is listening any more */
+          }
+        }
+      }
+    });
+
+    /* switch to the alternate screen and hide the cursor */
+    print!("\x1b[?1049h\x1b[?25l\x1b[2J");
+    io::stdout().flush()?;
+
+    Ok(Terminal
+    {
+      saved: saved.trim().to_string(),
+      keys: keys,
+      rows: rows,
+      columns: columns
+    })
+  }
+
+  /* return the keys pressed since the last call */
+  fn keys(&self) -> Vec<Key>
+  {
+    self.keys.try_iter().collect()
+  }
+
+  /* redraw the screen
+   * => top = line of text to show above the playfield
+   *    canvas = the playfield
+   *    bottom = line of text to show below the playfield */
+  fn draw(&mut self, top: &str, canvas: &Canvas, bottom: &str) -> io::Result<()>
+  {
+    let mut screen = String::from("\x1b[H");
+    screen.push_str(&format!("\x1b[0m{}\x1b[K\r\n", top));
+    canvas.render(&mut screen);
+    screen.push_str(&format!("\x1b[0m{}\x1b[K", bottom));
+
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    out.write_all(screen.as_bytes())?;
+    out.flush()
+  }
+}
+
+impl Drop for Terminal
+{
+  fn drop(&mut self)
+  {
+    /* show the cursor, go back to the normal screen, and restore the terminal's settings */
+    print!("\x1b[0m\x1b[?25h\x1b[?1049l");
+    let _ = io::stdout().flush();
+    let _ = stty(&[ self.saved.as_str() ]);
+  }
+}
+
+/* run stty on the terminal attached to stdin
+ * => args = arguments to pass to stty
+ * <= what stty printed, or an error if it failed, eg: stdin isn't a terminal */
+fn stty(args: &[&str]) -> io::Result<String>
+{
+  let output = Command::new("stty").args(args).stdin(Stdio::inherit()).stderr(Stdio::null()).output()?;
+  match output.status.success()
+  {
+    true => Ok(String::from_utf8_lossy(&output.stdout).into_owned()),
+    false => Err(io::Error::new(io::ErrorKind::Other, "can't set up the terminal: is this running in one?"))
+  }
+}
+
+/* ------------------------------------------------------------------------------ */
+
+/* a picture of the playfield made of braille dots */
+struct Canvas
+{
+  scale: f32,      /* game world units per dot */
+  columns: usize,  /* size of the picture in characters */
+  rows: usize,
+  dots: Vec<u8>,   /* braille dots set in each character */
+  colors: Vec<u8>  /* color of each character */
+}
+
+impl Canvas
+{
+  /* create a canvas that fits the playfield into the given number of characters */
+  fn new(columns: usize, rows: usize) -> Canvas
+  {
+    let width = FIELD_LEFT - FIELD_RIGHT;
+    let height = FIELD_TOP - FIELD_BOTTOM;
+
+    /* draw at one dot per game world unit if there's room, otherwise shrink the picture */
+    let scale = (width / (columns.max(1) * 2) as f32).max(height / (rows.max(1) * 4) as f32).max(1.0);
+    let columns = (width / scale / 2.0).ceil() as usize;
+    let rows = (height / scale / 4.0).ceil() as usize;
+
+    Canvas
+    {
+      scale: scale,
+      columns: columns,
+      rows: rows,
+      dots: vec![ 0; columns * rows ],
+      colors: vec![ 0; columns * rows ]
+    }
+  }
+
+  /* wipe the picture */
+  fn clear(&mut self)
+  {
+    for dots in self.dots.iter_mut()
+    {
+      *dots = 0;
+    }
+  }
+
+  /* fill in a rectangle in the picture
+   * => x, y = center of the rectangle in the game world
+   *    width, height = size of the rectangle in game world units
+   *    color = ANSI color of the rectangle */
+  fn fill(&mut self, x: f32, y: f32, width: f32, height: f32, color: u8)
+  {
+    let (left, right) = self.span(FIELD_LEFT - (x + width / 2.0), FIELD_LEFT - (x - width / 2.0));
+    let (top, bottom) = self.span(FIELD_TOP - (y + height / 2.0), FIELD_TOP - (y - height / 2.0));
+
+    for dot_y in top..bottom
+    {
+      for dot_x in left..right
+      {
+        self.plot(dot_x, dot_y, color);
+      }
+    }
+  }
+
+  /* turn a distance range from the top or left of the playfield into a range of dots,
+   * covering at least one dot */
+  fn span(&self, start: f32, end: f32) -> (isize, isize)
+  {
+    let first = (start / self.scale + 0.001).floor() as isize;
+    let last = (end / self.scale - 0.001).ceil() as isize;
+    (first, last.max(first + 1))
+  }
+
+  /* set a single dot. dots outside the picture are ignored */
+  fn plot(&mut self, dot_x: isize, dot_y: isize, color: u8)
+  {
+    if dot_x < 0 || dot_y < 0 || dot_x as usize >= self.columns * 2 || dot_y as usize >= self.rows * 4
+    {
+      return;
+    }
+
+    let (dot_x, dot_y) = (dot_x as usize, dot_y as usize);
+    let cell = ((dot_y / 4) * self.columns) + (dot_x / 2);
+    self.dots[cell] = self.dots[cell] | BRAILLE_DOTS[dot_y % 4][dot_x % 2];
+    self.colors[cell] = color;
+  }
+
+  /* draw an alien from its pixel design */
+  fn draw_alien(&mut self, alien: &aliens::Alien)
+  {
+    let (x, y, _) = alien.get_coords();
+    let squash = alien.get_angle().cos(); /* the alien spins around its y-axis */
+    self.draw_pixels(&aliens::design(alien.get_species()), (x, y), squash, alien.get_frame(),
+                     alien.get_state(), alien.ticks_since_death());
+  }
+
+  /* draw the mothership's sections and the escorts it has launched */
+  fn draw_boss(&mut self, boss: &boss::Mothership)
+  {
+    for section in boss.get_sections().iter()
+    {
+      let (x, y, _) = boss.section_coords(section);
+      self.draw_pixels(&section.get_part().design(), (x, y), 1.0, boss.get_frame(),
+                       section.get_state(), section.ticks_since_death());
+    }
+
+    for escort in boss.escorts.iter()
+    {
+      self.draw_alien(escort);
+    }
+  }
+
+  /* draw something built from pixels, such as an alien, or its debris as it blows up
+   * => design = the pixels to draw
+   *    center = (x, y) center of the design in the game world
+   *    squash = how much to narrow the design, as it spins around its y-axis
+   *    frame = the animation frame to draw
+   *    state = whether it's alive, blowing up or gone
+   *    death_ticks = ticks since it started blowing up, if it has */
+  fn draw_pixels(&mut self, design: &[aliens::Pixel], center: (f32, f32), squash: f32, frame: aliens::Frame,
+                 state: aliens::State, death_ticks: Option<u32>)
+  {
+    let (x, y) = center;
+    for (index, pixel) in design.iter().enumerate()
+    {
+      let (mut pixel_x, mut pixel_y) = match frame
+      {
+        aliens::Frame::Base => (pixel.x, pixel.y),
+        aliens::Frame::Translated => (pixel.x + pixel.tx, pixel.y + pixel.ty)
+      };
+
+      match state
+      {
+        aliens::State::Alive =>
+          self.fill(x + (pixel_x * squash), y + pixel_y, pixel.width * squash.abs(), pixel.height, color(pixel.r, pixel.g, pixel.b)),
+
+        aliens::State::Dying =>
+        {
+          /* send the pixels flying out from the center */
+          let ticks = death_ticks.unwrap_or(0);
+          if ticks < ALIEN_DEBRIS_TICKS
+          {
+            let (dx, dy) = debris_direction(pixel_x, pixel_y, index);
+            pixel_x = pixel_x + (dx * DEBRIS_SPEED * ticks as f32);
+            pixel_y = pixel_y + (dy * DEBRIS_SPEED * ticks as f32);
+            self.fill(x + pixel_x, y + pixel_y, 1.0, 1.0, COLOR_DEBRIS);
+          }
+        },
+
+        aliens::State::Dead => {}
+      }
+    }
+  }
+
+  /* draw the hero's ship, or its explosion */
+  fn draw_hero(&mut self, ship: &hero::Hero)
+  {
+    let (x, y, _) = ship.get_coords();
+    let ticks = match ship.state
+    {
+      hero::State::Alive => None,
+      hero::State::Dying => match ship.ticks_since_death()
+      {
+        Some(ticks) if ticks < HERO_DEBRIS_TICKS => Some(ticks),
+        _ => return
+      },
+      hero::State::Dead => return
+    };
+
+    let shield = ship.powers().iter().find(|(kind, _)| *kind == powerup::Kind::Shield).map(|(_, ticks)| *ticks);
+    let ship_color = match shield
+    {
+      Some(ticks) if (ticks / SHIELD_FLASH_TICKS) % 2 == 0 => COLOR_SHIELD,
+      _ => COLOR_HERO
+    };
+
+    let half_width = HERO_SHAPE[0].len() as f32 / 2.0;
+    let half_height = HERO_SHAPE.len() as f32 / 2.0;
+    for (row, line) in HERO_SHAPE.iter().enumerate()
+    {
+      for (column, _) in line.chars().enumerate().filter(|(_, c)| *c == '#')
+      {
+        /* screen left is the positive x direction */
+        let mut dot_x = half_width - column as f32 - 0.5;
+        let mut dot_y = half_height - row as f32 - 0.5;
+        let mut color = ship_color;
+
+        if let Some(ticks) = ticks
+        {
+          let (dx, dy) = debris_direction(dot_x, dot_y, row + column);
+          dot_x = dot_x + (dx * DEBRIS_SPEED * ticks as f32);
+          dot_y = dot_y + (dy * DEBRIS_SPEED * ticks as f32);
+          color = COLOR_DEBRIS;
+        }
+
+        self.fill(x + dot_x, y + dot_y, 1.0, 1.0, color);
+      }
+    }
+  }
+
+  /* draw everything on the playfield */
+  fn draw_playfield(&mut self, playfield: &Playfield)
+  {
+    for alien in playfield.aliens.get_squadron().iter()
+    {
+      self.draw_alien(alien);
+    }
+
+    if let Some(boss) = playfield.boss.as_ref()
+    {
+      self.draw_boss(boss);
+    }
+
+    for ship in playfield.heroes.iter()
+    {
+      self.draw_hero(ship);
+    }
+
+    /* beams trail down from the tip of the shot */
+    for shot in playfield.heroes.iter().flat_map(|p| p.shots.iter())
+    {
+      let (x, y, _) = shot.bullet.get_coords();
+      let width = shot.bullet.get_radius() * 2.0;
+      let (r, g, b) = shot.bullet.get_color();
+      let height = width.max(shot.get_length());
+      self.fill(x, y - ((height - width) / 2.0), width, height, color(r, g, b));
+    }
+
+    for projectile in playfield.bombs()
+    {
+      let (x, y, _) = projectile.get_coords();
+      let size = projectile.get_radius() * 2.0;
+      let (r, g, b) = projectile.get_color();
+      self.fill(x, y, size, size, color(r, g, b));
+    }
+
+    for capsule in playfield.powerups.capsules.iter()
+    {
+      let (x, y, _) = capsule.get_coords();
+      let (r, g, b) = capsule.get_kind().color();
+      self.fill(x, y, powerup::CAPSULE_SIZE, powerup::CAPSULE_SIZE, color(r, g, b));
+    }
+  }
+
+  /* turn the picture into text, with color changes, appended to the given string */
+  fn render(&self, screen: &mut String)
+  {
+    let mut current = 0;
+    for row in 0..self.rows
+    {
+      for column in 0..self.columns
+      {
+        let cell = (row * self.columns) + column;
+        match self.dots[cell]
+        {
+          0 => screen.push(' '),
+          dots =>
+          {
+            if self.colors[cell] != current
+            {
+              current = self.colors[cell];
+              screen.push_str(&format!("\x1b[{}m", current));
+            }
+            screen.push(std::char::from_u32(BRAILLE_BASE + dots as u32).unwrap_or('#'));
+          }
+        }
+      }
+      screen.push_str("\x1b[K\r\n");
+    }
+  }
+}
+
+/* returns the line shown below the playfield: the mothership's health as a bar of text,
+ * if there is one, each ship's weapon, and the seconds left of its power-ups */
+fn status_line(playfield: &Playfield) -> String
+{
+  let mut parts = Vec::new();
+  if let Some(boss) = playfield.boss.as_ref()
+  {
+    let full = (boss.health() * HEALTH_BAR_WIDTH as f32).ceil() as usize;
+    parts.push(format!("Mothership [{}{}]", "#".repeat(full), " ".repeat(HEALTH_BAR_WIDTH - full)));
+  }
+
+  for ship in playfield.heroes.iter()
+  {
+    parts.push(weapon::describe(ship.get_weapon(), CHARGE_BAR_WIDTH));
+    for (kind, ticks) in ship.powers()
+    {
+      parts.push(format!("{} {}s", kind.title(), (ticks + TICKS_PER_SECOND - 1) / TICKS_PER_SECOND));
+    }
+  }
+
+  return parts.join("    ");
+}
+
+/* pick the nearest of the terminal's eight basic colors to an RGB color.
+ * black is turned into gray, so it shows up on a black background */
+fn color(r: f32, g: f32, b: f32) -> u8
+{
+  let code = (r > 0.5) as u8 + ((g > 0.5) as u8 * 2) + ((b > 0.5) as u8 * 4);
+  match code
+  {
+    0 => 90,
+    _ => 30 + code
+  }
+}
+
+/* return a direction for a dot of debris to fly in, out from the center with a bit of
+ * variety so the explosion doesn't look too tidy
+ * => x, y = position of the dot relative to the center of the thing exploding
+ *    index = which dot this is, to vary its direction */
+fn debris_direction(x: f32, y: f32, index: usize) -> (f32, f32)
+{
+  let wobble = ((index * 7) % 5) as f32 - 2.0;
+  let (dx, dy) = (x + wobble, y - wobble + 1.0);
+  let length = ((dx * dx) + (dy * dy)).sqrt().max(1.0);
+  (dx / length, dy / length)
+}
+
+/* ------------------------------------------------------------------------------ */
+
+/* movement and fire controls held down by recent keypresses */
+struct HeldKeys
+{
+  left: u32, right: u32, fire: u32, special: u32 /* ticks left to hold down each control */
+}
+
+impl HeldKeys
+{
+  fn new() -> HeldKeys
+  {
+    HeldKeys { left: 0, right: 0, fire: 0, special: 0 }
+  }
+
+  fn press(&mut self, key: Key)
+  {
+    match key
+    {
+      Key::Left =>
+      {
+        self.left = MOVE_HOLD_TICKS;
+        self.right = 0;
+      },
+      Key::Right =>
+      {
+        self.right = MOVE_HOLD_TICKS;
+        self.left = 0;
+      },
+      Key::Fire => self.fire = FIRE_HOLD_TICKS,
+      Key::Special => self.special = SPECIAL_HOLD_TICKS,
+      Key::Quit => {}
+    }
+  }
+
+  /* return the controls held down for this tick, and count down the time left holding them */
+  fn tick(&mut self) -> Controls
+  {
+    let controls = Controls
+    {
+      left: self.left > 0,
+      right: self.right > 0,
+      fire: self.fire > 0,
+      special: self.special > 0,
+      stick: 0
+    };
+
+    self.left = self.left.saturating_sub(1);
+    self.right = self.right.saturating_sub(1);
+    self.fire = self.fire.saturating_sub(1);
+    self.special = self.special.saturating_sub(1);
+    return controls;
+  }
+}
+
+/* keep the game running at a steady number of frames per second
+ * => next = when the next frame is due, updated to the frame after */
+fn wait_for_frame(next: &mut Instant)
+{
+  *next = *next + Duration::from_secs(1) / FRAMES_PER_SECOND;
+  let now = Instant::now();
+  match *next > now
+  {
+    true => thread::sleep(*next - now),
+    false => *next = now /* running behind, so don't try to catch up */
+  }
+}
+
+/* ------------------------------------------------------------------------------ */
+
+/* play single-player games in the terminal until the player quits
+ * => options = command-line options: the seed, lives and level each game starts with
+ * <= error if the terminal couldn't be used */
+pub fn play(options: &Options) -> io::Result<()>
+{
+  let mut terminal = Terminal::open()?;
+  let mut canvas = Canvas::new(terminal.columns, terminal.rows.saturating_sub(TEXT_ROWS));
+
+  /* title screen, then a game, then the game over screen, until the player quits */
+  while title(&mut terminal, &mut canvas, "Rust Invaders", "Press space to play, or q to quit")? == true
+  {
+    let mut state = options.new_game(GameMode::Single, options.pick_seed());
+    loop
+    {
+      match play_level(&mut terminal, &mut canvas, &mut state)?
+      {
+        None => return Ok(()), /* player quit */
+        Some(LevelOutcome::Victory) => {},
+        Some(_) => break
+      }
+    }
+
+    let score = format!("Game over :(    Final score: {:07}    {}", state.score, state.stats.game_summary()[0]);
+    let message = match stats::record(&state, None)
+    {
+      Ok(()) => String::from("Press space to continue, or q to quit"),
+      Err(e) => format!("Could not record game stats: {}. Press space to continue, or q to quit", e)
+    };
+    if title(&mut terminal, &mut canvas, score.as_str(), message.as_str())? == false
+    {
+      return Ok(());
+    }
+  }
+
+  return Ok(());
+}
+
+/* show a spinning alien with a heading and a message until a key is pressed
+ * <= true if fire was pressed, or false if the player wants to quit */
+fn title(terminal: &mut Terminal, canvas: &mut Canvas, heading: &str, message: &str) -> io::Result<bool>
+{
+  let mut alien = aliens::Alien::new();
+  alien.spawn(0.0, 0.0, 0.0, 0.0);
+  let mut next = Instant::now();
+
+  loop
+  {
+    for key in terminal.keys()
+    {
+      match key
+      {
+        Key::Fire => return Ok(true),
+        Key::Quit => return Ok(false),
+        _ => {}
+      }
+    }
+
+    for _ in 0..TICKS_PER_FRAME
+    {
+      alien.animate(0.0); /* step = 0: don't move the alien */
+    }
+
+    canvas.clear();
+    canvas.draw_alien(&alien);
+    terminal.draw(heading, canvas, message)?;
+    wait_for_frame(&mut next);
+  }
+}
+
+/* play a level of the game in the terminal
+ * => state = game state variables
+ * <= how the level ended, or None if the player quit */
+fn play_level(terminal: &mut Terminal, canvas: &mut Canvas, state: &mut Game) -> io::Result<Option<LevelOutcome>>
+{
+  let mut playfield = Playfield::new(state);
+  let mut held = HeldKeys::new();
+  let mut next = Instant::now();
+  let mut outcome = None;
+
+  while outcome.is_none() == true
+  {
+    for key in terminal.keys()
+    {
+      match key
+      {
+        Key::Quit => return Ok(None),
+        key => held.press(key)
+      }
+    }
+
+    for _ in 0..TICKS_PER_FRAME
+    {
+      outcome = playfield.step(state, &[ held.tick() ]);
+      if outcome.is_some() == true
+      {
+        break;
+      }
+    }
+
+    canvas.clear();
+    canvas.draw_playfield(&playfield);
+    terminal.draw(state.score_line().as_str(), canvas, status_line(&playfield).as_str())?;
+    wait_for_frame(&mut next);
+  }
+
+  /* let the player see they've cleared the level before moving on */
+  if let Some(LevelOutcome::Victory) = outcome
+  {
+    let message = state.stats.level_summary().join("    ");
+    terminal.draw(state.score_line().as_str(), canvas, message.as_str())?;
+    thread::sleep(Duration::from_secs(MESSAGE_TICKS as u64) / TICKS_PER_SECOND);
+    terminal.keys(); /* ignore keys pressed while the message was up */
+  }
+
+  return Ok(outcome);
+}
@@ -0,0 +1,662 @@
+/* Space invaders in Rust
+ *
+ * Weapons: what the hero's ship fires, and how
+ *
+ * Game concept by Tomohiro Nishikado / Taito
+ * Rust code By Chris Williams <diodesign@tuta.io>
+ *
+ * Written for fun. See LICENSE.
+ *
+ */
+
+/* each weapon decides what happens when the fire button is worked: how many shots it
+ * fires at once, how many can be in play, how long it takes to reload, and how fast,
+ * big and damaging its shots are. a ship carries one weapon at a time, can switch to
+ * another, and can upgrade the one it has up to MAX_LEVEL. switching keeps the level,
+ * so an upgrade is never wasted. weapons are pure game state, like the rest of the
+ * simulation, so they're saved, recorded and played over the network along with it */
+
+use super::bullet::Bullet;
+use super::game::TICKS_PER_SECOND;
+use super::save;
+
+pub const MAX_LEVEL: u32 = 3;    /* weapons can be upgraded this many times, less one */
+
+/* the classic bullet is a small red ball */
+const BULLET_RADIUS: f32 = 2.0;
+const BULLET_COLOR: (f32, f32, f32) = (1.0, 0.0, 0.0);
+
+const TWIN_GAP: f32 = 6.0;         /* distance between the twin cannon's bullets */
+const TWIN_COLOR: (f32, f32, f32) = (1.0, 0.4, 0.0);
+
+const SPREAD_FAN: f32 = 0.3;       /* distance each bullet in a spread moves sideways each tick, per place from the middle */
+const SPREAD_COLOR: (f32, f32, f32) = (1.0, 0.9, 0.2);
+
+const LASER_WIDTH: f32 = 1.0;
+const LASER_LENGTH: f32 = 16.0;    /* length of a laser beam at level 1. each level adds half as much again */
+const LASER_SPEEDUP: f32 = 2.5;    /* laser beams climb this many times faster than bullets */
+const LASER_RELOAD_TICKS: u32 = TICKS_PER_SECOND / 2;
+const LASER_COLOR: (f32, f32, f32) = (0.4, 1.0, 0.4);
+
+const CHARGE_TICKS: u32 = TICKS_PER_SECOND / 2; /* time held down for each step of charge */
+const CHARGE_STEPS: u32 = 3;                    /* steps of charge a shot can build up, at level 1 */
+const CHARGE_RADIUS: f32 = 1.0;                 /* extra radius of a charged shot for each step of charge */
+const CHARGE_COLOR: (f32, f32, f32) = (1.0, 0.3, 1.0);
+
+/* shots made piercing by a power-up turn cyan */
+const PIERCING_COLOR: (f32, f32, f32) = (0.2, 1.0, 1.0);
+
+/* returns a line describing a weapon: its name and level, and how far it has charged
+ * up if it's a weapon that charges, eg: Charged shot 2 [###   ]
+ * => width = characters in the charge bar */
+pub fn describe(weapon: &dyn Weapon, width: usize) -> String
+{
+  let mut line = format!("{} {}", weapon.kind().title(), weapon.level());
+  if weapon.kind() == Kind::Charged
+  {
+    let full = ((weapon.charge() * width as f32) as usize).min(width);
+    line.push_str(format!(" [{}{}]", "#".repeat(full), " ".repeat(width - full)).as_str());
+  }
+  return line;
+}
+
+/* the weapons to pick from, in the order switching goes through them */
+#[derive(Clone, Copy, PartialEq)]
+pub enum Kind
+{
+  Classic, /* one bullet at a time, as per the original game */
+  Twin,    /* two bullets side by side */
+  Spread,  /* a fan of bullets */
+  Laser,   /* a fast beam that goes through everything in its way */
+  Charged  /* hold fire to charge up a big shot, and let go to fire it */
+}
+
+pub const KINDS: [Kind; 5] = [ Kind::Classic, Kind::Twin, Kind::Spread, Kind::Laser, Kind::Charged ];
+
+impl Kind
+{
+  /* returns the weapon's name, as used in files */
+  pub fn name(&self) -> &'static str
+  {
+    match self
+    {
+      Kind::Classic => "classic",
+      Kind::Twin => "twin",
+      Kind::Spread => "spread",
+      Kind::Laser => "laser",
+      Kind::Charged => "charged"
+    }
+  }
+
+  /* returns the weapon with the given name, or None if there isn't one */
+  pub fn from_name(name: &str) -> Option<Kind>
+  {
+    KINDS.iter().find(|k| k.name() == name).cloned()
+  }
+
+  /* returns the weapon's name as shown on screen */
+  pub fn title(&self) -> &'static str
+  {
+    match self
+    {
+      Kind::Classic => "Classic",
+      Kind::Twin => "Twin cannon",
+      Kind::Spread => "Spread",
+      Kind::Laser => "Laser",
+      Kind::Charged => "Charged shot"
+    }
+  }
+
+  /* returns the weapon after this one, going back to the first after the last */
+  pub fn next(&self) -> Kind
+  {
+    let index = KINDS.iter().position(|k| k == self).unwrap_or(0);
+    KINDS[(index + 1) % KINDS.len()]
+  }
+
+  /* create one of these weapons
+   * => level = how upgraded it is, from 1 to MAX_LEVEL */
+  pub fn create(&self, level: u32) -> Box<dyn Weapon>
+  {
+    let level = level.max(1).min(MAX_LEVEL);
+    match self
+    {
+      Kind::Classic => Box::new(Classic { level: level }),
+      Kind::Twin => Box::new(Twin { level: level }),
+      Kind::Spread => Box::new(Spread { level: level }),
+      Kind::Laser => Box::new(Laser { level: level }),
+      Kind::Charged => Box::new(Charged { level: level, charge_ticks: 0 })
+    }
+  }
+}
+
+/* what a shot looks like */
+#[derive(Clone, Copy, PartialEq)]
+pub enum Look
+{
+  Ball, /* a sphere the size of its bullet */
+  Beam  /* a thin beam trailing down from its bullet */
+}
+
+/* a shot fired by the ship, and how it flies */
+pub struct Shot
+{
+  pub bullet: Bullet,  /* where the shot is: the tip of a beam */
+  drift: f32,          /* distance the shot moves sideways each tick */
+  pub damage: u32,     /* hits' worth of damage the shot does to whatever it hits */
+  pub piercing: bool,  /* true if the shot flies on through the aliens it hits */
+  pub look: Look,
+  length: f32          /* length of a beam */
+}
+
+impl Shot
+{
+  /* create a round shot
+   * => x, y = where to start the shot
+   *    radius, color = size and (r, g, b) color of the shot
+   *    speed = distance the shot climbs each tick
+   *    drift = distance the shot moves sideways each tick
+   *    damage = hits' worth of damage the shot does */
+  pub fn ball(x: f32, y: f32, radius: f32, color: (f32, f32, f32), speed: f32, drift: f32, damage: u32) -> Shot
+  {
+    Shot
+    {
+      bullet: Bullet::new(x, y, radius, color.0, color.1, color.2, speed),
+      drift: drift,
+      damage: damage,
+      piercing: false,
+      look: Look::Ball,
+      length: 0.0
+    }
+  }
+
+  /* create a beam, which goes through everything in its way
+   * => x, y = where to start the tip of the beam
+   *    width, length, color = size and (r, g, b) color of the beam
+   *    speed = distance the beam climbs each tick
+   *    damage = hits' worth of damage the beam does */
+  pub fn beam(x: f32, y: f32, width: f32, length: f32, color: (f32, f32, f32), speed: f32, damage: u32) -> Shot
+  {
+    Shot
+    {
+      bullet: Bullet::new(x, y, width / 2.0, color.0, color.1, color.2, speed),
+      drift: 0.0,
+      damage: damage,
+      piercing: true,
+      look: Look::Beam,
+      length: length
+    }
+  }
+
+  /* turn a bullet from before there were weapons into a classic shot */
+  pub fn plain(bullet: Bullet) -> Shot
+  {
+    Shot
+    {
+      bullet: bullet,
+      drift: 0.0,
+      damage: 1,
+      piercing: false,
+      look: Look::Ball,
+      length: 0.0
+    }
+  }
+
+  /* make the shot fly on through the aliens it hits. round shots turn cyan to show it */
+  pub fn pierce(&mut self)
+  {
+    self.piercing = true;
+    if self.look == Look::Ball
+    {
+      let (x, y, _) = self.bullet.get_coords();
+      let (r, g, b) = PIERCING_COLOR;
+      self.bullet = Bullet::new(x, y, self.bullet.get_radius(), r, g, b, self.bullet.get_speed());
+    }
+  }
+
+  /* returns a copy of the shot moving sideways by the given extra distance each tick */
+  pub fn fanned(&self, drift: f32) -> Shot
+  {
+    let (x, y, _) = self.bullet.get_coords();
+    let (r, g, b) = self.bullet.get_color();
+    Shot
+    {
+      bullet: Bullet::new(x, y, self.bullet.get_radius(), r, g, b, self.bullet.get_speed()),
+      drift: self.drift + drift,
+      damage: self.damage,
+      piercing: self.piercing,
+      look: self.look,
+      length: self.length
+    }
+  }
+
+  /* move the shot along one tick */
+  pub fn animate(&mut self)
+  {
+    self.bullet.animate();
+    self.bullet.slide(self.drift);
+  }
+
+  /* returns the length of a beam, or 0.0 for a round shot */
+  pub fn get_length(&self) -> f32
+  {
+    self.length
+  }
+
+  /* write the shot to a saved game */
+  pub fn save(&self, writer: &mut save::Writer)
+  {
+    writer.label("shot");
+    writer.value(self.drift);
+    writer.value(self.piercing);
+    self.bullet.save(writer);
+
+    writer.label("payload");
+    writer.value(self.damage);
+    writer.value(match self.look
+    {
+      Look::Ball => "ball",
+      Look::Beam => "beam"
+    });
+    writer.value(self.length);
+  }
+
+  /* recreate a shot from a saved game. files from before there were weapons leave
+   * out the payload, and only had classic bullets */
+  pub fn restore(reader: &mut save::Reader) -> Result<Shot, save::SaveError>
+  {
+    reader.label("shot")?;
+    let (drift, piercing) = (reader.value()?, reader.value()?);
+    let bullet = Bullet::restore(reader)?;
+
+    let (mut damage, mut look, mut length) = (1, Look::Ball, 0.0);
+    if reader.next_is("payload") == true
+    {
+      reader.label("payload")?;
+      damage = reader.value()?;
+      look = match reader.value::<String>()?.as_str()
+      {
+        "ball" => Look::Ball,
+        "beam" => Look::Beam,
+        other => return Err(reader.unexpected("shot look", other))
+      };
+      length = reader.value()?;
+    }
+
+    Ok(Shot
+    {
+      bullet: bullet,
+      drift: drift,
+      damage: damage,
+      piercing: piercing,
+      look: look,
+      length: length
+    })
+  }
+}
+
+/* ------------------------------------------------------------------------------ */
+
+/* what every weapon can do */
+pub trait Weapon
+{
+  /* returns which weapon this is */
+  fn kind(&self) -> Kind;
+
+  /* returns how upgraded the weapon is, from 1 to MAX_LEVEL */
+  fn level(&self) -> u32;
+
+  /* make the weapon more powerful
+   * <= true if it was upgraded, false if it's already at MAX_LEVEL */
+  fn upgrade(&mut self) -> bool;
+
+  /* returns the number of shots fired at once */
+  fn volley(&self) -> usize;
+
+  /* returns the most of the weapon's shots that can be in play at once */
+  fn max_live(&self) -> usize;
+
+  /* returns the ticks to wait after firing before the weapon can fire again */
+  fn reload(&self) -> u32
+  {
+    0
+  }
+
+  /* work the trigger for one tick
+   * => held = true if fire is held down
+   *    ready = true if the ship can fire this tick
+   *    x, y = where shots leave the ship
+   *    speed = distance a plain bullet climbs each tick
+   * <= the shots fired this tick, if any */
+  fn trigger(&mut self, held: bool, ready: bool, x: f32, y: f32, speed: f32) -> Vec<Shot>;
+
+  /* returns how far the weapon has charged up, from 0.0 to 1.0, for weapons that charge */
+  fn charge(&self) -> f32
+  {
+    0.0
+  }
+
+  /* returns ticks spent charging up, for saving, and sets them again when restoring.
+   * weapons that don't charge up ignore these */
+  fn charge_ticks(&self) -> u32
+  {
+    0
+  }
+
+  fn set_charge_ticks(&mut self, _ticks: u32)
+  {
+  }
+}
+
+/* upgrade a weapon's level, if it isn't already at MAX_LEVEL
+ * <= true if it was upgraded */
+fn raise(level: &mut u32) -> bool
+{
+  if *level >= MAX_LEVEL
+  {
+    return false;
+  }
+  *level = *level + 1;
+  return true;
+}
+
+/* one bullet at a time, as per the original game. upgrades allow a second bullet in
+ * play, and then make the bullets twice as damaging */
+struct Classic
+{
+  level: u32
+}
+
+impl Weapon for Classic
+{
+  fn kind(&self) -> Kind
+  {
+    Kind::Classic
+  }
+
+  fn level(&self) -> u32
+  {
+    self.level
+  }
+
+  fn upgrade(&mut self) -> bool
+  {
+    raise(&mut self.level)
+  }
+
+  fn volley(&self) -> usize
+  {
+    1
+  }
+
+  fn max_live(&self) -> usize
+  {
+    match self.level >= 2
+    {
+      true => 2,
+      false => 1
+    }
+  }
+
+  fn trigger(&mut self, held: bool, ready: bool, x: f32, y: f32, speed: f32) -> Vec<Shot>
+  {
+    if held == false || ready == false
+    {
+      return Vec::new();
+    }
+
+    let damage = match self.level >= 3
+    {
+      true => 2,
+      false => 1
+    };
+    vec![ Shot::ball(x, y, BULLET_RADIUS, BULLET_COLOR, speed, 0.0, damage) ]
+  }
+}
+
+/* two bullets side by side. upgrades make them faster */
+struct Twin
+{
+  level: u32
+}
+
+impl Weapon for Twin
+{
+  fn kind(&self) -> Kind
+  {
+    Kind::Twin
+  }
+
+  fn level(&self) -> u32
+  {
+    self.level
+  }
+
+  fn upgrade(&mut self) -> bool
+  {
+    raise(&mut self.level)
+  }
+
+  fn volley(&self) -> usize
+  {
+    2
+  }
+
+  fn max_live(&self) -> usize
+  {
+    2
+  }
+
+  fn trigger(&mut self, held: bool, ready: bool, x: f32, y: f32, speed: f32) -> Vec<Shot>
+  {
+    if held == false || ready == false
+    {
+      return Vec::new();
+    }
+
+    let speed = speed * (1.0 + (0.25 * (self.level - 1) as f32));
+    [ 0.0 - TWIN_GAP / 2.0, TWIN_GAP / 2.0 ].iter()
+      .map(|offset| Shot::ball(x + offset, y, BULLET_RADIUS, TWIN_COLOR, speed, 0.0, 1))
+      .collect()
+  }
+}
+
+/* a fan of three bullets, widened to five by the last upgrade. the bullets are only
+ * half as fast as the classic's */
+struct Spread
+{
+  level: u32
+}
+
+impl Weapon for Spread
+{
+  fn kind(&self) -> Kind
+  {
+    Kind::Spread
+  }
+
+  fn level(&self) -> u32
+  {
+    self.level
+  }
+
+  fn upgrade(&mut self) -> bool
+  {
+    raise(&mut self.level)
+  }
+
+  fn volley(&self) -> usize
+  {
+    match self.level >= 3
+    {
+      true => 5,
+      false => 3
+    }
+  }
+
+  fn max_live(&self) -> usize
+  {
+    let volleys = match self.level >= 2
+    {
+      true => 2,
+      false => 1
+    };
+    self.volley() * volleys
+  }
+
+  fn trigger(&mut self, held: bool, ready: bool, x: f32, y: f32, speed: f32) -> Vec<Shot>
+  {
+    if held == false || ready == false
+    {
+      return Vec::new();
+    }
+
+    let middle = (self.volley() / 2) as f32;
+    (0..self.volley()).map(|place| (place as f32 - middle) * SPREAD_FAN)
+                      .map(|drift| Shot::ball(x, y, BULLET_RADIUS, SPREAD_COLOR, speed * 0.5, drift, 1))
+                      .collect()
+  }
+}
+
+/* a fast beam that goes through everything in its way, but takes a while to recharge.
+ * upgrades make the beam longer, then twice as damaging */
+struct Laser
+{
+  level: u32
+}
+
+impl Weapon for Laser
+{
+  fn kind(&self) -> Kind
+  {
+    Kind::Laser
+  }
+
+  fn level(&self) -> u32
+  {
+    self.level
+  }
+
+  fn upgrade(&mut self) -> bool
+  {
+    raise(&mut self.level)
+  }
+
+  fn volley(&self) -> usize
+  {
+    1
+  }
+
+  fn max_live(&self) -> usize
+  {
+    1
+  }
+
+  fn reload(&self) -> u32
+  {
+    LASER_RELOAD_TICKS
+  }
+
+  fn trigger(&mut self, held: bool, ready: bool, x: f32, y: f32, speed: f32) -> Vec<Shot>
+  {
+    if held == false || ready == false
+    {
+      return Vec::new();
+    }
+
+    let length = LASER_LENGTH * (1.0 + (0.5 * (self.level.min(2) - 1) as f32));
+    let damage = match self.level >= 3
+    {
+      true => 2,
+      false => 1
+    };
+    vec![ Shot::beam(x, y, LASER_WIDTH, length, LASER_COLOR, speed * LASER_SPEEDUP, damage) ]
+  }
+}
+
+/* hold fire to charge up, and let go to fire. the longer it's held, the bigger and more
+ * damaging the shot, and a fully charged shot goes through the aliens it hits. each
+ * upgrade adds another step of charge */
+struct Charged
+{
+  level: u32,
+  charge_ticks: u32  /* ticks fire has been held down for */
+}
+
+impl Charged
+{
+  /* returns the steps of charge the weapon can build up */
+  fn steps(&self) -> u32
+  {
+    CHARGE_STEPS + self.level - 1
+  }
+}
+
+impl Weapon for Charged
+{
+  fn kind(&self) -> Kind
+  {
+    Kind::Charged
+  }
+
+  fn level(&self) -> u32
+  {
+    self.level
+  }
+
+  fn upgrade(&mut self) -> bool
+  {
+    raise(&mut self.level)
+  }
+
+  fn volley(&self) -> usize
+  {
+    1
+  }
+
+  fn max_live(&self) -> usize
+  {
+    1
+  }
+
+  fn trigger(&mut self, held: bool, ready: bool, x: f32, y: f32, speed: f32) -> Vec<Shot>
+  {
+    /* build up charge while fire is held, as long as there's room to fire */
+    if held == true
+    {
+      if ready == true
+      {
+        self.charge_ticks = (self.charge_ticks + 1).min(self.steps() * CHARGE_TICKS);
+      }
+      return Vec::new();
+    }
+
+    /* letting go fires whatever's been built up */
+    if self.charge_ticks == 0 || ready == false
+    {
+      self.charge_ticks = 0;
+      return Vec::new();
+    }
+
+    let steps = self.charge_ticks / CHARGE_TICKS;
+    self.charge_ticks = 0;
+    let mut shot = Shot::ball(x, y, BULLET_RADIUS + (CHARGE_RADIUS * steps as f32), CHARGE_COLOR, speed, 0.0, steps + 1);
+    shot.piercing = steps >= self.steps();
+    vec![ shot ]
+  }
+
+  fn charge(&self) -> f32
+  {
+    self.charge_ticks as f32 / (self.steps() * CHARGE_TICKS) as f32
+  }
+
+  fn charge_ticks(&self) -> u32
+  {
+    self.charge_ticks
+  }
+
+  fn set_charge_ticks(&mut self, ticks: u32)
+  {
+    self.charge_ticks = ticks.min(self.steps() * CHARGE_TICKS);
+  }
+}